@@ -2,20 +2,51 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Sample, SampleFormat};
 use hound::{WavSpec, WavWriter};
+use std::future::Future;
 use std::io::Cursor;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::{channel, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Size of the header `encode_wav` writes before the samples
+const WAV_HEADER_BYTES: usize = 44;
 
 pub struct AudioRecorder {
     command_tx: Option<Sender<AudioCommand>>,
     is_recording: Arc<AtomicBool>,
+    /// Leave the stream open between recordings, set by `warm_up`
+    keep_open: Arc<AtomicBool>,
+    /// Name of the input device to open, or empty for the system default
+    device: String,
+    /// Set when the open stream's device goes away, so the next recording
+    /// opens a new one
+    stream_lost: Arc<AtomicBool>,
+    /// Set when the device changed during a recording, so the stream is
+    /// reopened on the new one once it stops
+    reopen_pending: bool,
+    /// Counts opened streams, so a replaced stream's thread ending doesn't
+    /// clear the recording flag of the one after it
+    stream_generation: Arc<AtomicUsize>,
+}
+
+/// Options for one recording on the stream
+#[derive(Clone)]
+struct Session {
+    level_tx: Option<Sender<f32>>,
+    chunk_overlap_seconds: u32,
+    keep_audio: bool,
 }
 
 enum AudioCommand {
-    Stop(Sender<Result<(), String>>),
-    DrainChunk(Sender<Result<Vec<u8>, String>>),
+    /// Start capturing on a stream left open by an earlier recording or
+    /// `warm_up`
+    Start(Session),
+    /// Replies with the whole recording when it was kept
+    Stop(oneshot::Sender<Result<Option<Vec<u8>>, String>>),
+    DrainChunk(oneshot::Sender<Result<Vec<u8>, String>>),
 }
 
 // AudioRecorder is Send because it only holds connection to the thread
@@ -23,11 +54,95 @@ enum AudioCommand {
 unsafe impl Send for AudioRecorder {}
 unsafe impl Sync for AudioRecorder {}
 
+/// Level (RMS, 0.0 to 1.0) above which the microphone is taken to hear
+/// speech rather than room noise
+const SPEECH_LEVEL: f32 = 0.02;
+
+/// Energy-based voice activity detection, fed with the levels a recording
+/// reports, for stopping it after a pause
+pub struct SilenceDetector {
+    timeout: Duration,
+    /// When speech was last heard, once any has been
+    last_speech: Option<Instant>,
+}
+
+impl SilenceDetector {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_speech: None,
+        }
+    }
+
+    /// Note the level heard at `now`, returning whether the silence since
+    /// the last speech has lasted the timeout. Silence before anything is
+    /// said doesn't count, so a recording waits for the speaker to start.
+    pub fn hear(&mut self, level: f32, now: Instant) -> bool {
+        if level >= SPEECH_LEVEL {
+            self.last_speech = Some(now);
+            return false;
+        }
+        self.last_speech
+            .is_some_and(|spoke| now.duration_since(spoke) >= self.timeout)
+    }
+}
+
+/// Names of the connected input devices
+pub fn input_device_names() -> Vec<String> {
+    match cpal::default_host().input_devices() {
+        Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+        Err(e) => {
+            log!("Failed to list input devices: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// The input device called `name`, falling back to the system default when
+/// it's empty or no longer connected
+fn input_device(name: &str) -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    if !name.is_empty() {
+        let found = host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|device| device.name().is_ok_and(|n| n == name)));
+        if found.is_some() {
+            return found;
+        }
+        log!("Input device \"{}\" not found; using the default", name);
+    }
+    host.default_input_device()
+}
+
 impl AudioRecorder {
     pub fn new() -> Self {
         Self {
             command_tx: None,
             is_recording: Arc::new(AtomicBool::new(false)),
+            keep_open: Arc::new(AtomicBool::new(false)),
+            device: String::new(),
+            stream_lost: Arc::new(AtomicBool::new(false)),
+            reopen_pending: false,
+            stream_generation: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Record from the input device called `name`, or the system default if
+    /// it's empty. A stream open on another device is reopened now, or once
+    /// the recording in progress stops.
+    pub fn set_input_device(&mut self, name: &str) {
+        if self.device == name {
+            return;
+        }
+        self.device = name.to_string();
+        if self.is_recording.load(Ordering::SeqCst) {
+            self.reopen_pending = self.command_tx.is_some();
+            return;
+        }
+        // Dropping the sender ends the old stream's thread
+        if self.command_tx.take().is_some() && self.keep_open.load(Ordering::SeqCst) {
+            self.open_stream(None);
         }
     }
 
@@ -35,222 +150,357 @@ impl AudioRecorder {
         &mut self,
         level_tx: Option<Sender<f32>>,
         chunk_overlap_seconds: u32,
+        keep_audio: bool,
     ) -> Result<(), String> {
         if self.is_recording.load(Ordering::SeqCst) {
             return Ok(()); // Already recording
         }
+        let session = Session {
+            level_tx,
+            chunk_overlap_seconds,
+            keep_audio,
+        };
+
+        let reopen = std::mem::take(&mut self.reopen_pending);
+        if self.stream_lost.swap(false, Ordering::SeqCst) || reopen {
+            self.command_tx = None;
+        }
+        // Capture starts at once on a stream that's already open
+        if let Some(tx) = &self.command_tx {
+            if tx.send(AudioCommand::Start(session.clone())).is_ok() {
+                return Ok(());
+            }
+        }
+        self.open_stream(Some(session));
+        Ok(())
+    }
 
+    /// Open the microphone ahead of the first recording and keep it open
+    /// between recordings, so capture starts the moment one is asked for.
+    /// Nothing is kept while no recording is in progress.
+    pub fn warm_up(&mut self) {
+        self.keep_open.store(true, Ordering::SeqCst);
+        if self.command_tx.is_none() {
+            self.open_stream(None);
+        }
+    }
+
+    /// Close the microphone between recordings again, once any recording in
+    /// progress stops
+    pub fn cool_down(&mut self) {
+        self.keep_open.store(false, Ordering::SeqCst);
+        if !self.is_recording.load(Ordering::SeqCst) {
+            // Dropping the sender ends the stream's thread
+            self.command_tx = None;
+        }
+    }
+
+    /// Start the thread that owns the stream, capturing into `session` from
+    /// the start if given
+    fn open_stream(&mut self, session: Option<Session>) {
         let (cmd_tx, cmd_rx) = channel();
         self.command_tx = Some(cmd_tx);
-        self.is_recording.store(true, Ordering::SeqCst);
+        self.reopen_pending = false;
+        let generation = self.stream_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let current_generation = self.stream_generation.clone();
+        if session.is_some() {
+            self.is_recording.store(true, Ordering::SeqCst);
+        }
 
         let is_recording_clone = self.is_recording.clone();
-
-        // Spawn thread to handle audio stream
+        let keep_open = self.keep_open.clone();
+        let device_name = self.device.clone();
+        let stream_lost = self.stream_lost.clone();
+        stream_lost.store(false, Ordering::SeqCst);
+
+        // Spawn thread to handle audio stream. A panic in it stops this
+        // recording instead of the app.
+        let thread_flag = self.is_recording.clone();
         thread::spawn(move || {
-            let samples = Arc::new(Mutex::new(Vec::new()));
-            let samples_producer = samples.clone();
-            let is_recording_flag = is_recording_clone.clone();
-
-            let err_fn = |err| eprintln!("Audio stream error: {}", err);
-
-            let host = cpal::default_host();
-            let device = match host.default_input_device() {
-                Some(d) => d,
-                None => {
-                    eprintln!("No input device");
-                    return;
-                }
-            };
-
-            let config = match device.default_input_config() {
-                Ok(c) => c,
-                Err(e) => {
-                    eprintln!("Error getting config: {}", e);
-                    return;
-                }
-            };
-
-            let sample_rate = config.sample_rate().0;
-            let channels = config.channels() as usize;
-
-            // Prepare level sender
-            let level_tx_16 = level_tx.clone();
-            let level_tx_32 = level_tx.clone();
-
-            // Stream creation
-            let stream_res = match config.sample_format() {
-                SampleFormat::I16 => device.build_input_stream(
-                    &config.into(),
-                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                        if is_recording_flag.load(Ordering::SeqCst) {
-                            let mut s = samples_producer.lock().unwrap();
-                            let mut sum_sq = 0.0;
-                            let count = data.len();
-
-                            for chunk in data.chunks(channels) {
-                                // Mono mix
-                                let mono: i32 = chunk.iter().map(|&x| x as i32).sum();
-                                let val = (mono / channels as i32) as i16;
-                                s.push(val);
-
-                                // RMS calculation
-                                let norm = val as f32 / 32768.0;
-                                sum_sq += norm * norm;
-                            }
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+                let samples = Arc::new(Mutex::new(Vec::new()));
+                let samples_producer = samples.clone();
+                let is_recording_flag = is_recording_clone.clone();
+                // Swapped for each recording's level listener
+                let level_tx: Arc<Mutex<Option<Sender<f32>>>> = Arc::new(Mutex::new(
+                    session
+                        .as_ref()
+                        .and_then(|session| session.level_tx.clone()),
+                ));
+
+                let err_fn = move |err| {
+                    if let cpal::StreamError::DeviceNotAvailable = err {
+                        stream_lost.store(true, Ordering::SeqCst);
+                    }
+                    log!("Audio stream error: {}", err);
+                };
+
+                let device = match input_device(&device_name) {
+                    Some(d) => d,
+                    None => {
+                        log!("No input device");
+                        return;
+                    }
+                };
 
-                            if let Some(tx) = &level_tx_16 {
-                                if count > 0 {
-                                    let rms = (sum_sq * channels as f32 / count as f32).sqrt();
-                                    let _ = tx.send(rms);
+                let config = match device.default_input_config() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        log!("Error getting config: {}", e);
+                        return;
+                    }
+                };
+
+                let sample_rate = config.sample_rate().0;
+                let channels = config.channels() as usize;
+
+                // Prepare level sender
+                let level_tx_16 = level_tx.clone();
+                let level_tx_32 = level_tx.clone();
+
+                // Stream creation
+                let stream_res = match config.sample_format() {
+                    SampleFormat::I16 => device.build_input_stream(
+                        &config.into(),
+                        move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                            if is_recording_flag.load(Ordering::SeqCst) {
+                                let mut s = samples_producer.lock().unwrap();
+                                let mut sum_sq = 0.0;
+                                let count = data.len();
+
+                                for chunk in data.chunks(channels) {
+                                    // Mono mix
+                                    let mono: i32 = chunk.iter().map(|&x| x as i32).sum();
+                                    let val = (mono / channels as i32) as i16;
+                                    s.push(val);
+
+                                    // RMS calculation
+                                    let norm = val as f32 / 32768.0;
+                                    sum_sq += norm * norm;
                                 }
-                            }
-                        }
-                    },
-                    err_fn,
-                    None,
-                ),
-                SampleFormat::F32 => device.build_input_stream(
-                    &config.into(),
-                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                        if is_recording_flag.load(Ordering::SeqCst) {
-                            let mut s = samples_producer.lock().unwrap();
-                            let mut sum_sq = 0.0;
-                            let count = data.len();
-
-                            for chunk in data.chunks(channels) {
-                                let mono: f32 = chunk.iter().sum();
-                                let val = mono / channels as f32;
-                                s.push(Sample::from_sample(val));
-
-                                sum_sq += val * val;
-                            }
 
-                            if let Some(tx) = &level_tx_32 {
-                                if count > 0 {
-                                    let rms = (sum_sq * channels as f32 / count as f32).sqrt();
-                                    let _ = tx.send(rms);
+                                if let Some(tx) = &*level_tx_16.lock().unwrap() {
+                                    if count > 0 {
+                                        let rms = (sum_sq * channels as f32 / count as f32).sqrt();
+                                        let _ = tx.send(rms);
+                                    }
                                 }
                             }
-                        }
-                    },
-                    err_fn,
-                    None,
-                ),
-                _ => {
-                    eprintln!("Unsupported format"); // Should communicate back but simple log for now
-                    return;
-                }
-            };
-
-            if let Ok(stream) = stream_res {
-                if let Err(e) = stream.play() {
-                    eprintln!("Failed to play stream: {}", e);
-                    return;
-                }
+                        },
+                        err_fn,
+                        None,
+                    ),
+                    SampleFormat::F32 => device.build_input_stream(
+                        &config.into(),
+                        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                            if is_recording_flag.load(Ordering::SeqCst) {
+                                let mut s = samples_producer.lock().unwrap();
+                                let mut sum_sq = 0.0;
+                                let count = data.len();
+
+                                for chunk in data.chunks(channels) {
+                                    let mono: f32 = chunk.iter().sum();
+                                    let val = mono / channels as f32;
+                                    s.push(Sample::from_sample(val));
+
+                                    sum_sq += val * val;
+                                }
 
-                let overlap_samples = sample_rate as usize * chunk_overlap_seconds as usize;
-                let mut last_chunk_index: usize = 0;
-
-                // Wait for commands
-                while let Ok(command) = cmd_rx.recv() {
-                    match command {
-                        AudioCommand::DrainChunk(reply_tx) => {
-                            let mut buffer = samples.lock().unwrap();
-                            let chunk_end = buffer.len();
-                            if chunk_end <= last_chunk_index {
-                                let _ = reply_tx.send(Err("No new audio".to_string()));
-                                continue;
+                                if let Some(tx) = &*level_tx_32.lock().unwrap() {
+                                    if count > 0 {
+                                        let rms = (sum_sq * channels as f32 / count as f32).sqrt();
+                                        let _ = tx.send(rms);
+                                    }
+                                }
                             }
+                        },
+                        err_fn,
+                        None,
+                    ),
+                    _ => {
+                        log!("Unsupported format"); // Should communicate back but simple log for now
+                        return;
+                    }
+                };
 
-                            let chunk_start = last_chunk_index.saturating_sub(overlap_samples);
-                            let chunk_samples = buffer[chunk_start..chunk_end].to_vec();
+                if let Ok(stream) = stream_res {
+                    if let Err(e) = stream.play() {
+                        log!("Failed to play stream: {}", e);
+                        return;
+                    }
 
-                            if overlap_samples > 0 {
-                                let retain_start = chunk_end.saturating_sub(overlap_samples);
-                                let retained = buffer.split_off(retain_start);
-                                *buffer = retained;
-                                last_chunk_index = buffer.len();
-                            } else {
-                                buffer.clear();
+                    let overlap_of = |session: &Session| {
+                        sample_rate as usize * session.chunk_overlap_seconds as usize
+                    };
+                    let mut overlap_samples = session.as_ref().map_or(0, overlap_of);
+                    let mut keep_audio = session.as_ref().is_some_and(|s| s.keep_audio);
+                    let mut last_chunk_index: usize = 0;
+                    // The whole recording at 16 kHz, without chunk overlaps
+                    let mut archive: Vec<i16> = Vec::new();
+                    // Each chunk's 16 kHz samples, reused from chunk to chunk
+                    let mut chunk_samples: Vec<i16> = Vec::new();
+
+                    // Wait for commands
+                    while let Ok(command) = cmd_rx.recv() {
+                        match command {
+                            AudioCommand::Start(session) => {
+                                samples.lock().unwrap().clear();
                                 last_chunk_index = 0;
+                                overlap_samples = overlap_of(&session);
+                                keep_audio = session.keep_audio;
+                                *level_tx.lock().unwrap() = session.level_tx;
+                                is_recording_clone.store(true, Ordering::SeqCst);
                             }
+                            AudioCommand::DrainChunk(reply_tx) => {
+                                let mut buffer = samples.lock().unwrap();
+                                let chunk_end = buffer.len();
+                                if chunk_end <= last_chunk_index {
+                                    let _ = reply_tx.send(Err("No new audio".to_string()));
+                                    continue;
+                                }
 
-                            drop(buffer);
+                                let chunk_start = last_chunk_index.saturating_sub(overlap_samples);
+                                // Straight from the capture buffer, without
+                                // copying it at the device rate first
+                                resample_into(
+                                    &buffer[chunk_start..chunk_end],
+                                    sample_rate,
+                                    16000,
+                                    &mut chunk_samples,
+                                );
+                                if keep_audio {
+                                    archive.extend(resample(
+                                        &buffer[last_chunk_index..chunk_end],
+                                        sample_rate,
+                                        16000,
+                                    ));
+                                }
 
-                            let resampled = if sample_rate != 16000 {
-                                resample(&chunk_samples, sample_rate, 16000)
-                            } else {
-                                chunk_samples
-                            };
+                                if overlap_samples > 0 {
+                                    let retain_start = chunk_end.saturating_sub(overlap_samples);
+                                    let retained = buffer.split_off(retain_start);
+                                    *buffer = retained;
+                                    last_chunk_index = buffer.len();
+                                } else {
+                                    buffer.clear();
+                                    last_chunk_index = 0;
+                                }
 
-                            let wav_data = encode_wav(&resampled, 16000);
-                            let _ = reply_tx.send(wav_data);
-                        }
-                        AudioCommand::Stop(reply_tx) => {
-                            drop(stream); // Stops recording
-                            is_recording_clone.store(false, Ordering::SeqCst);
-                            let _ = reply_tx.send(Ok(()));
-                            break;
+                                drop(buffer);
+
+                                let wav_data = encode_wav(&chunk_samples, 16000);
+                                let _ = reply_tx.send(wav_data);
+                            }
+                            AudioCommand::Stop(reply_tx) => {
+                                is_recording_clone.store(false, Ordering::SeqCst);
+                                *level_tx.lock().unwrap() = None;
+                                let kept = if keep_audio {
+                                    let buffer = samples.lock().unwrap();
+                                    let start = last_chunk_index.min(buffer.len());
+                                    archive.extend(resample(&buffer[start..], sample_rate, 16000));
+                                    encode_wav(&archive, 16000).map(Some)
+                                } else {
+                                    Ok(None)
+                                };
+                                let _ = reply_tx.send(kept);
+                                if !keep_open.load(Ordering::SeqCst) {
+                                    break; // Dropping the stream stops it
+                                }
+                                // Between recordings nothing is held
+                                samples.lock().unwrap().clear();
+                                archive = Vec::new();
+                            }
                         }
                     }
+                } else {
+                    log!("Failed to build stream: {:?}", stream_res.err());
                 }
-            } else {
-                eprintln!("Failed to build stream: {:?}", stream_res.err());
+            }));
+            if result.is_err() {
+                log!("Audio thread panicked; recording stopped");
+                crate::crash::discard_recovered();
+            }
+            // However the thread ends, let the next recording start afresh,
+            // unless a newer stream has already taken over
+            if current_generation.load(Ordering::SeqCst) == generation {
+                thread_flag.store(false, Ordering::SeqCst);
             }
         });
-
-        Ok(())
     }
 
-    pub fn drain_chunk(&mut self) -> Result<Vec<u8>, String> {
-        if let Some(tx) = &self.command_tx {
-            let (reply_tx, reply_rx) = channel();
-
-            tx.send(AudioCommand::DrainChunk(reply_tx))
-                .map_err(|_| "Failed to send chunk command".to_string())?;
-
-            match reply_rx.recv() {
-                Ok(res) => res,
-                Err(_) => Err("Failed to receive audio chunk".to_string()),
-            }
-        } else {
-            Err("Not recording".to_string())
+    /// Take the audio since the last chunk, as WAV. Only sending the
+    /// request needs the recorder, so callers can let go of it before
+    /// awaiting the chunk.
+    pub fn drain_chunk(&self) -> impl Future<Output = Result<Vec<u8>, String>> + Send + 'static {
+        let reply = self.request(AudioCommand::DrainChunk);
+        async move {
+            reply?
+                .await
+                .map_err(|_| "Failed to receive audio chunk".to_string())?
         }
     }
 
-    pub fn stop_recording(&mut self) -> Result<(), String> {
-        if let Some(tx) = self.command_tx.take() {
-            let (reply_tx, reply_rx) = channel();
-
-            tx.send(AudioCommand::Stop(reply_tx))
-                .map_err(|_| "Failed to send stop command".to_string())?;
-
-            match reply_rx.recv() {
-                Ok(res) => res,
-                Err(_) => Err("Failed to stop recording".to_string()),
-            }
-        } else {
-            Err("Not recording".to_string())
+    /// Stop recording, returning the whole recording as WAV if it was kept.
+    /// A warmed-up stream stays open for the next recording.
+    pub fn stop_recording(
+        &mut self,
+    ) -> impl Future<Output = Result<Option<Vec<u8>>, String>> + Send + 'static {
+        let reply = self.request(AudioCommand::Stop);
+        if !self.keep_open.load(Ordering::SeqCst) {
+            self.command_tx = None;
+        } else if self.reopen_pending {
+            // The old stream finishes this recording, then the device
+            // chosen meanwhile is opened for the next
+            self.command_tx = None;
+            self.open_stream(None);
+        }
+        async move {
+            reply?
+                .await
+                .map_err(|_| "Failed to stop recording".to_string())?
         }
     }
+
+    /// Send the recording thread `command`, returning where its reply will
+    /// arrive
+    fn request<T>(
+        &self,
+        command: fn(oneshot::Sender<T>) -> AudioCommand,
+    ) -> Result<oneshot::Receiver<T>, String> {
+        let tx = self.command_tx.as_ref().ok_or("Not recording")?;
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(command(reply_tx))
+            .map_err(|_| "The recording thread has stopped".to_string())?;
+        Ok(reply_rx)
+    }
+}
+
+pub(crate) fn resample(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
+    let mut resampled = Vec::new();
+    resample_into(samples, from_rate, to_rate, &mut resampled);
+    resampled
 }
 
-fn resample(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
+/// `resample` into `out`, replacing what it held but keeping its allocation
+fn resample_into(samples: &[i16], from_rate: u32, to_rate: u32, out: &mut Vec<i16>) {
+    out.clear();
+    if from_rate == to_rate {
+        out.extend_from_slice(samples);
+        return;
+    }
     let ratio = from_rate as f64 / to_rate as f64;
     let new_len = (samples.len() as f64 / ratio) as usize;
-    let mut resampled = Vec::with_capacity(new_len);
+    out.reserve(new_len);
 
     for i in 0..new_len {
         let src_idx = (i as f64 * ratio) as usize;
         if src_idx < samples.len() {
-            resampled.push(samples[src_idx]);
+            out.push(samples[src_idx]);
         }
     }
-    resampled
 }
 
-fn encode_wav(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>, String> {
+pub(crate) fn encode_wav(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>, String> {
     let spec = WavSpec {
         channels: 1,
         sample_rate,
@@ -258,7 +508,10 @@ fn encode_wav(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>, String> {
         sample_format: hound::SampleFormat::Int,
     };
 
-    let mut cursor = Cursor::new(Vec::new());
+    // Sized up front so the WAV is written without growing
+    let mut cursor = Cursor::new(Vec::with_capacity(
+        WAV_HEADER_BYTES + samples.len() * std::mem::size_of::<i16>(),
+    ));
     {
         let mut writer = WavWriter::new(&mut cursor, spec)
             .map_err(|e| format!("Failed to create WAV writer: {}", e))?;
@@ -275,3 +528,27 @@ fn encode_wav(samples: &[i16], sample_rate: u32) -> Result<Vec<u8>, String> {
 
     Ok(cursor.into_inner())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silence_after_speech_stops() {
+        let start = Instant::now();
+        let mut detector = SilenceDetector::new(Duration::from_secs(3));
+        assert!(!detector.hear(0.2, start));
+        assert!(!detector.hear(0.001, start + Duration::from_secs(2)));
+        assert!(!detector.hear(0.1, start + Duration::from_secs(2)));
+        assert!(!detector.hear(0.001, start + Duration::from_secs(4)));
+        assert!(detector.hear(0.001, start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn silence_before_speech_keeps_waiting() {
+        let start = Instant::now();
+        let mut detector = SilenceDetector::new(Duration::from_secs(3));
+        assert!(!detector.hear(0.001, start));
+        assert!(!detector.hear(0.001, start + Duration::from_secs(10)));
+    }
+}
@@ -0,0 +1,155 @@
+//! Decoding audio files (voice memos, exported recordings) into the same
+//! WAV chunks a live recording produces, so they share its transcription path
+
+use crate::audio;
+use std::ops::Range;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Rate chunks are sent to the server at, as for recordings
+const SAMPLE_RATE: u32 = 16000;
+
+/// Extensions of the files that can be transcribed
+pub const EXTENSIONS: &[&str] = &["wav", "mp3", "m4a", "aac", "flac", "ogg"];
+
+/// Whether `path` looks like an audio file that can be transcribed
+pub fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            EXTENSIONS
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(ext))
+        })
+}
+
+/// Decode `path` to WAV chunks of `chunk_seconds`, each overlapping the one
+/// before by `overlap_seconds`
+pub fn decode_chunks(
+    path: &Path,
+    chunk_seconds: u32,
+    overlap_seconds: u32,
+) -> Result<Vec<Vec<u8>>, String> {
+    let samples = decode(path)?;
+    if samples.is_empty() {
+        return Err(format!("{} has no audio", path.display()));
+    }
+    chunk_ranges(
+        samples.len(),
+        (chunk_seconds * SAMPLE_RATE) as usize,
+        (overlap_seconds * SAMPLE_RATE) as usize,
+    )
+    .into_iter()
+    .map(|range| audio::encode_wav(&samples[range], SAMPLE_RATE))
+    .collect()
+}
+
+/// Mono 16 kHz samples of the first audio track in `path`
+fn decode(path: &Path) -> Result<Vec<i16>, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Unsupported audio file {}: {}", path.display(), e))?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| format!("{} has no audio track", path.display()))?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported audio codec in {}: {}", path.display(), e))?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // Skip a damaged packet, as players do
+            Err(Error::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Failed to decode {}: {}", path.display(), e)),
+        };
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        let mut buffer = SampleBuffer::<i16>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend(downmix(buffer.samples(), spec.channels.count()));
+    }
+
+    if sample_rate == 0 {
+        return Err(format!("{} has no audio", path.display()));
+    }
+    Ok(audio::resample(&samples, sample_rate, SAMPLE_RATE))
+}
+
+/// Average interleaved `channels` into one
+fn downmix(samples: &[i16], channels: usize) -> Vec<i16> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(channels)
+        .map(|frame| (frame.iter().map(|&s| s as i32).sum::<i32>() / frame.len() as i32) as i16)
+        .collect()
+}
+
+/// Sample ranges of `chunk` samples covering `len`, each starting `overlap`
+/// before the previous one ends
+fn chunk_ranges(len: usize, chunk: usize, overlap: usize) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + chunk).min(len);
+        ranges.push(start..end);
+        if end == len || chunk <= overlap {
+            return ranges;
+        }
+        start = end - overlap;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_overlap_and_cover_the_whole_file() {
+        assert_eq!(chunk_ranges(25, 10, 2), vec![0..10, 8..18, 16..25]);
+        assert_eq!(chunk_ranges(10, 10, 2), vec![0..10]);
+        assert_eq!(chunk_ranges(4, 10, 2), vec![0..4]);
+    }
+
+    #[test]
+    fn stereo_is_averaged_to_mono() {
+        assert_eq!(downmix(&[100, 300, -50, 50], 2), vec![200, 0]);
+        assert_eq!(downmix(&[1, 2, 3], 1), vec![1, 2, 3]);
+        assert!(is_supported(Path::new("memo.M4A")));
+        assert!(!is_supported(Path::new("notes.txt")));
+    }
+}
@@ -0,0 +1,277 @@
+//! Local HTTP control API, so AutoHotkey scripts, Stream Decks, and other
+//! tools can drive dictation. Every request needs the configured token.
+//!
+//! - `GET /state`: `{ status, error, recording, paused }`
+//! - `GET /last-transcript`: `{ text, timestamp }` of the latest history entry
+//! - `POST /start`, `/stop`, `/toggle`, `/cancel`: control the recording,
+//!   answering `{ ok, error }`
+
+use crate::settings::AutomationApi;
+use crate::{AppState, TrayStatus};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request head read before giving up on a client
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+/// How long a client gets to send its request
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// Pause after a failed accept before trying again
+const ACCEPT_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// The parts of a request the API looks at
+#[derive(Debug, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    State,
+    LastTranscript,
+    Start,
+    Stop,
+    Toggle,
+    Cancel,
+}
+
+fn parse_request(head: &str) -> Option<Request> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.split('?').next()?.to_string();
+    let token = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.trim().strip_prefix("Bearer "))
+        .map(|token| token.trim().to_string());
+    Some(Request {
+        method,
+        path,
+        token,
+    })
+}
+
+/// The action for `method` and `path`, or the HTTP status to refuse with
+fn route(method: &str, path: &str) -> Result<Action, u16> {
+    let (action, expected) = match path.trim_end_matches('/') {
+        "/state" => (Action::State, "GET"),
+        "/last-transcript" => (Action::LastTranscript, "GET"),
+        "/start" => (Action::Start, "POST"),
+        "/stop" => (Action::Stop, "POST"),
+        "/toggle" => (Action::Toggle, "POST"),
+        "/cancel" => (Action::Cancel, "POST"),
+        _ => return Err(404),
+    };
+    if method != expected {
+        return Err(405);
+    }
+    Ok(action)
+}
+
+/// Serve `config`, stopping any server already running. Does nothing more
+/// when the API is off.
+pub fn restart(app: &AppHandle, config: &AutomationApi) {
+    let state = app.state::<AppState>();
+    if let Some(server) = state.automation_server.lock().unwrap().take() {
+        server.abort();
+    }
+    if !config.enabled || config.token.is_empty() {
+        return;
+    }
+
+    let app_handle = app.clone();
+    let config = config.clone();
+    let server = tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::bind(("127.0.0.1", config.port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                log!(
+                    "Failed to start automation API on port {}: {}",
+                    config.port,
+                    e
+                );
+                return;
+            }
+        };
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    // Errors like running out of file handles repeat until
+                    // something is freed, so don't spin on them
+                    log!("Automation API failed to accept a connection: {}", e);
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                }
+            };
+            let app = app_handle.clone();
+            let token = config.token.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = serve(&app, stream, &token).await {
+                    log!("Automation API request failed: {}", e);
+                }
+            });
+        }
+    });
+    *state.automation_server.lock().unwrap() = Some(server);
+}
+
+async fn serve(app: &AppHandle, mut stream: TcpStream, token: &str) -> std::io::Result<()> {
+    let head = match tokio::time::timeout(REQUEST_TIMEOUT, read_head(&mut stream)).await {
+        Ok(head) => head?,
+        Err(_) => return Ok(()),
+    };
+    let (status, body) = match head.as_deref().and_then(parse_request) {
+        None => (400, json!({ "error": "Malformed request" })),
+        Some(request) if !token_matches(request.token.as_deref(), token) => {
+            (401, json!({ "error": "Missing or wrong token" }))
+        }
+        Some(request) => match route(&request.method, &request.path) {
+            Ok(action) => (200, perform(app, action).await),
+            Err(404) => (404, json!({ "error": "No such endpoint" })),
+            Err(status) => (status, json!({ "error": "Wrong method for this endpoint" })),
+        },
+    };
+    respond(&mut stream, status, &body).await
+}
+
+/// Whether a request's token is the configured one, compared in a time that
+/// doesn't depend on where they differ
+fn token_matches(given: Option<&str>, expected: &str) -> bool {
+    let Some(given) = given else {
+        return false;
+    };
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// The request line and headers, or `None` when they're too long
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+        if buffer.len() > MAX_REQUEST_BYTES {
+            return Ok(None);
+        }
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    Ok(Some(String::from_utf8_lossy(&buffer).into_owned()))
+}
+
+/// Carry out `action`. Stopping answers once the transcript has been sent
+/// on.
+async fn perform(app: &AppHandle, action: Action) -> Value {
+    let state = app.state::<AppState>();
+    match action {
+        Action::State => {
+            let (status, error) = match state.tray_status.lock().unwrap().clone() {
+                TrayStatus::Idle => ("idle", None),
+                TrayStatus::Recording => ("recording", None),
+                TrayStatus::Processing => ("processing", None),
+                TrayStatus::Error(message) => ("error", Some(message)),
+            };
+            json!({
+                "status": status,
+                "error": error,
+                "recording": *state.is_recording.lock().unwrap(),
+                "paused": crate::paused_reason(&state).is_some(),
+            })
+        }
+        Action::LastTranscript => {
+            let latest = crate::with_history(app, |history| history.latest())
+                .await
+                .ok()
+                .flatten();
+            json!({
+                "text": latest.as_ref().map(|entry| &entry.processed_text),
+                "timestamp": latest.as_ref().map(|entry| &entry.timestamp),
+            })
+        }
+        Action::Start => outcome(crate::start_recording(app)),
+        Action::Stop => outcome(crate::stop_recording(app.clone()).await),
+        Action::Toggle if *state.is_recording.lock().unwrap() => {
+            outcome(crate::stop_recording(app.clone()).await)
+        }
+        Action::Toggle => outcome(crate::start_recording(app)),
+        Action::Cancel => outcome(crate::discard_recording(app.clone()).await),
+    }
+}
+
+fn outcome(result: Result<(), String>) -> Value {
+    match result {
+        Ok(()) => json!({ "ok": true }),
+        Err(error) => json!({ "ok": false, "error": error }),
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: u16, body: &Value) -> std::io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Error",
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_are_parsed_with_their_bearer_token() {
+        let request =
+            parse_request("POST /toggle?source=deck HTTP/1.1\r\nHost: localhost\r\nauthorization: Bearer abc123 \r\n\r\n")
+                .unwrap();
+        assert_eq!(
+            request,
+            Request {
+                method: "POST".to_string(),
+                path: "/toggle".to_string(),
+                token: Some("abc123".to_string()),
+            }
+        );
+        let request = parse_request("GET /state HTTP/1.1\r\nX-API-Key: abc123\r\n\r\n").unwrap();
+        assert_eq!(request.token, None);
+        assert_eq!(parse_request(""), None);
+    }
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(token_matches(Some("abc123"), "abc123"));
+        assert!(!token_matches(Some("abc124"), "abc123"));
+        assert!(!token_matches(Some("abc1234"), "abc123"));
+        assert!(!token_matches(None, "abc123"));
+    }
+
+    #[test]
+    fn routes_check_the_method() {
+        assert_eq!(route("GET", "/state"), Ok(Action::State));
+        assert_eq!(route("POST", "/cancel/"), Ok(Action::Cancel));
+        assert_eq!(route("GET", "/start"), Err(405));
+        assert_eq!(route("POST", "/shutdown"), Err(404));
+    }
+}
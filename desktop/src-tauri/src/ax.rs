@@ -0,0 +1,245 @@
+//! macOS Accessibility helpers, the counterpart of `uia` for the focused
+//! control. Needs the app to be allowed under Privacy & Security >
+//! Accessibility; without that every lookup comes back empty.
+
+/// Screen position of the bottom-left corner of the caret in the focused
+/// control, in physical pixels
+#[cfg(target_os = "macos")]
+pub fn caret_position() -> Option<(i32, i32)> {
+    use ffi::*;
+
+    unsafe {
+        let system = AXUIElementCreateSystemWide();
+        if system.is_null() {
+            return None;
+        }
+        let focused = copy_attribute(system, "AXFocusedUIElement");
+        CFRelease(system);
+        let focused = focused?;
+        let range = copy_attribute(focused, "AXSelectedTextRange");
+        let bounds = range.and_then(|range| {
+            let bounds = copy_parameterized_attribute(focused, "AXBoundsForRange", range);
+            CFRelease(range);
+            bounds
+        });
+        CFRelease(focused);
+        let bounds = bounds?;
+
+        let mut rect = CGRect::default();
+        let read = AXValueGetValue(
+            bounds,
+            K_AX_VALUE_CG_RECT_TYPE,
+            &mut rect as *mut CGRect as *mut std::ffi::c_void,
+        );
+        CFRelease(bounds);
+        if !read {
+            return None;
+        }
+        // Accessibility reports points; the popup is placed in pixels
+        let bottom_left = CGPoint {
+            x: rect.origin.x,
+            y: rect.origin.y + rect.size.height,
+        };
+        let scale = display_scale(bottom_left);
+        Some((
+            (bottom_left.x * scale).round() as i32,
+            (bottom_left.y * scale).round() as i32,
+        ))
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn caret_position() -> Option<(i32, i32)> {
+    None
+}
+
+/// Process id and lowercased name of the app with keyboard focus
+#[cfg(target_os = "macos")]
+pub fn focused_app() -> Option<(i32, Option<String>)> {
+    use ffi::*;
+
+    unsafe {
+        let system = AXUIElementCreateSystemWide();
+        if system.is_null() {
+            return None;
+        }
+        let app = copy_attribute(system, "AXFocusedApplication");
+        CFRelease(system);
+        let app = app?;
+        let mut pid = 0;
+        let error = AXUIElementGetPid(app, &mut pid);
+        CFRelease(app);
+        if error != K_AX_ERROR_SUCCESS || pid <= 0 {
+            return None;
+        }
+
+        let mut buffer = [0u8; 256];
+        let len = proc_name(pid, buffer.as_mut_ptr().cast(), buffer.len() as u32);
+        let name =
+            (len > 0).then(|| String::from_utf8_lossy(&buffer[..len as usize]).to_lowercase());
+        Some((pid, name))
+    }
+}
+
+/// Bring app `pid` to the front, returning whether it was accepted
+#[cfg(target_os = "macos")]
+pub fn activate_app(pid: i32) -> bool {
+    use ffi::*;
+
+    unsafe {
+        let app = AXUIElementCreateApplication(pid);
+        if app.is_null() {
+            return false;
+        }
+        let Some(name) = cf_string("AXFrontmost") else {
+            CFRelease(app);
+            return false;
+        };
+        let error = AXUIElementSetAttributeValue(app, name, kCFBooleanTrue);
+        CFRelease(name);
+        CFRelease(app);
+        error == K_AX_ERROR_SUCCESS
+    }
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn copy_attribute(element: ffi::CFTypeRef, name: &str) -> Option<ffi::CFTypeRef> {
+    let name = ffi::cf_string(name)?;
+    let mut value = std::ptr::null();
+    let error = ffi::AXUIElementCopyAttributeValue(element, name, &mut value);
+    ffi::CFRelease(name);
+    (error == ffi::K_AX_ERROR_SUCCESS && !value.is_null()).then_some(value)
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn copy_parameterized_attribute(
+    element: ffi::CFTypeRef,
+    name: &str,
+    parameter: ffi::CFTypeRef,
+) -> Option<ffi::CFTypeRef> {
+    let name = ffi::cf_string(name)?;
+    let mut value = std::ptr::null();
+    let error =
+        ffi::AXUIElementCopyParameterizedAttributeValue(element, name, parameter, &mut value);
+    ffi::CFRelease(name);
+    (error == ffi::K_AX_ERROR_SUCCESS && !value.is_null()).then_some(value)
+}
+
+/// Pixels per point on the display showing `point`
+#[cfg(target_os = "macos")]
+unsafe fn display_scale(point: ffi::CGPoint) -> f64 {
+    let mut display = 0u32;
+    let mut count = 0u32;
+    if ffi::CGGetDisplaysWithPoint(point, 1, &mut display, &mut count) != 0 || count == 0 {
+        display = ffi::CGMainDisplayID();
+    }
+    let mode = ffi::CGDisplayCopyDisplayMode(display);
+    if mode.is_null() {
+        return 1.0;
+    }
+    let points = ffi::CGDisplayModeGetWidth(mode);
+    let pixels = ffi::CGDisplayModeGetPixelWidth(mode);
+    ffi::CGDisplayModeRelease(mode);
+    if points == 0 {
+        1.0
+    } else {
+        pixels as f64 / points as f64
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[allow(non_snake_case)]
+mod ffi {
+    use std::ffi::{c_char, c_void, CString};
+
+    pub type CFTypeRef = *const c_void;
+
+    pub const K_AX_ERROR_SUCCESS: i32 = 0;
+    pub const K_AX_VALUE_CG_RECT_TYPE: u32 = 3;
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+
+    #[repr(C)]
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct CGPoint {
+        pub x: f64,
+        pub y: f64,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct CGSize {
+        pub width: f64,
+        pub height: f64,
+    }
+
+    #[repr(C)]
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct CGRect {
+        pub origin: CGPoint,
+        pub size: CGSize,
+    }
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        pub fn AXUIElementCreateSystemWide() -> CFTypeRef;
+        pub fn AXUIElementCreateApplication(pid: i32) -> CFTypeRef;
+        pub fn AXUIElementGetPid(element: CFTypeRef, pid: *mut i32) -> i32;
+        pub fn AXUIElementSetAttributeValue(
+            element: CFTypeRef,
+            attribute: CFTypeRef,
+            value: CFTypeRef,
+        ) -> i32;
+        pub fn AXUIElementCopyAttributeValue(
+            element: CFTypeRef,
+            attribute: CFTypeRef,
+            value: *mut CFTypeRef,
+        ) -> i32;
+        pub fn AXUIElementCopyParameterizedAttributeValue(
+            element: CFTypeRef,
+            attribute: CFTypeRef,
+            parameter: CFTypeRef,
+            result: *mut CFTypeRef,
+        ) -> i32;
+        pub fn AXValueGetValue(value: CFTypeRef, value_type: u32, value_ptr: *mut c_void) -> bool;
+    }
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            allocator: CFTypeRef,
+            c_str: *const c_char,
+            encoding: u32,
+        ) -> CFTypeRef;
+        pub fn CFRelease(cf: CFTypeRef);
+        #[allow(non_upper_case_globals)]
+        pub static kCFBooleanTrue: CFTypeRef;
+    }
+
+    // libproc, part of libSystem
+    extern "C" {
+        pub fn proc_name(pid: i32, buffer: *mut c_void, size: u32) -> i32;
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        pub fn CGMainDisplayID() -> u32;
+        pub fn CGGetDisplaysWithPoint(
+            point: CGPoint,
+            max_displays: u32,
+            displays: *mut u32,
+            matching_count: *mut u32,
+        ) -> i32;
+        pub fn CGDisplayCopyDisplayMode(display: u32) -> *mut c_void;
+        pub fn CGDisplayModeGetWidth(mode: *mut c_void) -> usize;
+        pub fn CGDisplayModeGetPixelWidth(mode: *mut c_void) -> usize;
+        pub fn CGDisplayModeRelease(mode: *mut c_void);
+    }
+
+    /// A new CFString for `text`, which the caller releases
+    pub unsafe fn cf_string(text: &str) -> Option<CFTypeRef> {
+        let text = CString::new(text).ok()?;
+        let string =
+            CFStringCreateWithCString(std::ptr::null(), text.as_ptr(), K_CF_STRING_ENCODING_UTF8);
+        (!string.is_null()).then_some(string)
+    }
+}
@@ -0,0 +1,200 @@
+//! Transcribing every audio file in a folder, a few at a time, into a text
+//! or subtitle file next to each one
+
+use crate::settings::SubtitleFormat;
+use crate::{audio_file, subtitles, AppState};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Files transcribed at once
+const CONCURRENCY: usize = 3;
+
+/// Where one file in a batch has got to, sent as a `batch-progress` event
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProgress {
+    pub path: String,
+    pub status: BatchStatus,
+    pub error: Option<String>,
+    /// Files finished when this was sent, whether done, failed, or skipped
+    pub completed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Started,
+    Done,
+    Failed,
+    /// Already has a transcript from an earlier run
+    Skipped,
+}
+
+/// Totals for a finished batch
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct BatchSummary {
+    pub done: usize,
+    pub failed: usize,
+    pub skipped: usize,
+}
+
+/// Audio files in `folder` and its subfolders, in path order
+pub fn audio_files(folder: &Path) -> Result<Vec<PathBuf>, String> {
+    if !folder.is_dir() {
+        return Err(format!("{} isn't a folder", folder.display()));
+    }
+    let mut files = Vec::new();
+    let mut pending = vec![folder.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if audio_file::is_supported(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The transcript file written next to `audio`, plain text or subtitles
+pub fn sidecar_path(audio: &Path, format: Option<SubtitleFormat>) -> PathBuf {
+    audio.with_extension(format.map_or("txt", SubtitleFormat::extension))
+}
+
+/// Transcribe every audio file under `folder` that has no transcript yet,
+/// reporting each one's progress as it goes
+pub(crate) async fn transcribe_folder(
+    app: AppHandle,
+    folder: PathBuf,
+) -> Result<BatchSummary, String> {
+    let files = audio_files(&folder)?;
+    if files.is_empty() {
+        return Err(format!("No audio files in {}", folder.display()));
+    }
+    let settings = Arc::new(
+        app.state::<AppState>()
+            .settings
+            .lock()
+            .unwrap()
+            .with_active_profile(),
+    );
+    let format = settings.subtitle_format;
+    let total = files.len();
+    let finished = Arc::new(AtomicUsize::new(0));
+    let mut summary = BatchSummary::default();
+    let progress = |path: &Path, status, error| {
+        let completed = finished.load(Ordering::SeqCst);
+        emit_progress(&app, path, status, error, completed, total);
+    };
+
+    let semaphore = Arc::new(Semaphore::new(CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for path in files {
+        if sidecar_path(&path, format).exists() {
+            summary.skipped += 1;
+            finished.fetch_add(1, Ordering::SeqCst);
+            progress(&path, BatchStatus::Skipped, None);
+            continue;
+        }
+        let semaphore = semaphore.clone();
+        let settings = settings.clone();
+        let finished = finished.clone();
+        let app = app.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let completed = finished.load(Ordering::SeqCst);
+            emit_progress(&app, &path, BatchStatus::Started, None, completed, total);
+            let transcript = match format {
+                Some(format) => crate::transcribe_audio_file_cues(&path, &settings)
+                    .await
+                    .map(|cues| subtitles::render(format, &cues)),
+                None => crate::transcribe_audio_file(&path, &settings).await,
+            };
+            let sidecar = sidecar_path(&path, format);
+            let result = transcript.and_then(|transcript| {
+                std::fs::write(&sidecar, transcript)
+                    .map_err(|e| format!("Failed to write {}: {}", sidecar.display(), e))
+            });
+            (path, result)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        finished.fetch_add(1, Ordering::SeqCst);
+        let Ok((path, result)) = joined else {
+            summary.failed += 1;
+            continue;
+        };
+        match result {
+            Ok(()) => {
+                summary.done += 1;
+                progress(&path, BatchStatus::Done, None);
+            }
+            Err(e) => {
+                log!("Failed to transcribe {}: {}", path.display(), e);
+                summary.failed += 1;
+                progress(&path, BatchStatus::Failed, Some(e));
+            }
+        }
+    }
+    Ok(summary)
+}
+
+fn emit_progress(
+    app: &AppHandle,
+    path: &Path,
+    status: BatchStatus,
+    error: Option<String>,
+    completed: usize,
+    total: usize,
+) {
+    let _ = app.emit(
+        "batch-progress",
+        BatchProgress {
+            path: path.display().to_string(),
+            status,
+            error,
+            completed,
+            total,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_audio_files_in_subfolders() {
+        let folder = std::env::temp_dir().join(format!("ww-batch-{}", std::process::id()));
+        std::fs::create_dir_all(folder.join("older")).unwrap();
+        for name in ["b.mp3", "a.wav", "notes.txt", "older/c.m4a"] {
+            std::fs::write(folder.join(name), b"").unwrap();
+        }
+        let files = audio_files(&folder).unwrap();
+        let _ = std::fs::remove_dir_all(&folder);
+
+        assert_eq!(
+            files,
+            vec![
+                folder.join("a.wav"),
+                folder.join("b.mp3"),
+                folder.join("older").join("c.m4a"),
+            ]
+        );
+        assert_eq!(sidecar_path(&files[1], None), folder.join("b.txt"));
+        assert_eq!(
+            sidecar_path(&files[1], Some(SubtitleFormat::Vtt)),
+            folder.join("b.vtt")
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Command-line switches for scripts and shortcuts, e.g.
+//! `windows-whisper --toggle` or `windows-whisper --transcribe memo.wav`
+
+use std::path::PathBuf;
+
+/// What the command line asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    /// Transcribe an audio file and print the transcript to stdout
+    Transcribe(PathBuf),
+    /// Start recording unless already recording
+    Start,
+    /// Stop the recording in progress, if any
+    Stop,
+    /// Start or stop, like pressing the hotkey
+    Toggle,
+}
+
+pub const USAGE: &str =
+    "Usage: windows-whisper [--transcribe <audio file> | --start | --stop | --toggle] [--portable]";
+
+/// The command in `args` (without the program name), if any. Switches the
+/// CLI doesn't own, like `--portable`, are left alone.
+pub fn parse(args: &[String]) -> Result<Option<CliCommand>, String> {
+    let mut command = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let next = match arg.as_str() {
+            "--transcribe" => match args.next() {
+                Some(path) => CliCommand::Transcribe(PathBuf::from(path)),
+                None => return Err("--transcribe needs a file path".to_string()),
+            },
+            "--start" => CliCommand::Start,
+            "--stop" => CliCommand::Stop,
+            "--toggle" => CliCommand::Toggle,
+            _ => continue,
+        };
+        if command.is_some() {
+            return Err(
+                "Only one of --transcribe, --start, --stop, --toggle at a time".to_string(),
+            );
+        }
+        command = Some(next);
+    }
+    Ok(command)
+}
+
+/// Let a release build, which has no console of its own, print to the
+/// console it was started from
+#[cfg(target_os = "windows")]
+pub fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn attach_parent_console() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn switches_are_parsed_alongside_unrelated_ones() {
+        assert_eq!(parse(&args(&[])), Ok(None));
+        assert_eq!(parse(&args(&["--portable"])), Ok(None));
+        assert_eq!(
+            parse(&args(&["--portable", "--toggle"])),
+            Ok(Some(CliCommand::Toggle))
+        );
+        assert_eq!(
+            parse(&args(&["--transcribe", "memo.wav"])),
+            Ok(Some(CliCommand::Transcribe(PathBuf::from("memo.wav"))))
+        );
+    }
+
+    #[test]
+    fn missing_paths_and_conflicting_switches_are_rejected() {
+        assert!(parse(&args(&["--transcribe"])).is_err());
+        assert!(parse(&args(&["--start", "--stop"])).is_err());
+    }
+}
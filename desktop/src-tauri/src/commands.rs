@@ -1,7 +1,10 @@
-use crate::history::TranscriptionHistory;
-use crate::settings::Settings;
-use crate::AppState;
-use tauri::State;
+use crate::history::{
+    DateRange, ExportFormat, HistoryFilter, HistoryPage, TranscriptionLog, HISTORY_LIST_LIMIT,
+};
+use crate::settings::{OnboardingState, OnboardingStep, Settings, SettingsError};
+use crate::statistics::Statistics;
+use crate::{AppState, ReviewAction};
+use tauri::{AppHandle, State};
 
 #[tauri::command]
 pub fn hide_popup(window: tauri::Window) {
@@ -14,20 +17,297 @@ pub fn get_settings(state: State<AppState>) -> Settings {
 }
 
 #[tauri::command]
-pub fn save_settings(state: State<AppState>, settings: Settings) -> Result<(), String> {
-    settings.save()?;
-    *state.settings.lock().unwrap() = settings;
+pub fn save_settings(app: AppHandle, mut settings: Settings) -> Result<(), SettingsError> {
+    crate::keymap::canonicalize_hotkeys(&mut settings);
+    settings.normalize_urls();
+    let errors = settings.validate();
+    if !errors.is_empty() {
+        return Err(SettingsError::Invalid { errors });
+    }
+    crate::validate_hotkeys(&app, &settings)?;
+    crate::apply_settings(&app, settings).map_err(|message| SettingsError::Io { message })
+}
+
+#[tauri::command]
+pub fn start_recording(app: AppHandle) -> Result<(), String> {
+    crate::start_recording(&app)
+}
+
+#[tauri::command]
+pub async fn stop_recording(app: AppHandle) -> Result<(), String> {
+    crate::stop_recording(app).await
+}
+
+#[tauri::command]
+pub async fn cancel_recording(app: AppHandle) -> Result<(), String> {
+    crate::discard_recording(app).await
+}
+
+#[tauri::command]
+pub fn set_enabled(app: AppHandle, enabled: bool) {
+    crate::set_enabled(&app, enabled);
+}
+
+#[tauri::command]
+pub fn open_settings(app: AppHandle, tab: Option<String>) {
+    crate::open_settings_window(&app, tab.as_deref());
+}
+
+#[tauri::command]
+pub fn reset_settings(app: AppHandle, keep_credentials: bool) -> Result<Settings, String> {
+    crate::reset_settings(&app, keep_credentials)
+}
+
+#[tauri::command]
+pub async fn test_connection(api_url: String, api_key: String) -> Result<String, String> {
+    crate::test_connection(&api_url, &api_key).await
+}
+
+#[tauri::command]
+pub fn get_onboarding_state(state: State<AppState>) -> OnboardingState {
+    state.settings.lock().unwrap().onboarding_state()
+}
+
+#[tauri::command]
+pub fn complete_onboarding_step(
+    app: AppHandle,
+    step: OnboardingStep,
+) -> Result<OnboardingState, String> {
+    crate::complete_onboarding_step(&app, step)
+}
+
+#[tauri::command]
+pub async fn test_microphone(app: AppHandle) -> Result<f32, String> {
+    crate::test_microphone(app).await
+}
+
+#[tauri::command]
+pub fn set_active_profile(app: AppHandle, name: String) -> Result<(), String> {
+    crate::set_active_profile(&app, &name)
+}
+
+#[tauri::command]
+pub fn list_audio_devices() -> Vec<String> {
+    crate::audio::input_device_names()
+}
+
+#[tauri::command]
+pub fn list_supported_keys() -> Vec<&'static str> {
+    crate::keymap::supported_keys()
+}
+
+#[tauri::command]
+pub async fn get_history(app: AppHandle) -> Result<Vec<TranscriptionLog>, String> {
+    crate::with_history(&app, |history| history.recent(HISTORY_LIST_LIMIT)).await?
+}
+
+#[tauri::command]
+pub async fn get_history_page(
+    app: AppHandle,
+    offset: usize,
+    limit: usize,
+    filter: Option<HistoryFilter>,
+) -> Result<HistoryPage, String> {
+    crate::with_history(&app, move |history| {
+        history.page(&filter.unwrap_or_default(), offset, limit)
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn search_history(
+    handle: AppHandle,
+    query: String,
+    date_range: Option<DateRange>,
+    app: Option<String>,
+) -> Result<Vec<TranscriptionLog>, String> {
+    crate::with_history(&handle, move |history| {
+        history.search(
+            &query,
+            &date_range.unwrap_or_default(),
+            app.as_deref().filter(|app| !app.is_empty()),
+            HISTORY_LIST_LIMIT,
+        )
+    })
+    .await?
+}
+
+#[tauri::command]
+pub async fn export_history(
+    app: AppHandle,
+    format: ExportFormat,
+    path: String,
+    range: Option<DateRange>,
+) -> Result<String, String> {
+    let range = range.unwrap_or_default();
+    let entries = crate::with_history(&app, move |history| history.entries_in(&range)).await??;
+    let content = crate::history::render_export(&entries, format)?;
+
+    let path = if path.trim().is_empty() {
+        let mut path = dirs::document_dir().ok_or("No Documents folder to export to")?;
+        path.push(format!(
+            "windows-whisper-history-{}.{}",
+            chrono::Local::now().format("%Y-%m-%d"),
+            format.extension()
+        ));
+        path
+    } else {
+        std::path::PathBuf::from(path.trim())
+    };
+    tokio::fs::write(&path, content)
+        .await
+        .map_err(|e| format!("Failed to write export: {}", e))?;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command]
+pub async fn run_diagnostics(app: AppHandle) -> Vec<crate::diagnostics::DiagnosticCheck> {
+    crate::diagnostics::run(app).await
+}
+
+#[tauri::command]
+pub async fn transcribe_files(app: AppHandle, paths: Vec<String>) -> Result<String, String> {
+    crate::transcribe_files(
+        &app,
+        paths.into_iter().map(std::path::PathBuf::from).collect(),
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn transcribe_folder(
+    app: AppHandle,
+    folder: String,
+) -> Result<crate::batch::BatchSummary, String> {
+    crate::batch::transcribe_folder(app, std::path::PathBuf::from(folder)).await
+}
+
+#[tauri::command]
+pub fn export_diagnostics(state: State<AppState>, path: String) -> Result<String, String> {
+    let path = if path.trim().is_empty() {
+        let mut path = dirs::document_dir().ok_or("No Documents folder to export to")?;
+        path.push(format!(
+            "windows-whisper-diagnostics-{}.zip",
+            chrono::Local::now().format("%Y-%m-%d")
+        ));
+        path
+    } else {
+        std::path::PathBuf::from(path.trim())
+    };
+    let settings = state.settings.lock().unwrap().clone();
+    crate::diagnostics::export(&settings, &path)?;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command]
+pub async fn pin_entry(app: AppHandle, id: i64) -> Result<(), String> {
+    crate::with_history(&app, move |history| history.set_pinned(id, true)).await?
+}
+
+#[tauri::command]
+pub async fn unpin_entry(app: AppHandle, id: i64) -> Result<(), String> {
+    crate::with_history(&app, move |history| history.set_pinned(id, false)).await?
+}
+
+#[tauri::command]
+pub async fn copy_history_entry(app: AppHandle, id: i64) -> Result<(), String> {
+    crate::copy_history_entry(&app, id).await
+}
+
+#[tauri::command]
+pub async fn play_history_audio(app: AppHandle, id: i64) -> Result<(), String> {
+    crate::play_history_audio(&app, id).await
+}
+
+#[tauri::command]
+pub fn stop_history_audio(state: State<AppState>) {
+    *state.playback.lock().unwrap() = None;
+}
+
+#[tauri::command]
+pub async fn delete_entry(app: AppHandle, id: i64) -> Result<(), String> {
+    crate::with_history(&app, move |history| history.trash(id)).await??;
+    crate::refresh_recent_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn restore_entry(app: AppHandle, id: i64) -> Result<(), String> {
+    crate::with_history(&app, move |history| history.restore(id)).await??;
+    crate::refresh_recent_menu(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_trash(app: AppHandle) -> Result<Vec<TranscriptionLog>, String> {
+    crate::with_history(&app, |history| history.trashed()).await?
+}
+
+#[tauri::command]
+pub async fn get_statistics(app: AppHandle) -> Result<Statistics, String> {
+    let samples = crate::with_history(&app, |history| history.samples()).await??;
+    Ok(crate::statistics::statistics(&samples))
+}
+
+#[tauri::command]
+pub async fn clear_history(app: AppHandle) -> Result<(), String> {
+    crate::with_history(&app, |history| {
+        history.clear();
+        crate::recordings::remove_all();
+    })
+    .await?;
+    crate::refresh_recent_menu(&app);
     Ok(())
 }
 
 #[tauri::command]
-pub fn get_history(state: State<AppState>) -> TranscriptionHistory {
-    state.history.lock().unwrap().clone()
+pub async fn wipe_all_data(
+    app: AppHandle,
+    include_settings: bool,
+    keep_api_key: bool,
+) -> Result<(), String> {
+    crate::wipe_all_data(&app, include_settings, keep_api_key).await
 }
 
 #[tauri::command]
-pub fn clear_history(state: State<AppState>) -> Result<(), String> {
-    let mut history = state.history.lock().unwrap();
-    history.clear();
+pub async fn resolve_review(app: AppHandle, action: ReviewAction) -> Result<(), String> {
+    crate::resolve_review(app, action, None).await;
     Ok(())
 }
+
+#[tauri::command]
+pub fn get_pending_transcript(state: State<AppState>) -> Option<String> {
+    state
+        .pending_review
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|pending| pending.text.clone())
+}
+
+#[tauri::command]
+pub async fn confirm_paste(app: AppHandle, text: String) -> Result<(), String> {
+    crate::resolve_review(app, ReviewAction::Paste, Some(text)).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn discard_transcript(app: AppHandle) -> Result<(), String> {
+    crate::resolve_review(app, ReviewAction::Discard, None).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn undo_last_dictation(app: AppHandle) -> Result<(), String> {
+    crate::undo_last_dictation(app).await
+}
+
+#[tauri::command]
+pub async fn repaste_last_transcription(app: AppHandle) -> Result<(), String> {
+    crate::repaste_last_transcription(app).await
+}
+
+#[tauri::command]
+pub async fn paste_history_entry(app: AppHandle, offset: usize) -> Result<(), String> {
+    crate::paste_history_entry(app, offset).await
+}
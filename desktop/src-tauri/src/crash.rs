@@ -0,0 +1,87 @@
+//! Crash reports: a panic writes what happened, with the latest log lines,
+//! to the data folder, and the next start points the user at it
+
+use crate::{logs, paths};
+use std::cell::RefCell;
+use std::path::PathBuf;
+
+/// Remembers the report not yet shown to the user
+const UNREPORTED_FILE: &str = "unreported";
+
+thread_local! {
+    /// The report for this thread's latest panic, in case it's caught
+    static LAST_REPORT: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Where crash reports are written
+pub fn crash_dir() -> PathBuf {
+    paths::data_dir().join("crashes")
+}
+
+/// Write a crash report for every panic, then carry on as before (printing
+/// it, and exiting when it's on the main thread)
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let mut report = format!(
+            "Windows Whisper {} crashed at {}\nThread: {}\n{}\n\nBacktrace:\n{}\n\nRecent log:\n",
+            env!("CARGO_PKG_VERSION"),
+            chrono::Local::now().to_rfc3339(),
+            thread.name().unwrap_or("unnamed"),
+            info,
+            std::backtrace::Backtrace::force_capture(),
+        );
+        for line in logs::recent() {
+            report.push_str(&line);
+            report.push('\n');
+        }
+        match write_report(&report) {
+            Ok(path) => LAST_REPORT.with(|last| *last.borrow_mut() = Some(path)),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        previous(info);
+    }));
+}
+
+fn write_report(report: &str) -> std::io::Result<PathBuf> {
+    let dir = crash_dir();
+    std::fs::create_dir_all(&dir)?;
+    let name = format!(
+        "crash-{}.txt",
+        chrono::Local::now().format("%Y%m%d-%H%M%S%.3f")
+    );
+    let path = dir.join(&name);
+    std::fs::write(&path, report)?;
+    std::fs::write(dir.join(UNREPORTED_FILE), name)?;
+    Ok(path)
+}
+
+/// Withdraw the report for a panic on this thread that was caught and
+/// recovered from, so the next start doesn't announce a crash
+pub fn discard_recovered() {
+    let Some(path) = LAST_REPORT.with(|last| last.borrow_mut().take()) else {
+        return;
+    };
+    let marker = crash_dir().join(UNREPORTED_FILE);
+    let name = path.file_name().and_then(|name| name.to_str());
+    if std::fs::read_to_string(&marker)
+        .ok()
+        .as_deref()
+        .map(str::trim)
+        == name
+    {
+        let _ = std::fs::remove_file(&marker);
+    }
+    let _ = std::fs::remove_file(&path);
+}
+
+/// The crash report written since the user was last told about one, if any.
+/// Each report is only returned once.
+pub fn take_unreported() -> Option<PathBuf> {
+    let dir = crash_dir();
+    let marker = dir.join(UNREPORTED_FILE);
+    let name = std::fs::read_to_string(&marker).ok()?;
+    let _ = std::fs::remove_file(&marker);
+    Some(dir.join(name.trim())).filter(|path| path.exists())
+}
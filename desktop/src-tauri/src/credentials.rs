@@ -0,0 +1,92 @@
+//! Secrets kept in Windows Credential Manager instead of `settings.json`
+
+/// Credential Manager entry holding the transcription API key
+pub const API_KEY_TARGET: &str = "windows-whisper:api_key";
+/// Credential Manager entry holding the automation API's bearer token
+pub const AUTOMATION_TOKEN_TARGET: &str = "windows-whisper:automation_token";
+
+/// Credential Manager entry holding the API key of the hotkey binding on
+/// `hotkey`
+pub fn binding_key_target(hotkey: &str) -> String {
+    format!("windows-whisper:binding:{}:api_key", hotkey)
+}
+
+/// Credential Manager entry holding the API key of the profile `name`
+pub fn profile_key_target(name: &str) -> String {
+    format!("windows-whisper:profile:{}:api_key", name)
+}
+
+#[cfg(target_os = "windows")]
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// The secret stored under `target`, or `None` if there isn't one
+#[cfg(target_os = "windows")]
+pub fn read(target: &str) -> Option<String> {
+    use windows_sys::Win32::Security::Credentials::{
+        CredFree, CredReadW, CREDENTIALW, CRED_TYPE_GENERIC,
+    };
+
+    let target = wide(target);
+    let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+    unsafe {
+        if CredReadW(target.as_ptr(), CRED_TYPE_GENERIC, 0, &mut credential) == 0 {
+            return None;
+        }
+        let blob = std::slice::from_raw_parts(
+            (*credential).CredentialBlob,
+            (*credential).CredentialBlobSize as usize,
+        );
+        let secret = String::from_utf8(blob.to_vec()).ok();
+        CredFree(credential as *const _);
+        secret
+    }
+}
+
+/// Store `secret` under `target`, replacing any previous value
+#[cfg(target_os = "windows")]
+pub fn write(target: &str, secret: &str) -> Result<(), String> {
+    use windows_sys::Win32::Security::Credentials::{
+        CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+    };
+
+    let mut target = wide(target);
+    let mut blob = secret.as_bytes().to_vec();
+    let mut credential: CREDENTIALW = unsafe { std::mem::zeroed() };
+    credential.Type = CRED_TYPE_GENERIC;
+    credential.TargetName = target.as_mut_ptr();
+    credential.CredentialBlobSize = blob.len() as u32;
+    credential.CredentialBlob = blob.as_mut_ptr();
+    credential.Persist = CRED_PERSIST_LOCAL_MACHINE;
+
+    if unsafe { CredWriteW(&credential, 0) } == 0 {
+        return Err(format!(
+            "Failed to save credential: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+/// Remove the secret stored under `target`, if any
+#[cfg(target_os = "windows")]
+pub fn delete(target: &str) {
+    use windows_sys::Win32::Security::Credentials::{CredDeleteW, CRED_TYPE_GENERIC};
+
+    let target = wide(target);
+    unsafe { CredDeleteW(target.as_ptr(), CRED_TYPE_GENERIC, 0) };
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn read(_target: &str) -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn write(_target: &str, _secret: &str) -> Result<(), String> {
+    Err("Credential Manager is only available on Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn delete(_target: &str) {}
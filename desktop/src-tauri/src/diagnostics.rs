@@ -0,0 +1,244 @@
+//! Troubleshooting: a self-check of each part of a dictation, and a
+//! diagnostics bundle for bug reports (logs, crash reports, settings, and
+//! version and device details in one zip, with secrets masked)
+
+use crate::settings::Settings;
+use crate::{crash, injection, logs, paths, redact, uia, AppState};
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::Path;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_global_shortcut::GlobalShortcutExt;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+/// Newest crash reports included
+const CRASH_REPORTS: usize = 5;
+/// Time for the paste check's scratch window to load and take focus
+const SCRATCH_WINDOW_DELAY_MS: u64 = 800;
+/// Time for the scratch window to receive the paste
+const SCRATCH_PASTE_DELAY_MS: u64 = 300;
+
+/// Outcome of one self-check, for the Troubleshoot panel
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticCheck {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl DiagnosticCheck {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        let passed = result.is_ok();
+        let detail = result.unwrap_or_else(|e| e);
+        Self {
+            name,
+            passed,
+            detail,
+        }
+    }
+}
+
+/// Check the microphone, hotkey, clipboard, server, and pasting in turn
+pub(crate) async fn run(app: AppHandle) -> Vec<DiagnosticCheck> {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    vec![
+        DiagnosticCheck::new("Microphone", check_microphone(&app).await),
+        DiagnosticCheck::new("Hotkey", check_hotkey(&app, &settings)),
+        DiagnosticCheck::new("Clipboard", check_clipboard(&app)),
+        DiagnosticCheck::new("Server", check_server(&settings).await),
+        DiagnosticCheck::new("Paste", check_paste(&app, &settings).await),
+    ]
+}
+
+async fn check_microphone(app: &AppHandle) -> Result<String, String> {
+    let peak = crate::test_microphone(app.clone()).await?;
+    if peak <= 0.0 {
+        return Err("The default input device recorded only silence".to_string());
+    }
+    Ok(format!("Recorded audio, peak level {:.0}%", peak * 100.0))
+}
+
+fn check_hotkey(app: &AppHandle, settings: &Settings) -> Result<String, String> {
+    if crate::mouse_hook::parse_mouse_trigger(&settings.hotkey).is_some() {
+        return Ok(format!(
+            "{} is a mouse button, read by the mouse hook",
+            settings.hotkey
+        ));
+    }
+    let Some(shortcut) = crate::keymap::parse_hotkey(&settings.hotkey) else {
+        return Err(format!("\"{}\" is not a valid hotkey", settings.hotkey));
+    };
+    if let Some(reason) = crate::paused_reason(&app.state::<AppState>()) {
+        return Err(format!("Recording hotkeys are off ({})", reason));
+    }
+    if settings.swallow_trigger_keys {
+        return Ok(format!("{} is read by the keyboard hook", settings.hotkey));
+    }
+    if !app.global_shortcut().is_registered(shortcut) {
+        return Err(format!(
+            "{} isn't registered; another app may be using it",
+            settings.hotkey
+        ));
+    }
+    Ok(format!("{} is registered", settings.hotkey))
+}
+
+/// Write to the clipboard and read it back, then put back what was there
+fn check_clipboard(app: &AppHandle) -> Result<String, String> {
+    let marker = format!(
+        "windows-whisper-check-{}",
+        chrono::Local::now().timestamp_millis()
+    );
+    let previous = app.clipboard().read_text().ok();
+    app.clipboard()
+        .write_text(marker.clone())
+        .map_err(|e| format!("Couldn't write: {}", e))?;
+    let read = app.clipboard().read_text().ok();
+    if let Some(previous) = previous {
+        let _ = app.clipboard().write_text(previous);
+    }
+    if read.as_deref() != Some(marker.as_str()) {
+        return Err("Wrote to the clipboard but read back something else".to_string());
+    }
+    Ok("Wrote and read back text".to_string())
+}
+
+async fn check_server(settings: &Settings) -> Result<String, String> {
+    if settings.api_url.is_empty() || settings.api_key.is_empty() {
+        return Err("No worker URL or API key set".to_string());
+    }
+    let api_url = crate::test_connection(&settings.api_url, &settings.api_key).await?;
+    Ok(format!("{} accepted the API key", api_url))
+}
+
+/// Paste a marker into a scratch window and read it back through UI
+/// Automation
+async fn check_paste(app: &AppHandle, settings: &Settings) -> Result<String, String> {
+    let window = WebviewWindowBuilder::new(
+        app,
+        "diagnostics-scratch",
+        WebviewUrl::App("scratch.html".into()),
+    )
+    .title("Windows Whisper paste check")
+    .inner_size(360.0, 160.0)
+    .always_on_top(true)
+    .center()
+    .build()
+    .map_err(|e| format!("Couldn't open the scratch window: {}", e))?;
+    tokio::time::sleep(std::time::Duration::from_millis(SCRATCH_WINDOW_DELAY_MS)).await;
+    let _ = window.set_focus();
+
+    let marker = format!("paste-check-{}", chrono::Local::now().timestamp_millis());
+    let previous = app.clipboard().read_text().ok();
+    let result = async {
+        app.clipboard()
+            .write_text(marker.clone())
+            .map_err(|e| format!("Couldn't write the clipboard: {}", e))?;
+        injection::paste_text(settings.paste_chord_for(None))?;
+        tokio::time::sleep(std::time::Duration::from_millis(SCRATCH_PASTE_DELAY_MS)).await;
+        let text = tauri::async_runtime::spawn_blocking(uia::focused_text)
+            .await
+            .ok()
+            .flatten();
+        match text {
+            Some(text) if text.contains(&marker) => Ok("Pasted into a test window".to_string()),
+            Some(_) => Err("The paste chord didn't reach the test window".to_string()),
+            None => Err("Couldn't read the test window's text".to_string()),
+        }
+    }
+    .await;
+
+    let _ = window.close();
+    if let Some(previous) = previous {
+        let _ = app.clipboard().write_text(previous);
+    }
+    result
+}
+
+/// App version and how it was built and started
+fn build_info() -> Value {
+    json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "profile": if cfg!(debug_assertions) { "debug" } else { "release" },
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "portable": paths::is_portable(),
+        "exported_at": chrono::Local::now().to_rfc3339(),
+    })
+}
+
+/// Audio input devices and their default formats
+fn device_info() -> Value {
+    let host = cpal::default_host();
+    let default = host
+        .default_input_device()
+        .and_then(|device| device.name().ok());
+    let inputs: Vec<Value> = host
+        .input_devices()
+        .map(|devices| {
+            devices
+                .map(|device| {
+                    let config = device.default_input_config().ok();
+                    json!({
+                        "name": device.name().unwrap_or_default(),
+                        "sample_rate": config.as_ref().map(|c| c.sample_rate().0),
+                        "channels": config.as_ref().map(|c| c.channels()),
+                        "sample_format": config.as_ref().map(|c| format!("{:?}", c.sample_format())),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    json!({
+        "audio_host": format!("{:?}", host.id()),
+        "default_input": default,
+        "inputs": inputs,
+    })
+}
+
+/// Write the bundle for `settings` to `path`
+pub fn export(settings: &Settings, path: &Path) -> Result<(), String> {
+    let secrets = redact::secrets(settings);
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let mut add = |name: &str, content: &str| -> Result<(), String> {
+        zip.start_file(name, FileOptions::default())
+            .map_err(|e| e.to_string())?;
+        zip.write_all(redact::text(content, &secrets).as_bytes())
+            .map_err(|e| e.to_string())
+    };
+
+    let pretty = |value: &Value| serde_json::to_string_pretty(value).unwrap_or_default();
+    add("build.json", &pretty(&build_info()))?;
+    add("devices.json", &pretty(&device_info()))?;
+    add("settings.json", &pretty(&redact::settings_json(settings)))?;
+
+    let log = logs::file_path();
+    for path in [log.with_extension("log.old"), log] {
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            add(&format!("logs/{}", name), &content)?;
+        }
+    }
+
+    let mut reports: Vec<_> = std::fs::read_dir(crash::crash_dir())
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    reports.retain(|path| path.extension().is_some_and(|ext| ext == "txt"));
+    // Named by time, so the newest sort last
+    reports.sort();
+    for path in reports.iter().rev().take(CRASH_REPORTS) {
+        if let Ok(content) = std::fs::read_to_string(path) {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            add(&format!("crashes/{}", name), &content)?;
+        }
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,195 @@
+//! Routes a finished transcript to every enabled output
+
+use crate::settings::{OutputTarget, Settings};
+use crate::{output, set_tray_status, AppState, TrayStatus};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// The outputs for this dictation: the starting hotkey's target when it has
+/// one (still recording history if enabled), otherwise the configured set.
+fn outputs_for_session(
+    settings: &Settings,
+    session_output: Option<OutputTarget>,
+) -> Vec<OutputTarget> {
+    match session_output {
+        Some(target) => {
+            let mut outputs = vec![target];
+            if target != OutputTarget::History && settings.outputs.contains(&OutputTarget::History)
+            {
+                outputs.push(OutputTarget::History);
+            }
+            outputs
+        }
+        None => settings.outputs.clone(),
+    }
+}
+
+/// Describe the finished dictation for the webhook.
+fn webhook_payload(app: &AppHandle, text: &str) -> output::WebhookPayload {
+    let state = app.state::<AppState>();
+    output::WebhookPayload {
+        text: text.to_string(),
+        timestamp: chrono::Local::now().to_rfc3339(),
+        app: state
+            .recording_target
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(|t| t.process_name.clone()),
+        duration_seconds: state
+            .last_recording_duration
+            .lock()
+            .unwrap()
+            .map(|d| d.as_secs_f64()),
+        translation: None,
+    }
+}
+
+/// `text` in the translation language, or `None` when translation is off.
+/// A failed translation is logged and added to `errors`, and the original
+/// is delivered alone.
+async fn translate(text: &str, settings: &Settings, errors: &mut Vec<String>) -> Option<String> {
+    let target = settings.translation_language.trim();
+    if target.is_empty() {
+        return None;
+    }
+    match crate::translate_text(
+        &settings.api_url,
+        &settings.api_key,
+        text,
+        settings.language.trim(),
+        target,
+    )
+    .await
+    {
+        Ok(translation) if !translation.is_empty() => Some(translation),
+        Ok(_) => None,
+        Err(e) => {
+            log!("Failed to translate transcript: {}", e);
+            errors.push(e);
+            None
+        }
+    }
+}
+
+/// Send a finished transcript to each enabled output. Pasting runs first
+/// since it depends on the target window still having focus.
+pub async fn dispatch_output(app: AppHandle, raw_text: String, text: String, settings: Settings) {
+    let state = app.state::<AppState>();
+    let session_output = state
+        .session_binding
+        .lock()
+        .unwrap()
+        .take()
+        .map(|binding| binding.output);
+    let outputs = outputs_for_session(&settings, session_output);
+    let mut errors = Vec::new();
+
+    // Only pasting the translation waits for it; otherwise the original is
+    // pasted before the translation request goes out
+    let pasted_translation = if settings.paste_translation {
+        translate(&text, &settings, &mut errors).await
+    } else {
+        None
+    };
+    let pasted = pasted_translation.clone().unwrap_or_else(|| text.clone());
+
+    // Captured before pasting, which consumes the recording target
+    let app_name = crate::target_app_name(&state);
+    let mut payload = webhook_payload(&app, &text);
+
+    if outputs.contains(&OutputTarget::Paste) {
+        crate::paste_transcript(&app, &pasted, &settings).await;
+    } else {
+        *state.recording_target.lock().unwrap() = None;
+    }
+
+    let translation = if settings.paste_translation {
+        pasted_translation
+    } else {
+        translate(&text, &settings, &mut errors).await
+    };
+    payload.translation = translation.clone();
+    // The version that wasn't pasted is left on the clipboard
+    let copied = match &translation {
+        Some(_) if settings.paste_translation => Some(text.clone()),
+        Some(translation) => Some(translation.clone()),
+        None => None,
+    };
+    if let (Some(copied), true) = (&copied, outputs.contains(&OutputTarget::Paste)) {
+        use tauri_plugin_clipboard_manager::ClipboardExt;
+        if let Err(e) = app.clipboard().write_text(copied) {
+            errors.push(format!("Failed to write clipboard: {}", e));
+        }
+    }
+    // Files and notes get both
+    let versions: Vec<&str> = std::iter::once(text.as_str())
+        .chain(translation.as_deref())
+        .collect();
+
+    if outputs.contains(&OutputTarget::History) {
+        crate::record_history(&app, raw_text, text.clone(), app_name).await;
+    }
+
+    for target in &outputs {
+        let result = match target {
+            OutputTarget::Paste | OutputTarget::History => Ok(()),
+            // Already holding the version that wasn't pasted
+            OutputTarget::ClipboardOnly
+                if copied.is_some() && outputs.contains(&OutputTarget::Paste) =>
+            {
+                Ok(())
+            }
+            OutputTarget::ClipboardOnly => {
+                use tauri_plugin_clipboard_manager::ClipboardExt;
+                app.clipboard()
+                    .write_text(versions.join("\n\n"))
+                    .map_err(|e| format!("Failed to write clipboard: {}", e))
+            }
+            OutputTarget::AppendToFile if settings.append_file_path.is_empty() => {
+                Err("No append file configured".to_string())
+            }
+            OutputTarget::AppendToFile => versions.iter().try_for_each(|version| {
+                output::append_to_file(&settings.append_file_path, version)
+            }),
+            OutputTarget::DailyNote if settings.daily_note_folder.is_empty() => {
+                Err("No daily note folder configured".to_string())
+            }
+            OutputTarget::DailyNote => versions.iter().try_for_each(|version| {
+                output::append_to_daily_note(
+                    &settings.daily_note_folder,
+                    &settings.daily_note_heading,
+                    &settings.daily_note_template,
+                    version,
+                )
+            }),
+            OutputTarget::Webhook if settings.webhook_url.is_empty() => {
+                Err("No webhook URL configured".to_string())
+            }
+            OutputTarget::Webhook => {
+                // Don't hold up the other outputs on a slow endpoint
+                let url = settings.webhook_url.clone();
+                let payload = payload.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = output::send_webhook(&url, &payload).await {
+                        log!("Failed to send webhook: {}", e);
+                    }
+                });
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            log!("Output {:?} failed: {}", target, e);
+            errors.push(e);
+        }
+    }
+
+    if let Some(error) = errors.into_iter().next() {
+        let _ = app.emit("show-error", error.clone());
+        set_tray_status(&app, TrayStatus::Error(error));
+        return;
+    }
+    set_tray_status(&app, TrayStatus::Idle);
+    if !outputs.contains(&OutputTarget::Paste) {
+        let _ = app.emit("show-success", pasted);
+    }
+}
@@ -0,0 +1,242 @@
+//! Tap, double-tap, and long-press detection for the recording hotkey, and
+//! tracking which recording hotkeys are held
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long after a tap a second press still counts as a double tap
+pub const DOUBLE_TAP_WINDOW: Duration = Duration::from_millis(300);
+/// How long the hotkey must be held to count as a long press
+pub const LONG_PRESS_THRESHOLD: Duration = Duration::from_millis(600);
+/// Longest gap between auto-repeat presses of a held key. A press of a key
+/// still marked held that comes later is a new one whose release was missed.
+pub const KEY_REPEAT_GAP: Duration = Duration::from_millis(1500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// Toggle recording
+    Tap,
+    /// Re-paste the last transcription
+    DoubleTap,
+    /// Open history
+    LongPress,
+}
+
+/// Outcome of releasing the hotkey
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Release {
+    Gesture(Gesture),
+    /// A first tap; call [`GestureDetector::tap_elapsed`] with this id once
+    /// [`DOUBLE_TAP_WINDOW`] has passed
+    PendingTap(u64),
+    /// The press was already handled (long press) or never seen
+    Ignored,
+}
+
+/// State machine fed with hotkey presses, releases, and timer expiries.
+/// Timers carry the id of the press that scheduled them so stale ones are
+/// ignored.
+#[derive(Debug, Default)]
+pub struct GestureDetector {
+    generation: u64,
+    held: bool,
+    long_press_fired: bool,
+    /// Id of a released tap waiting to see whether a second tap follows
+    pending_tap: Option<u64>,
+}
+
+impl GestureDetector {
+    /// Record a press, returning its id for the long-press timer
+    pub fn press(&mut self) -> u64 {
+        self.generation += 1;
+        self.held = true;
+        self.long_press_fired = false;
+        self.generation
+    }
+
+    /// Record a release
+    pub fn release(&mut self) -> Release {
+        if !self.held {
+            return Release::Ignored;
+        }
+        self.held = false;
+
+        if self.long_press_fired {
+            Release::Ignored
+        } else if self.pending_tap.take().is_some() {
+            Release::Gesture(Gesture::DoubleTap)
+        } else {
+            self.pending_tap = Some(self.generation);
+            Release::PendingTap(self.generation)
+        }
+    }
+
+    /// The long-press timer for press `id` expired
+    pub fn long_press_elapsed(&mut self, id: u64) -> Option<Gesture> {
+        if self.held && self.generation == id && !self.long_press_fired {
+            self.long_press_fired = true;
+            self.pending_tap = None;
+            Some(Gesture::LongPress)
+        } else {
+            None
+        }
+    }
+
+    /// The double-tap window after tap `id` expired
+    pub fn tap_elapsed(&mut self, id: u64) -> Option<Gesture> {
+        // A second press is in progress; its release decides
+        if self.held {
+            return None;
+        }
+        if self.pending_tap == Some(id) {
+            self.pending_tap = None;
+            Some(Gesture::Tap)
+        } else {
+            None
+        }
+    }
+}
+
+/// A recording hotkey marked as held down
+#[derive(Debug)]
+struct HeldKey {
+    last_press: Instant,
+    /// Pressed for the current recording, rather than left over from before
+    /// it with its release missed
+    current: bool,
+}
+
+/// Which recording hotkeys are held, by shortcut id, so key repeat isn't
+/// taken for new presses and hold-to-talk ends only once every key is up.
+/// Releases can go missing (the screen locks, or an elevated window takes
+/// focus), so a key marked held never stops the hotkey working.
+#[derive(Debug, Default)]
+pub struct HeldHotkeys(HashMap<u32, HeldKey>);
+
+impl HeldHotkeys {
+    /// Note hotkey `id` going down at `now`, returning whether it's a new
+    /// press rather than key repeat
+    pub fn press(&mut self, id: u32, recording: bool, now: Instant) -> bool {
+        if let Some(key) = self.0.get_mut(&id) {
+            if now.duration_since(key.last_press) <= KEY_REPEAT_GAP {
+                key.last_press = now;
+                return false;
+            }
+        }
+        if !recording {
+            // Keys still marked held aren't part of the recording this
+            // press starts
+            for key in self.0.values_mut() {
+                key.current = false;
+            }
+        }
+        self.0.insert(
+            id,
+            HeldKey {
+                last_press: now,
+                current: true,
+            },
+        );
+        true
+    }
+
+    /// Note hotkey `id` going up, returning whether it was the last key held
+    /// for the current recording
+    pub fn release(&mut self, id: u32) -> bool {
+        self.0.remove(&id).is_some_and(|key| key.current) && !self.0.values().any(|key| key.current)
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_tap_fires_once_its_window_runs_out() {
+        let mut detector = GestureDetector::default();
+        let id = detector.press();
+        assert_eq!(detector.release(), Release::PendingTap(id));
+        assert_eq!(detector.long_press_elapsed(id), None);
+        assert_eq!(detector.tap_elapsed(id), Some(Gesture::Tap));
+        assert_eq!(detector.tap_elapsed(id), None);
+    }
+
+    #[test]
+    fn second_tap_within_the_window_is_a_double_tap() {
+        let mut detector = GestureDetector::default();
+        let first = detector.press();
+        assert_eq!(detector.release(), Release::PendingTap(first));
+        let second = detector.press();
+        // The first tap's window runs out while the second press is held
+        assert_eq!(detector.tap_elapsed(first), None);
+        assert_eq!(detector.release(), Release::Gesture(Gesture::DoubleTap));
+        assert_eq!(detector.tap_elapsed(first), None);
+        assert_eq!(detector.long_press_elapsed(second), None);
+    }
+
+    #[test]
+    fn held_press_is_a_long_press() {
+        let mut detector = GestureDetector::default();
+        let id = detector.press();
+        assert_eq!(detector.long_press_elapsed(id), Some(Gesture::LongPress));
+        assert_eq!(detector.release(), Release::Ignored);
+        assert_eq!(detector.tap_elapsed(id), None);
+    }
+
+    #[test]
+    fn stale_long_press_timer_is_ignored() {
+        let mut detector = GestureDetector::default();
+        let first = detector.press();
+        detector.release();
+        detector.tap_elapsed(first);
+        let second = detector.press();
+        assert_eq!(detector.long_press_elapsed(first), None);
+        assert_eq!(
+            detector.long_press_elapsed(second),
+            Some(Gesture::LongPress)
+        );
+    }
+
+    #[test]
+    fn key_repeat_is_not_a_new_press() {
+        let start = Instant::now();
+        let mut held = HeldHotkeys::default();
+        assert!(held.press(1, false, start));
+        assert!(!held.press(1, true, start + Duration::from_millis(500)));
+        assert!(!held.press(1, true, start + Duration::from_millis(530)));
+        assert!(held.release(1));
+    }
+
+    #[test]
+    fn missed_release_leaves_the_key_working() {
+        let start = Instant::now();
+        let mut held = HeldHotkeys::default();
+        assert!(held.press(1, false, start));
+        // Released while the screen was locked
+        assert!(held.press(1, false, start + Duration::from_secs(10)));
+        assert!(held.release(1));
+    }
+
+    #[test]
+    fn stale_key_doesnt_hold_up_another_release() {
+        let start = Instant::now();
+        let mut held = HeldHotkeys::default();
+        assert!(held.press(1, false, start));
+        assert!(held.press(2, false, start + Duration::from_secs(10)));
+        assert!(held.release(2));
+    }
+
+    #[test]
+    fn release_waits_for_every_key_of_the_recording() {
+        let start = Instant::now();
+        let mut held = HeldHotkeys::default();
+        assert!(held.press(1, false, start));
+        assert!(held.press(2, true, start + Duration::from_millis(100)));
+        assert!(!held.release(1));
+        assert!(held.release(2));
+    }
+}
@@ -0,0 +1,238 @@
+//! Generic HID devices (foot pedals, presenter remotes) as recording
+//! triggers, read through Raw Input
+
+use crate::settings::HidTrigger;
+#[cfg(target_os = "windows")]
+use std::sync::mpsc::SyncSender;
+#[cfg(target_os = "windows")]
+use std::sync::{Mutex, OnceLock};
+
+/// The device collection and button being watched, if any
+#[cfg(target_os = "windows")]
+static WATCHED: Mutex<Option<HidTrigger>> = Mutex::new(None);
+/// Forwards button changes from the input thread
+#[cfg(target_os = "windows")]
+static EVENTS: OnceLock<SyncSender<bool>> = OnceLock::new();
+/// The input thread, told to re-register when the watched device changes
+#[cfg(target_os = "windows")]
+static THREAD_ID: OnceLock<u32> = OnceLock::new();
+
+/// Watch `trigger`, calling `on_event(pressed)` when its button goes down or
+/// up. Calling again switches devices but keeps the first callback.
+#[cfg(target_os = "windows")]
+pub fn install<F>(trigger: Option<HidTrigger>, on_event: F)
+where
+    F: Fn(bool) + Send + 'static,
+{
+    use windows_sys::Win32::UI::WindowsAndMessaging::{PostThreadMessageW, WM_APP};
+
+    let enabled = trigger.is_some();
+    *WATCHED.lock().unwrap() = trigger;
+
+    if let Some(thread_id) = THREAD_ID.get() {
+        unsafe { PostThreadMessageW(*thread_id, WM_APP, 0, 0) };
+        return;
+    }
+    if !enabled {
+        return;
+    }
+    let (tx, rx) = std::sync::mpsc::sync_channel(16);
+    if EVENTS.set(tx).is_err() {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        for pressed in rx {
+            on_event(pressed);
+        }
+    });
+
+    std::thread::spawn(|| unsafe { run_input_thread() });
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn install<F>(trigger: Option<HidTrigger>, _on_event: F)
+where
+    F: Fn(bool) + Send + 'static,
+{
+    if trigger.is_some() {
+        log!("HID triggers are only supported on Windows");
+    }
+}
+
+/// Own a message-only window that receives `WM_INPUT` for the watched
+/// collection, even while another app has focus
+#[cfg(target_os = "windows")]
+unsafe fn run_input_thread() {
+    use windows_sys::Win32::System::Threading::GetCurrentThreadId;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DispatchMessageW, GetMessageW, TranslateMessage, HWND_MESSAGE, MSG,
+        WM_APP, WM_INPUT,
+    };
+
+    let class: Vec<u16> = "STATIC\0".encode_utf16().collect();
+    let hwnd = CreateWindowExW(
+        0,
+        class.as_ptr(),
+        std::ptr::null(),
+        0,
+        0,
+        0,
+        0,
+        0,
+        HWND_MESSAGE,
+        0,
+        0,
+        std::ptr::null(),
+    );
+    if hwnd == 0 {
+        log!("Failed to create HID input window");
+        return;
+    }
+    let _ = THREAD_ID.set(GetCurrentThreadId());
+
+    let mut registered = register_device(hwnd, None);
+    let mut was_pressed = false;
+    let mut msg: MSG = std::mem::zeroed();
+    while GetMessageW(&mut msg, 0, 0, 0) > 0 {
+        match msg.message {
+            WM_APP => {
+                registered = register_device(hwnd, registered);
+                was_pressed = false;
+            }
+            WM_INPUT => {
+                if let Some(pressed) = read_button(msg.lParam) {
+                    if pressed != was_pressed {
+                        was_pressed = pressed;
+                        if let Some(tx) = EVENTS.get() {
+                            let _ = tx.try_send(pressed);
+                        }
+                    }
+                }
+                DispatchMessageW(&msg);
+            }
+            _ => {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+    }
+}
+
+/// Swap the Raw Input registration from `previous` to the watched collection
+#[cfg(target_os = "windows")]
+unsafe fn register_device(hwnd: isize, previous: Option<(u16, u16)>) -> Option<(u16, u16)> {
+    use windows_sys::Win32::UI::Input::{
+        RegisterRawInputDevices, RAWINPUTDEVICE, RIDEV_INPUTSINK, RIDEV_REMOVE,
+    };
+
+    let register = |usage_page: u16, usage: u16, flags, target| {
+        let device = RAWINPUTDEVICE {
+            usUsagePage: usage_page,
+            usUsage: usage,
+            dwFlags: flags,
+            hwndTarget: target,
+        };
+        RegisterRawInputDevices(&device, 1, std::mem::size_of::<RAWINPUTDEVICE>() as u32) != 0
+    };
+
+    if let Some((usage_page, usage)) = previous {
+        register(usage_page, usage, RIDEV_REMOVE, 0);
+    }
+
+    let trigger = WATCHED.lock().unwrap().clone()?;
+    if register(trigger.usage_page, trigger.usage, RIDEV_INPUTSINK, hwnd) {
+        Some((trigger.usage_page, trigger.usage))
+    } else {
+        log!(
+            "Failed to watch HID usage {:#06x}:{:#06x}",
+            trigger.usage_page,
+            trigger.usage
+        );
+        None
+    }
+}
+
+/// Whether the watched button is down in a `WM_INPUT` report, or `None` for
+/// reports from other devices
+#[cfg(target_os = "windows")]
+unsafe fn read_button(lparam: isize) -> Option<bool> {
+    use windows_sys::Win32::Devices::HumanInterfaceDevice::{HidP_GetUsages, HidP_Input};
+    use windows_sys::Win32::UI::Input::{
+        GetRawInputData, GetRawInputDeviceInfoW, RAWINPUT, RAWINPUTHEADER, RIDI_PREPARSEDDATA,
+        RID_INPUT, RIM_TYPEHID,
+    };
+
+    const HIDP_STATUS_SUCCESS: i32 = 0x0011_0000;
+    const BUTTON_PAGE: u16 = 0x09;
+
+    let trigger = WATCHED.lock().unwrap().clone()?;
+    let header_size = std::mem::size_of::<RAWINPUTHEADER>() as u32;
+
+    let mut size = 0u32;
+    GetRawInputData(
+        lparam,
+        RID_INPUT,
+        std::ptr::null_mut(),
+        &mut size,
+        header_size,
+    );
+    // u64 storage keeps the RAWINPUT header suitably aligned
+    let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+    if GetRawInputData(
+        lparam,
+        RID_INPUT,
+        buffer.as_mut_ptr().cast(),
+        &mut size,
+        header_size,
+    ) == u32::MAX
+    {
+        return None;
+    }
+    let input = &*(buffer.as_ptr() as *const RAWINPUT);
+    if input.header.dwType != RIM_TYPEHID {
+        return None;
+    }
+
+    let mut preparsed_size = 0u32;
+    GetRawInputDeviceInfoW(
+        input.header.hDevice,
+        RIDI_PREPARSEDDATA,
+        std::ptr::null_mut(),
+        &mut preparsed_size,
+    );
+    let mut preparsed = vec![0u64; (preparsed_size as usize).div_ceil(8)];
+    if GetRawInputDeviceInfoW(
+        input.header.hDevice,
+        RIDI_PREPARSEDDATA,
+        preparsed.as_mut_ptr().cast(),
+        &mut preparsed_size,
+    ) == u32::MAX
+    {
+        return None;
+    }
+
+    let hid = &input.data.hid;
+    let reports = std::slice::from_raw_parts(
+        hid.bRawData.as_ptr(),
+        (hid.dwSizeHid * hid.dwCount) as usize,
+    );
+    for report in reports.chunks(hid.dwSizeHid.max(1) as usize) {
+        let mut usages = [0u16; 32];
+        let mut count = usages.len() as u32;
+        let status = HidP_GetUsages(
+            HidP_Input,
+            BUTTON_PAGE,
+            0,
+            usages.as_mut_ptr(),
+            &mut count,
+            preparsed.as_ptr() as isize,
+            report.as_ptr() as *mut u8,
+            report.len() as u32,
+        );
+        if status == HIDP_STATUS_SUCCESS {
+            return Some(usages[..count as usize].contains(&trigger.button));
+        }
+    }
+    None
+}
@@ -1,82 +1,1037 @@
-//! Transcription history management
+//! Transcription history management, stored in SQLite
 
+use crate::settings::HistoryRetention;
+use crate::statistics::DictationSample;
+use rusqlite::{params, Connection, Row};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-const MAX_HISTORY_ENTRIES: usize = 50;
-const HISTORY_FILE: &str = "history.json";
+const HISTORY_DB: &str = "history.db";
+/// The JSON file used before history moved to SQLite, imported once
+const LEGACY_HISTORY_FILE: &str = "history.json";
+/// Entries returned by `get_history`
+pub const HISTORY_LIST_LIMIT: usize = 100;
+
+/// Schema changes, applied in order and tracked with `PRAGMA user_version`
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE entries (
+        id INTEGER PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        raw_text TEXT NOT NULL,
+        processed_text TEXT NOT NULL
+    );
+    CREATE INDEX entries_timestamp ON entries (timestamp);",
+    "ALTER TABLE entries ADD COLUMN app TEXT;",
+    // Full-text index over both texts, kept in sync by triggers
+    "CREATE VIRTUAL TABLE entries_fts USING fts5(
+        raw_text, processed_text, content='entries', content_rowid='id'
+    );
+    INSERT INTO entries_fts (rowid, raw_text, processed_text)
+        SELECT id, raw_text, processed_text FROM entries;
+    CREATE TRIGGER entries_ai AFTER INSERT ON entries BEGIN
+        INSERT INTO entries_fts (rowid, raw_text, processed_text)
+            VALUES (new.id, new.raw_text, new.processed_text);
+    END;
+    CREATE TRIGGER entries_ad AFTER DELETE ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, raw_text, processed_text)
+            VALUES ('delete', old.id, old.raw_text, old.processed_text);
+    END;
+    CREATE TRIGGER entries_au AFTER UPDATE ON entries BEGIN
+        INSERT INTO entries_fts (entries_fts, rowid, raw_text, processed_text)
+            VALUES ('delete', old.id, old.raw_text, old.processed_text);
+        INSERT INTO entries_fts (rowid, raw_text, processed_text)
+            VALUES (new.id, new.raw_text, new.processed_text);
+    END;",
+    "ALTER TABLE entries ADD COLUMN audio_path TEXT;",
+    "ALTER TABLE entries ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0;",
+    "ALTER TABLE entries ADD COLUMN duration_ms INTEGER;
+    ALTER TABLE entries ADD COLUMN word_count INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE entries ADD COLUMN language TEXT;
+    ALTER TABLE entries ADD COLUMN backend TEXT;
+    ALTER TABLE entries ADD COLUMN latency_ms INTEGER;
+    -- Backfill by counting spaces, close enough for existing entries
+    UPDATE entries SET word_count =
+        length(trim(processed_text)) - length(replace(trim(processed_text), ' ', '')) + 1
+        WHERE trim(processed_text) != '';",
+    "ALTER TABLE entries ADD COLUMN deleted_at TEXT;",
+    "ALTER TABLE entries ADD COLUMN repeat_count INTEGER NOT NULL DEFAULT 1;",
+];
 
 /// A single transcription log entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscriptionLog {
+    #[serde(default)]
+    pub id: i64,
     /// ISO 8601 timestamp
     pub timestamp: String,
     /// Raw transcription before post-processing
     pub raw_text: String,
     /// Text after post-processing was applied
     pub processed_text: String,
+    /// Executable the transcript was dictated into, e.g. `slack.exe`
+    #[serde(default)]
+    pub app: Option<String>,
+    /// Saved recording of the dictation, when keeping audio is enabled
+    #[serde(default)]
+    pub audio_path: Option<String>,
+    /// Pinned entries list first and are never evicted
+    #[serde(default)]
+    pub pinned: bool,
+    /// Length of the recording
+    #[serde(default)]
+    pub duration_ms: Option<u32>,
+    #[serde(default)]
+    pub word_count: u32,
+    /// Language requested from the transcription API, if one was set
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Host of the transcription API that produced the text
+    #[serde(default)]
+    pub backend: Option<String>,
+    /// Time from stopping the recording to having the final text
+    #[serde(default)]
+    pub latency_ms: Option<u32>,
+    /// When the entry was moved to the trash
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+    /// Times this text was dictated back to back
+    #[serde(default = "default_repeat_count")]
+    pub repeat_count: u32,
 }
 
-/// Collection of transcription history entries
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct TranscriptionHistory {
+fn default_repeat_count() -> u32 {
+    1
+}
+
+/// What is known about a dictation besides its text, recorded with it
+#[derive(Debug, Clone, Default)]
+pub struct EntryMetadata {
+    pub app: Option<String>,
+    pub duration_ms: Option<u32>,
+    pub language: Option<String>,
+    pub backend: Option<String>,
+    pub latency_ms: Option<u32>,
+}
+
+impl TranscriptionLog {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get("id")?,
+            timestamp: row.get("timestamp")?,
+            raw_text: row.get("raw_text")?,
+            processed_text: row.get("processed_text")?,
+            app: row.get("app")?,
+            audio_path: row.get("audio_path")?,
+            pinned: row.get("pinned")?,
+            duration_ms: row.get("duration_ms")?,
+            word_count: row.get("word_count")?,
+            language: row.get("language")?,
+            backend: row.get("backend")?,
+            latency_ms: row.get("latency_ms")?,
+            deleted_at: row.get("deleted_at")?,
+            repeat_count: row.get("repeat_count")?,
+        })
+    }
+}
+
+/// Inclusive range of local dates (`YYYY-MM-DD`); either end may be open
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DateRange {
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+/// What `get_history_page` narrows history down to; empty matches everything
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct HistoryFilter {
+    pub query: String,
+    pub date_range: DateRange,
+    pub app: Option<String>,
+}
+
+/// A slice of filtered history, with the total so the UI knows when to stop
+#[derive(Debug, Clone, Serialize)]
+pub struct HistoryPage {
     pub entries: Vec<TranscriptionLog>,
+    pub offset: usize,
+    pub total: usize,
+}
+
+/// `WHERE` clause for searches outside the trash: an FTS query in ?1 (empty
+/// for any text), a date range in ?2 and ?3, and an app name in ?4
+const FILTER_SQL: &str = "WHERE deleted_at IS NULL
+    AND (?1 = '' OR id IN (SELECT rowid FROM entries_fts WHERE entries_fts MATCH ?1))
+    AND (?2 IS NULL OR substr(timestamp, 1, 10) >= ?2)
+    AND (?3 IS NULL OR substr(timestamp, 1, 10) <= ?3)
+    AND (?4 IS NULL OR app = ?4 COLLATE NOCASE)";
+
+/// Turn free text into an FTS5 query matching entries that contain every
+/// word (or a word starting with it), with syntax characters neutralised
+fn fts_query(text: &str) -> String {
+    text.split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The start of `text` on one line, shortened to `max_chars` with an ellipsis
+pub fn preview(text: &str, max_chars: usize) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= max_chars {
+        return line;
+    }
+    let cut: String = line.chars().take(max_chars.saturating_sub(1)).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Shape of the legacy `history.json`
+#[derive(Deserialize)]
+struct LegacyHistory {
+    entries: Vec<TranscriptionLog>,
+}
+
+/// File formats for `export_history`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    /// A readable journal grouped by day
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render `entries` (oldest first) in `format`
+pub fn render_export(entries: &[TranscriptionLog], format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(entries).map_err(|e| e.to_string()),
+        ExportFormat::Csv => {
+            let mut out = String::from(
+                "id,timestamp,app,raw_text,processed_text,duration_ms,word_count,language,\
+                 backend,latency_ms\n",
+            );
+            let number = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_default();
+            for entry in entries {
+                let fields = [
+                    entry.id.to_string(),
+                    csv_field(&entry.timestamp),
+                    csv_field(entry.app.as_deref().unwrap_or("")),
+                    csv_field(&entry.raw_text),
+                    csv_field(&entry.processed_text),
+                    number(entry.duration_ms),
+                    entry.word_count.to_string(),
+                    csv_field(entry.language.as_deref().unwrap_or("")),
+                    csv_field(entry.backend.as_deref().unwrap_or("")),
+                    number(entry.latency_ms),
+                ];
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+            Ok(out)
+        }
+        ExportFormat::Markdown => {
+            let mut out = String::from("# Dictation History\n");
+            let mut current_day = "";
+            for entry in entries {
+                let day = entry.timestamp.get(..10).unwrap_or(&entry.timestamp);
+                if day != current_day {
+                    out.push_str(&format!("\n## {}\n", day));
+                    current_day = day;
+                }
+                let time = entry.timestamp.get(11..16).unwrap_or("");
+                match &entry.app {
+                    Some(app) => out.push_str(&format!("\n### {} · {}\n\n", time, app)),
+                    None => out.push_str(&format!("\n### {}\n\n", time)),
+                }
+                out.push_str(entry.processed_text.trim());
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// Transcription history, newest first
+pub struct TranscriptionHistory {
+    conn: Connection,
+    retention: HistoryRetention,
 }
 
 impl TranscriptionHistory {
-    /// Get the path to the history file
-    fn get_path() -> PathBuf {
-        let mut path = dirs::data_local_dir().unwrap_or_default();
-        path.push("windows-whisper");
+    fn data_dir() -> PathBuf {
+        let path = crate::paths::data_dir();
         std::fs::create_dir_all(&path).ok();
-        path.push(HISTORY_FILE);
         path
     }
 
-    /// Load history from disk
-    pub fn load() -> Self {
-        let path = Self::get_path();
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            if let Ok(history) = serde_json::from_str(&content) {
-                return history;
+    /// Open the history database, importing the old JSON history on first
+    /// run and trimming it to `retention`. Falls back to an in-memory store
+    /// if the file can't be opened.
+    pub fn load(retention: HistoryRetention) -> Self {
+        let dir = Self::data_dir();
+        let mut history = Connection::open(dir.join(HISTORY_DB))
+            .map_err(|e| e.to_string())
+            .and_then(Self::from_connection)
+            .unwrap_or_else(|e| {
+                log!("Failed to open history database: {}", e);
+                Self::in_memory()
+            });
+        history.import_legacy(&dir.join(LEGACY_HISTORY_FILE));
+        history.set_retention(retention);
+        history
+    }
+
+    fn in_memory() -> Self {
+        Connection::open_in_memory()
+            .map_err(|e| e.to_string())
+            .and_then(Self::from_connection)
+            .expect("in-memory SQLite should always open")
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self, String> {
+        // WAL lets readers and the writer work concurrently; the timeout
+        // waits out another process's write instead of failing
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))
+            .map_err(|e| e.to_string())?;
+
+        let version: usize = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(|e| e.to_string())?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            conn.execute_batch(migration)
+                .map_err(|e| format!("History migration {} failed: {}", index + 1, e))?;
+            conn.pragma_update(None, "user_version", index + 1)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(Self {
+            conn,
+            retention: HistoryRetention::default(),
+        })
+    }
+
+    /// Move entries from the pre-SQLite JSON file into the database, keeping
+    /// the file as a `.bak` once imported
+    fn import_legacy(&self, path: &Path) {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return;
+        };
+        let Ok(legacy) = serde_json::from_str::<LegacyHistory>(&content) else {
+            log!("Skipping unreadable legacy history at {}", path.display());
+            return;
+        };
+        // Stored newest first; insert oldest first so ids follow time
+        for entry in legacy.entries.iter().rev() {
+            let result = self.insert(
+                &entry.timestamp,
+                &entry.raw_text,
+                &entry.processed_text,
+                &EntryMetadata::default(),
+            );
+            if let Err(e) = result {
+                log!("Failed to import legacy history: {}", e);
+                return;
             }
         }
-        Self::default()
+        let _ = std::fs::rename(path, path.with_extension("json.bak"));
     }
 
-    /// Save history to disk
-    pub fn save(&self) -> Result<(), String> {
-        let path = Self::get_path();
-        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
-        std::fs::write(path, content).map_err(|e| e.to_string())?;
-        Ok(())
+    fn insert(
+        &self,
+        timestamp: &str,
+        raw_text: &str,
+        processed_text: &str,
+        metadata: &EntryMetadata,
+    ) -> Result<i64, String> {
+        let word_count = processed_text.split_whitespace().count() as u32;
+        self.conn
+            .execute(
+                "INSERT INTO entries (timestamp, raw_text, processed_text, app, duration_ms,
+                     word_count, language, backend, latency_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    timestamp,
+                    raw_text,
+                    processed_text,
+                    metadata.app,
+                    metadata.duration_ms,
+                    word_count,
+                    metadata.language,
+                    metadata.backend,
+                    metadata.latency_ms,
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(self.conn.last_insert_rowid())
     }
 
-    /// Add a new transcription entry
-    pub fn add_entry(&mut self, raw_text: String, processed_text: String) {
+    /// Add a new transcription entry with what is known about its session.
+    /// Re-dictating the newest entry's text bumps its repeat count instead of
+    /// adding a duplicate. Returns the entry's id, or `None` when history is
+    /// turned off.
+    pub fn add_entry(
+        &mut self,
+        raw_text: String,
+        processed_text: String,
+        metadata: EntryMetadata,
+    ) -> Option<i64> {
+        if !self.retention.enabled {
+            return None;
+        }
         let timestamp = chrono::Local::now().to_rfc3339();
+        if let Some(latest) = self.latest() {
+            if latest.processed_text == processed_text {
+                let result = self.conn.execute(
+                    "UPDATE entries SET repeat_count = repeat_count + 1, timestamp = ?1
+                     WHERE id = ?2",
+                    params![timestamp, latest.id],
+                );
+                return match result {
+                    Ok(_) => Some(latest.id),
+                    Err(e) => {
+                        log!("Failed to save history entry: {}", e);
+                        None
+                    }
+                };
+            }
+        }
+        let id = self
+            .insert(&timestamp, &raw_text, &processed_text, &metadata)
+            .map_err(|e| log!("Failed to save history entry: {}", e))
+            .ok()?;
+        self.enforce_retention();
+        Some(id)
+    }
 
-        self.entries.insert(
-            0,
-            TranscriptionLog {
-                timestamp,
-                raw_text,
-                processed_text,
-            },
-        );
+    /// Switch to a new retention policy, trimming history to fit it
+    pub fn set_retention(&mut self, retention: HistoryRetention) {
+        self.retention = retention;
+        self.enforce_retention();
+    }
+
+    /// Delete the entries, and their saved recordings, that the retention
+    /// policy no longer allows
+    fn enforce_retention(&mut self) {
+        let retention = &self.retention;
+        let days_ago = |days: u32| {
+            (days > 0)
+                .then(|| (chrono::Local::now() - chrono::Duration::days(days as i64)).to_rfc3339())
+        };
+        let cutoff = days_ago(retention.max_age_days);
+        let trash_cutoff = days_ago(retention.trash_days);
+        let result = self
+            .conn
+            .prepare(
+                "DELETE FROM entries
+                 WHERE ?1 = 0
+                    OR (?4 IS NOT NULL AND deleted_at < ?4)
+                    OR (pinned = 0 AND deleted_at IS NULL AND (
+                        (?2 IS NOT NULL AND timestamp < ?2)
+                        OR (?3 > 0 AND id NOT IN (
+                            SELECT id FROM entries WHERE pinned = 0 AND deleted_at IS NULL
+                            ORDER BY id DESC LIMIT ?3
+                        ))
+                    ))
+                 RETURNING audio_path",
+            )
+            .and_then(|mut statement| {
+                statement
+                    .query_map(
+                        params![
+                            retention.enabled,
+                            cutoff,
+                            retention.max_entries,
+                            trash_cutoff
+                        ],
+                        |row| row.get::<_, Option<String>>(0),
+                    )?
+                    .collect::<rusqlite::Result<Vec<_>>>()
+            });
+
+        match result {
+            Ok(audio_paths) => {
+                for path in audio_paths.into_iter().flatten() {
+                    let _ = std::fs::remove_file(path);
+                }
+            }
+            Err(e) => log!("Failed to apply history retention: {}", e),
+        }
+    }
+
+    /// Attach a saved recording to entry `id`
+    pub fn set_audio_path(&mut self, id: i64, path: &Path) -> Result<(), String> {
+        self.conn
+            .execute(
+                "UPDATE entries SET audio_path = ?1 WHERE id = ?2",
+                params![path.to_string_lossy(), id],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Forget the recordings of entries whose audio was pruned
+    pub fn clear_audio_paths(&mut self, ids: &[i64]) {
+        for id in ids {
+            if let Err(e) = self.conn.execute(
+                "UPDATE entries SET audio_path = NULL WHERE id = ?1",
+                params![id],
+            ) {
+                log!("Failed to update history entry {}: {}", id, e);
+            }
+        }
+    }
+
+    /// Pin or unpin entry `id`
+    pub fn set_pinned(&mut self, id: i64, pinned: bool) -> Result<(), String> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE entries SET pinned = ?1 WHERE id = ?2",
+                params![pinned, id],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("No history entry with id {}", id));
+        }
+        Ok(())
+    }
+
+    /// The most recent `limit` entries, pinned first, then newest first
+    pub fn recent(&self, limit: usize) -> Result<Vec<TranscriptionLog>, String> {
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT * FROM entries WHERE deleted_at IS NULL
+                 ORDER BY pinned DESC, id DESC LIMIT ?1",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map(params![limit as i64], TranscriptionLog::from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())
+    }
+
+    /// Entries matching every word of `query` in their raw or processed
+    /// text, pinned first, then newest first. An empty query matches
+    /// everything; `app` matches the executable name case-insensitively.
+    pub fn search(
+        &self,
+        query: &str,
+        date_range: &DateRange,
+        app: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<TranscriptionLog>, String> {
+        self.search_from(query, date_range, app, 0, limit)
+    }
+
+    fn search_from(
+        &self,
+        query: &str,
+        date_range: &DateRange,
+        app: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<TranscriptionLog>, String> {
+        let fts = fts_query(query);
+        let mut statement = self
+            .conn
+            .prepare(&format!(
+                "SELECT * FROM entries {} ORDER BY pinned DESC, id DESC LIMIT ?5 OFFSET ?6",
+                FILTER_SQL
+            ))
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map(
+                params![
+                    fts,
+                    date_range.from,
+                    date_range.to,
+                    app,
+                    limit as i64,
+                    offset as i64
+                ],
+                TranscriptionLog::from_row,
+            )
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())
+    }
+
+    /// One page of the entries matching `filter`, ordered like `search`
+    pub fn page(
+        &self,
+        filter: &HistoryFilter,
+        offset: usize,
+        limit: usize,
+    ) -> Result<HistoryPage, String> {
+        let app = filter.app.as_deref().filter(|app| !app.is_empty());
+        let entries = self.search_from(&filter.query, &filter.date_range, app, offset, limit)?;
+        let total: i64 = self
+            .conn
+            .query_row(
+                &format!("SELECT COUNT(*) FROM entries {}", FILTER_SQL),
+                params![
+                    fts_query(&filter.query),
+                    filter.date_range.from,
+                    filter.date_range.to,
+                    app
+                ],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(HistoryPage {
+            entries,
+            offset,
+            total: total as usize,
+        })
+    }
+
+    /// Every entry in `date_range`, oldest first, for exporting
+    pub fn entries_in(&self, date_range: &DateRange) -> Result<Vec<TranscriptionLog>, String> {
+        let mut entries = self.search("", date_range, None, i64::MAX as usize)?;
+        entries.sort_by_key(|entry| entry.id);
+        Ok(entries)
+    }
 
-        // Keep only the most recent entries
-        if self.entries.len() > MAX_HISTORY_ENTRIES {
-            self.entries.truncate(MAX_HISTORY_ENTRIES);
+    /// Word counts and durations of every entry outside the trash, oldest
+    /// first
+    pub fn samples(&self) -> Result<Vec<DictationSample>, String> {
+        let mut statement = self
+            .conn
+            .prepare(
+                "SELECT timestamp, word_count, duration_ms FROM entries
+                 WHERE deleted_at IS NULL ORDER BY id",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(DictationSample {
+                    timestamp: row.get(0)?,
+                    word_count: row.get(1)?,
+                    duration_ms: row.get(2)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())
+    }
+
+    /// The newest `limit` entries, pinned or not
+    pub fn newest(&self, limit: usize) -> Result<Vec<TranscriptionLog>, String> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT * FROM entries WHERE deleted_at IS NULL ORDER BY id DESC LIMIT ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map(params![limit as i64], TranscriptionLog::from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())
+    }
+
+    /// The newest entry, pinned or not
+    pub fn latest(&self) -> Option<TranscriptionLog> {
+        self.newest(1).ok()?.into_iter().next()
+    }
+
+    /// The entry with `id`, if it still exists
+    pub fn get(&self, id: i64) -> Option<TranscriptionLog> {
+        self.conn
+            .query_row(
+                "SELECT * FROM entries WHERE id = ?1",
+                params![id],
+                TranscriptionLog::from_row,
+            )
+            .ok()
+    }
+
+    /// Move entry `id` to the trash, where it's kept for the retention
+    /// policy's `trash_days` before being deleted for good
+    pub fn trash(&mut self, id: i64) -> Result<(), String> {
+        let now = chrono::Local::now().to_rfc3339();
+        self.set_deleted_at(id, Some(&now))
+    }
+
+    /// Bring entry `id` back from the trash
+    pub fn restore(&mut self, id: i64) -> Result<(), String> {
+        self.set_deleted_at(id, None)
+    }
+
+    fn set_deleted_at(&mut self, id: i64, deleted_at: Option<&str>) -> Result<(), String> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE entries SET deleted_at = ?1 WHERE id = ?2",
+                params![deleted_at, id],
+            )
+            .map_err(|e| e.to_string())?;
+        if changed == 0 {
+            return Err(format!("No history entry with id {}", id));
         }
+        Ok(())
+    }
+
+    /// Entries in the trash, most recently deleted first
+    pub fn trashed(&self) -> Result<Vec<TranscriptionLog>, String> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT * FROM entries WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC")
+            .map_err(|e| e.to_string())?;
+        let rows = statement
+            .query_map([], TranscriptionLog::from_row)
+            .map_err(|e| e.to_string())?;
+        rows.collect::<rusqlite::Result<_>>()
+            .map_err(|e| e.to_string())
+    }
 
-        // Auto-save after adding
-        let _ = self.save();
+    /// Delete every entry, trash and pins included, zeroing the freed pages
+    /// so the text can't be recovered from the database file
+    pub fn wipe(&mut self) -> Result<(), String> {
+        let conn = &self.conn;
+        conn.query_row("PRAGMA secure_delete = ON", [], |_| Ok(()))
+            .map_err(|e| e.to_string())?;
+        conn.execute("DELETE FROM entries", [])
+            .map_err(|e| e.to_string())?;
+        conn.execute("VACUUM", []).map_err(|e| e.to_string())?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .map_err(|e| e.to_string())?;
+        Ok(())
     }
 
     /// Clear all history entries
     pub fn clear(&mut self) {
-        self.entries.clear();
-        let _ = self.save();
+        if let Err(e) = self.conn.execute("DELETE FROM entries", []) {
+            log!("Failed to clear history: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_come_back_newest_first() {
+        let mut history = TranscriptionHistory::in_memory();
+        history.add_entry("one".into(), "One.".into(), EntryMetadata::default());
+        history.add_entry("two".into(), "Two.".into(), EntryMetadata::default());
+
+        let entries = history.recent(10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].processed_text, "Two.");
+        assert_eq!(history.latest().unwrap().raw_text, "two");
+        assert_eq!(history.recent(1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn session_metadata_is_stored_with_the_entry() {
+        let mut history = TranscriptionHistory::in_memory();
+        history.add_entry(
+            "hello there".into(),
+            "Hello there.".into(),
+            EntryMetadata {
+                app: Some("Code.exe".into()),
+                duration_ms: Some(2500),
+                language: Some("en".into()),
+                backend: Some("whisper.example.com".into()),
+                latency_ms: Some(800),
+            },
+        );
+
+        let entry = history.latest().unwrap();
+        assert_eq!(entry.word_count, 2);
+        assert_eq!(entry.duration_ms, Some(2500));
+        assert_eq!(entry.language.as_deref(), Some("en"));
+        assert_eq!(entry.backend.as_deref(), Some("whisper.example.com"));
+        assert_eq!(entry.latency_ms, Some(800));
+    }
+
+    #[test]
+    fn deleted_entries_go_to_the_trash_until_restored() {
+        let mut history = TranscriptionHistory::in_memory();
+        let id = history
+            .add_entry("one".into(), "One.".into(), EntryMetadata::default())
+            .unwrap();
+        assert_eq!(history.get(id).unwrap().processed_text, "One.");
+
+        history.trash(id).unwrap();
+        assert!(history.latest().is_none());
+        assert_eq!(history.trashed().unwrap()[0].id, id);
+
+        history.restore(id).unwrap();
+        assert_eq!(history.latest().unwrap().id, id);
+        assert!(history.trashed().unwrap().is_empty());
+        assert!(history.trash(99).is_err());
+    }
+
+    #[test]
+    fn trash_is_emptied_after_its_retention() {
+        let mut history = TranscriptionHistory::in_memory();
+        let id = history
+            .add_entry("one".into(), "One.".into(), EntryMetadata::default())
+            .unwrap();
+        history.trash(id).unwrap();
+        history
+            .conn
+            .execute(
+                "UPDATE entries SET deleted_at = '2000-01-01T00:00:00+00:00'",
+                [],
+            )
+            .unwrap();
+
+        history.set_retention(HistoryRetention::default());
+        assert!(history.get(id).is_none());
+    }
+
+    #[test]
+    fn previews_fit_on_one_short_line() {
+        assert_eq!(preview("Short\nnote.", 20), "Short note.");
+        assert_eq!(preview("A much longer dictation", 10), "A much lo…");
+    }
+
+    #[test]
+    fn repeated_dictations_are_counted_not_duplicated() {
+        let mut history = TranscriptionHistory::in_memory();
+        let first = history.add_entry("hi".into(), "Hi.".into(), EntryMetadata::default());
+        let again = history.add_entry("hi".into(), "Hi.".into(), EntryMetadata::default());
+        assert_eq!(first, again);
+        assert_eq!(history.latest().unwrap().repeat_count, 2);
+
+        history.add_entry("bye".into(), "Bye.".into(), EntryMetadata::default());
+        history.add_entry("hi".into(), "Hi.".into(), EntryMetadata::default());
+        assert_eq!(history.newest(10).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn wipe_removes_pinned_and_trashed_entries() {
+        let mut history = TranscriptionHistory::in_memory();
+        let pinned = history
+            .add_entry("one".into(), "One.".into(), EntryMetadata::default())
+            .unwrap();
+        history.set_pinned(pinned, true).unwrap();
+        let trashed = history
+            .add_entry("two".into(), "Two.".into(), EntryMetadata::default())
+            .unwrap();
+        history.trash(trashed).unwrap();
+
+        history.wipe().unwrap();
+        assert!(history.get(pinned).is_none());
+        assert!(history.get(trashed).is_none());
+    }
+
+    #[test]
+    fn pages_walk_through_filtered_history() {
+        let mut history = TranscriptionHistory::in_memory();
+        for text in ["alpha one", "beta", "alpha two", "alpha three"] {
+            history.add_entry(text.into(), text.into(), EntryMetadata::default());
+        }
+        let filter = HistoryFilter {
+            query: "alpha".into(),
+            ..HistoryFilter::default()
+        };
+
+        let first = history.page(&filter, 0, 2).unwrap();
+        assert_eq!(first.total, 3);
+        assert_eq!(first.entries[0].raw_text, "alpha three");
+        let second = history.page(&filter, 2, 2).unwrap();
+        assert_eq!(second.entries.len(), 1);
+        assert_eq!(second.entries[0].raw_text, "alpha one");
+    }
+
+    #[test]
+    fn clear_removes_everything() {
+        let mut history = TranscriptionHistory::in_memory();
+        history.add_entry("one".into(), "One.".into(), EntryMetadata::default());
+        history.clear();
+        assert!(history.latest().is_none());
+    }
+
+    #[test]
+    fn search_matches_words_app_and_dates() {
+        let mut history = TranscriptionHistory::in_memory();
+        history.add_entry(
+            "send it to 42 baker street".into(),
+            "Send it to 42 Baker Street.".into(),
+            EntryMetadata {
+                app: Some("Outlook.exe".into()),
+                ..EntryMetadata::default()
+            },
+        );
+        history.add_entry(
+            "call bob".into(),
+            "Call Bob.".into(),
+            EntryMetadata::default(),
+        );
+        let all = DateRange::default();
+
+        let found = history.search("bak street", &all, None, 10).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].app.as_deref(), Some("Outlook.exe"));
+
+        assert_eq!(history.search("", &all, None, 10).unwrap().len(), 2);
+        assert_eq!(
+            history
+                .search("", &all, Some("outlook.exe"), 10)
+                .unwrap()
+                .len(),
+            1
+        );
+        // Quotes and FTS operators are treated as plain text
+        assert!(history.search("\"bob OR", &all, None, 10).is_ok());
+
+        let future = DateRange {
+            from: Some("9999-01-01".into()),
+            to: None,
+        };
+        assert!(history.search("", &future, None, 10).unwrap().is_empty());
+    }
+
+    fn log(id: i64, timestamp: &str, text: &str, app: Option<&str>) -> TranscriptionLog {
+        TranscriptionLog {
+            id,
+            timestamp: timestamp.to_string(),
+            raw_text: text.to_lowercase(),
+            processed_text: text.to_string(),
+            app: app.map(str::to_string),
+            audio_path: None,
+            pinned: false,
+            duration_ms: None,
+            word_count: text.split_whitespace().count() as u32,
+            language: None,
+            backend: None,
+            latency_ms: None,
+            deleted_at: None,
+            repeat_count: 1,
+        }
+    }
+
+    #[test]
+    fn csv_export_quotes_awkward_fields() {
+        let entries = [log(1, "2026-03-01T09:15:00+00:00", "Hi, \"you\"", None)];
+        let csv = render_export(&entries, ExportFormat::Csv).unwrap();
+        assert_eq!(
+            csv,
+            "id,timestamp,app,raw_text,processed_text,duration_ms,word_count,language,\
+             backend,latency_ms\n\
+             1,2026-03-01T09:15:00+00:00,,\"hi, \"\"you\"\"\",\"Hi, \"\"you\"\"\",,2,,,\n"
+        );
+    }
+
+    #[test]
+    fn markdown_export_groups_by_day() {
+        let entries = [
+            log(1, "2026-03-01T09:15:00+00:00", "First.", Some("Code.exe")),
+            log(2, "2026-03-01T17:40:00+00:00", "Second.", None),
+            log(3, "2026-03-02T08:00:00+00:00", "Third.", None),
+        ];
+        let markdown = render_export(&entries, ExportFormat::Markdown).unwrap();
+        assert_eq!(markdown.matches("## 2026-03-01").count(), 1);
+        assert!(markdown.contains("### 09:15 · Code.exe\n\nFirst.\n"));
+        assert!(markdown.contains("## 2026-03-02\n\n### 08:00\n\nThird.\n"));
+    }
+
+    #[test]
+    fn retention_keeps_the_newest_unpinned_entries() {
+        let mut history = TranscriptionHistory::in_memory();
+        let pinned = history
+            .add_entry("one".into(), "One.".into(), EntryMetadata::default())
+            .unwrap();
+        history.set_pinned(pinned, true).unwrap();
+        for text in ["two", "three", "four"] {
+            history.add_entry(text.into(), text.into(), EntryMetadata::default());
+        }
+
+        history.set_retention(HistoryRetention {
+            max_entries: 2,
+            ..HistoryRetention::default()
+        });
+        let kept: Vec<_> = history
+            .recent(10)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.raw_text)
+            .collect();
+        assert_eq!(kept, ["one", "four", "three"]);
+    }
+
+    #[test]
+    fn disabled_history_keeps_nothing() {
+        let mut history = TranscriptionHistory::in_memory();
+        history.add_entry("one".into(), "One.".into(), EntryMetadata::default());
+        history.set_retention(HistoryRetention {
+            enabled: false,
+            ..HistoryRetention::default()
+        });
+        assert!(history.latest().is_none());
+        assert!(history
+            .add_entry("two".into(), "Two.".into(), EntryMetadata::default())
+            .is_none());
+        assert!(history.latest().is_none());
+    }
+
+    #[test]
+    fn pinned_entries_sort_to_the_top() {
+        let mut history = TranscriptionHistory::in_memory();
+        let first = history
+            .add_entry("one".into(), "One.".into(), EntryMetadata::default())
+            .unwrap();
+        history.add_entry("two".into(), "Two.".into(), EntryMetadata::default());
+        history.set_pinned(first, true).unwrap();
+
+        let entries = history.recent(10).unwrap();
+        assert_eq!(entries[0].id, first);
+        assert!(entries[0].pinned);
+        // Re-paste still picks the newest dictation
+        assert_eq!(history.latest().unwrap().raw_text, "two");
+
+        history.set_pinned(first, false).unwrap();
+        assert_eq!(history.recent(10).unwrap()[0].raw_text, "two");
+        assert!(history.set_pinned(99, true).is_err());
+    }
+
+    #[test]
+    fn audio_paths_can_be_attached_and_cleared() {
+        let mut history = TranscriptionHistory::in_memory();
+        let id = history
+            .add_entry("one".into(), "One.".into(), EntryMetadata::default())
+            .unwrap();
+        history.set_audio_path(id, Path::new("1.wav")).unwrap();
+        assert_eq!(
+            history.latest().unwrap().audio_path.as_deref(),
+            Some("1.wav")
+        );
+
+        history.clear_audio_paths(&[id]);
+        assert!(history.latest().unwrap().audio_path.is_none());
+    }
+
+    #[test]
+    fn migrations_are_not_reapplied() {
+        let conn = Connection::open_in_memory().unwrap();
+        let history = TranscriptionHistory::from_connection(conn).unwrap();
+        let conn = history.conn;
+        assert!(TranscriptionHistory::from_connection(conn).is_ok());
     }
 }
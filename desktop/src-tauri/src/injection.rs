@@ -0,0 +1,378 @@
+//! Text injection backends for delivering transcripts to the focused app
+
+use crate::settings::PasteChord;
+use enigo::Key;
+
+/// The modifier used by the platform's standard paste shortcut
+#[cfg(target_os = "macos")]
+const PRIMARY_MODIFIER: Key = Key::Meta;
+#[cfg(not(target_os = "macos"))]
+const PRIMARY_MODIFIER: Key = Key::Control;
+
+/// Letter keys sent by virtual-key code on Windows. Shortcuts are matched on
+/// virtual keys, so this works on any layout, while `Key::Unicode` goes
+/// through the active layout and fails on ones without a Latin `v`.
+#[cfg(target_os = "windows")]
+const KEY_V: Key = Key::Other(0x56);
+#[cfg(target_os = "windows")]
+const KEY_Z: Key = Key::Other(0x5A);
+#[cfg(not(target_os = "windows"))]
+const KEY_V: Key = Key::Unicode('v');
+#[cfg(not(target_os = "windows"))]
+const KEY_Z: Key = Key::Unicode('z');
+
+/// Sends the given paste chord to the focused window.
+pub fn paste_text(chord: PasteChord) -> Result<(), String> {
+    use enigo::{Direction, Enigo, Keyboard, Settings};
+
+    if send_paste_message() || send_paste_command(chord) {
+        return Ok(());
+    }
+
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
+
+    let (modifiers, key) = match chord {
+        PasteChord::CtrlV => (vec![PRIMARY_MODIFIER], KEY_V),
+        PasteChord::CtrlShiftV => (vec![PRIMARY_MODIFIER, Key::Shift], KEY_V),
+        #[cfg(not(target_os = "macos"))]
+        PasteChord::ShiftInsert => (vec![Key::Shift], Key::Insert),
+        #[cfg(target_os = "macos")]
+        PasteChord::ShiftInsert => (vec![PRIMARY_MODIFIER], KEY_V),
+    };
+
+    for modifier in &modifiers {
+        enigo
+            .key(*modifier, Direction::Press)
+            .map_err(|e| format!("Failed to press {:?}: {}", modifier, e))?;
+    }
+    let clicked = enigo
+        .key(key, Direction::Click)
+        .map_err(|e| format!("Failed to press {:?}: {}", key, e));
+    for modifier in modifiers.iter().rev() {
+        enigo
+            .key(*modifier, Direction::Release)
+            .map_err(|e| format!("Failed to release {:?}: {}", modifier, e))?;
+    }
+
+    clicked
+}
+
+/// Sends `WM_PASTE` to a focused native edit control, which pastes without
+/// involving the keyboard at all. Returns false when that isn't possible.
+#[cfg(target_os = "windows")]
+fn send_paste_message() -> bool {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        SendMessageTimeoutW, SMTO_ABORTIFHUNG, WM_PASTE,
+    };
+
+    let Some(hwnd) = crate::target::focused_edit_control() else {
+        return false;
+    };
+    let mut result = 0usize;
+    unsafe { SendMessageTimeoutW(hwnd, WM_PASTE, 0, 0, SMTO_ABORTIFHUNG, 200, &mut result) != 0 }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn send_paste_message() -> bool {
+    false
+}
+
+/// Sends the chord through `wtype` on Wayland, where compositors ignore
+/// enigo's synthetic input, or `xdotool` on X11. Returns false when the tool
+/// isn't installed or fails, leaving enigo to try.
+#[cfg(target_os = "linux")]
+fn send_paste_command(chord: PasteChord) -> bool {
+    let status = if is_wayland() {
+        let args: &[&str] = match chord {
+            PasteChord::CtrlV => &["-M", "ctrl", "v", "-m", "ctrl"],
+            PasteChord::CtrlShiftV => &[
+                "-M", "ctrl", "-M", "shift", "v", "-m", "shift", "-m", "ctrl",
+            ],
+            PasteChord::ShiftInsert => &["-M", "shift", "-k", "Insert", "-m", "shift"],
+        };
+        std::process::Command::new("wtype").args(args).status()
+    } else {
+        let keys = match chord {
+            PasteChord::CtrlV => "ctrl+v",
+            PasteChord::CtrlShiftV => "ctrl+shift+v",
+            PasteChord::ShiftInsert => "shift+Insert",
+        };
+        std::process::Command::new("xdotool")
+            .args(["key", "--clearmodifiers", keys])
+            .status()
+    };
+    matches!(status, Ok(status) if status.success())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_paste_command(_chord: PasteChord) -> bool {
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn is_wayland() -> bool {
+    std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Presses Backspace `count` times to erase freshly inserted text.
+pub fn send_backspaces(count: usize) -> Result<(), String> {
+    use enigo::{Direction, Enigo, Keyboard, Settings};
+
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
+
+    for _ in 0..count {
+        enigo
+            .key(Key::Backspace, Direction::Click)
+            .map_err(|e| format!("Failed to press Backspace: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Sends the platform undo shortcut (Ctrl+Z, or Cmd+Z on macOS).
+pub fn send_undo() -> Result<(), String> {
+    use enigo::{Direction, Enigo, Keyboard, Settings};
+
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
+
+    enigo
+        .key(PRIMARY_MODIFIER, Direction::Press)
+        .map_err(|e| format!("Failed to press {:?}: {}", PRIMARY_MODIFIER, e))?;
+    let clicked = enigo
+        .key(KEY_Z, Direction::Click)
+        .map_err(|e| format!("Failed to press Z: {}", e));
+    enigo
+        .key(PRIMARY_MODIFIER, Direction::Release)
+        .map_err(|e| format!("Failed to release {:?}: {}", PRIMARY_MODIFIER, e))?;
+
+    clicked
+}
+
+/// Types the text character-by-character for apps that ignore the paste chord.
+pub fn type_text(text: &str) -> Result<(), String> {
+    use enigo::{Enigo, Keyboard, Settings};
+
+    #[cfg(target_os = "linux")]
+    if is_wayland()
+        && matches!(
+            std::process::Command::new("wtype").args(["--", text]).status(),
+            Ok(status) if status.success()
+        )
+    {
+        return Ok(());
+    }
+
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
+
+    enigo
+        .text(text)
+        .map_err(|e| format!("Failed to type text: {}", e))
+}
+
+/// Types the text at a steady `chars_per_second` so apps that drop bursts of
+/// input keep up. A rate of 0 types as fast as possible.
+pub fn type_text_paced(text: &str, chars_per_second: u32) -> Result<(), String> {
+    use enigo::{Enigo, Keyboard, Settings};
+
+    if chars_per_second == 0 {
+        return type_text(text);
+    }
+
+    let mut enigo =
+        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
+    let interval = std::time::Duration::from_secs_f64(1.0 / chars_per_second as f64);
+    let mut buf = [0u8; 4];
+
+    for ch in text.chars() {
+        enigo
+            .text(ch.encode_utf8(&mut buf))
+            .map_err(|e| format!("Failed to type text: {}", e))?;
+        std::thread::sleep(interval);
+    }
+
+    Ok(())
+}
+
+/// Injects the text as Unicode key events via `SendInput`, bypassing both the
+/// clipboard and the active keyboard layout.
+#[cfg(target_os = "windows")]
+pub fn send_unicode_text(text: &str) -> Result<(), String> {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+        VK_RETURN,
+    };
+
+    fn key_input(vk: u16, scan: u16, flags: u32) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: vk,
+                    wScan: scan,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
+
+    let mut inputs: Vec<INPUT> = Vec::with_capacity(text.len() * 2);
+    for ch in text.chars() {
+        match ch {
+            '\r' => continue,
+            // Unicode events for newline are ignored by most edit controls
+            '\n' => {
+                inputs.push(key_input(VK_RETURN, 0, 0));
+                inputs.push(key_input(VK_RETURN, 0, KEYEVENTF_KEYUP));
+            }
+            _ => {
+                let mut units = [0u16; 2];
+                for &unit in ch.encode_utf16(&mut units).iter() {
+                    inputs.push(key_input(0, unit, KEYEVENTF_UNICODE));
+                    inputs.push(key_input(0, unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP));
+                }
+            }
+        }
+    }
+
+    if inputs.is_empty() {
+        return Ok(());
+    }
+
+    let sent = unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        )
+    };
+
+    if sent as usize != inputs.len() {
+        return Err(format!(
+            "SendInput injected {} of {} events (input may be blocked)",
+            sent,
+            inputs.len()
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn send_unicode_text(text: &str) -> Result<(), String> {
+    type_text(text)
+}
+
+/// Writes the text straight into the input buffer of the console that owns
+/// `hwnd`, bypassing conhost's unreliable paste handling.
+#[cfg(target_os = "windows")]
+pub fn write_console_input(hwnd: isize, text: &str) -> Result<(), String> {
+    use windows_sys::Win32::Foundation::{
+        CloseHandle, GENERIC_READ, GENERIC_WRITE, INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+    use windows_sys::Win32::System::Console::{
+        AttachConsole, FreeConsole, WriteConsoleInputW, INPUT_RECORD, INPUT_RECORD_0, KEY_EVENT,
+        KEY_EVENT_RECORD, KEY_EVENT_RECORD_0,
+    };
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::VK_RETURN;
+    use windows_sys::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    fn key_record(key_down: bool, vk: u16, unit: u16) -> INPUT_RECORD {
+        INPUT_RECORD {
+            EventType: KEY_EVENT as u16,
+            Event: INPUT_RECORD_0 {
+                KeyEvent: KEY_EVENT_RECORD {
+                    bKeyDown: key_down as i32,
+                    wRepeatCount: 1,
+                    wVirtualKeyCode: vk,
+                    wVirtualScanCode: 0,
+                    uChar: KEY_EVENT_RECORD_0 { UnicodeChar: unit },
+                    dwControlKeyState: 0,
+                },
+            },
+        }
+    }
+
+    let mut records: Vec<INPUT_RECORD> = Vec::with_capacity(text.len() * 2);
+    for ch in text.chars() {
+        match ch {
+            '\r' => continue,
+            '\n' => {
+                records.push(key_record(true, VK_RETURN, '\r' as u16));
+                records.push(key_record(false, VK_RETURN, '\r' as u16));
+            }
+            _ => {
+                let mut units = [0u16; 2];
+                for &unit in ch.encode_utf16(&mut units).iter() {
+                    records.push(key_record(true, 0, unit));
+                    records.push(key_record(false, 0, unit));
+                }
+            }
+        }
+    }
+
+    if records.is_empty() {
+        return Ok(());
+    }
+
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+    if pid == 0 {
+        return Err("Could not find the console's process".to_string());
+    }
+
+    // A process can only be attached to one console, so detach from our own
+    // (present in dev builds) before attaching to the target's.
+    unsafe {
+        FreeConsole();
+        if AttachConsole(pid) == 0 {
+            return Err("Failed to attach to the target console".to_string());
+        }
+    }
+
+    let conin: Vec<u16> = "CONIN$".encode_utf16().chain(std::iter::once(0)).collect();
+    let result = unsafe {
+        let handle = CreateFileW(
+            conin.as_ptr(),
+            GENERIC_READ | GENERIC_WRITE,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            std::ptr::null(),
+            OPEN_EXISTING,
+            0,
+            0,
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            Err("Failed to open the console input buffer".to_string())
+        } else {
+            let mut written = 0u32;
+            let ok =
+                WriteConsoleInputW(handle, records.as_ptr(), records.len() as u32, &mut written);
+            CloseHandle(handle);
+            if ok == 0 || written as usize != records.len() {
+                Err(format!(
+                    "WriteConsoleInput wrote {} of {} events",
+                    written,
+                    records.len()
+                ))
+            } else {
+                Ok(())
+            }
+        }
+    };
+
+    unsafe { FreeConsole() };
+    result
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn write_console_input(_hwnd: isize, _text: &str) -> Result<(), String> {
+    Err("Console input injection is only supported on Windows".to_string())
+}
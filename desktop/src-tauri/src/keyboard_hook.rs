@@ -0,0 +1,248 @@
+//! Recording hotkeys caught with a low-level keyboard hook, so the trigger
+//! key can be kept from the focused app (e.g. ScrollLock toggling its LED)
+
+#[cfg(target_os = "windows")]
+use std::sync::mpsc::SyncSender;
+#[cfg(target_os = "windows")]
+use std::sync::{Mutex, OnceLock};
+use tauri_plugin_global_shortcut::Shortcut;
+#[cfg(target_os = "windows")]
+use tauri_plugin_global_shortcut::{Code, Modifiers};
+
+/// Hotkeys being watched and the virtual keys currently held down for them
+#[cfg(target_os = "windows")]
+struct Watched {
+    shortcuts: Vec<(Shortcut, u16)>,
+    /// Keys whose press was swallowed, so their release is swallowed too even
+    /// if the modifiers were let go first
+    held: Vec<(Shortcut, u16)>,
+}
+
+#[cfg(target_os = "windows")]
+static WATCHED: Mutex<Watched> = Mutex::new(Watched {
+    shortcuts: Vec::new(),
+    held: Vec::new(),
+});
+/// Forwards key events from the hook thread, which must return quickly
+#[cfg(target_os = "windows")]
+static EVENTS: OnceLock<SyncSender<(Shortcut, bool)>> = OnceLock::new();
+
+/// Watch `shortcuts`, calling `on_event(shortcut, pressed)` on each press
+/// and release. Matching key events are swallowed system-wide. Calling again
+/// replaces the watched shortcuts but keeps the first callback.
+#[cfg(target_os = "windows")]
+pub fn install<F>(shortcuts: &[Shortcut], on_event: F)
+where
+    F: Fn(Shortcut, bool) + Send + 'static,
+{
+    use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, GetMessageW, SetWindowsHookExW, TranslateMessage, MSG, WH_KEYBOARD_LL,
+    };
+
+    let watched: Vec<(Shortcut, u16)> = shortcuts
+        .iter()
+        .filter_map(|shortcut| match virtual_key(shortcut.key) {
+            Some(vk) => Some((*shortcut, vk)),
+            None => {
+                log!("Can't watch {} with the keyboard hook", shortcut);
+                None
+            }
+        })
+        .collect();
+    let empty = watched.is_empty();
+    {
+        let mut state = WATCHED.lock().unwrap();
+        state.shortcuts = watched;
+        state.held.clear();
+    }
+
+    // Once installed, the hook stays; later calls only update the shortcuts
+    if empty || EVENTS.get().is_some() {
+        return;
+    }
+    let (tx, rx) = std::sync::mpsc::sync_channel(16);
+    if EVENTS.set(tx).is_err() {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        for (shortcut, pressed) in rx {
+            on_event(shortcut, pressed);
+        }
+    });
+
+    std::thread::spawn(|| unsafe {
+        let hook = SetWindowsHookExW(
+            WH_KEYBOARD_LL,
+            Some(hook_proc),
+            GetModuleHandleW(std::ptr::null()),
+            0,
+        );
+        if hook == 0 {
+            log!("Failed to install keyboard hook");
+            return;
+        }
+
+        // Low-level hooks are called on this thread, which needs a message loop
+        let mut msg: MSG = std::mem::zeroed();
+        while GetMessageW(&mut msg, 0, 0, 0) > 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    });
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn install<F>(shortcuts: &[Shortcut], _on_event: F)
+where
+    F: Fn(Shortcut, bool) + Send + 'static,
+{
+    if !shortcuts.is_empty() {
+        log!("Swallowing trigger keys is only supported on Windows");
+    }
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn hook_proc(code: i32, wparam: usize, lparam: isize) -> isize {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, HC_ACTION, KBDLLHOOKSTRUCT, LLKHF_INJECTED, WM_KEYDOWN, WM_KEYUP,
+        WM_SYSKEYDOWN, WM_SYSKEYUP,
+    };
+
+    if code == HC_ACTION as i32 {
+        let info = &*(lparam as *const KBDLLHOOKSTRUCT);
+        let message = wparam as u32;
+        let pressed = match message {
+            WM_KEYDOWN | WM_SYSKEYDOWN => Some(true),
+            WM_KEYUP | WM_SYSKEYUP => Some(false),
+            _ => None,
+        };
+
+        if let Some(pressed) = pressed.filter(|_| info.flags & LLKHF_INJECTED == 0) {
+            if let Some((shortcut, first)) = match_key(info.vkCode as u16, pressed) {
+                // Auto-repeats are swallowed without reporting another press
+                if first {
+                    if let Some(tx) = EVENTS.get() {
+                        let _ = tx.try_send((shortcut, pressed));
+                    }
+                }
+                return 1;
+            }
+        }
+    }
+
+    CallNextHookEx(0, code, wparam, lparam)
+}
+
+/// The watched shortcut a key event belongs to, and whether it's the first
+/// event of its kind (not an auto-repeat)
+#[cfg(target_os = "windows")]
+fn match_key(vk: u16, pressed: bool) -> Option<(Shortcut, bool)> {
+    let mut state = WATCHED.lock().unwrap();
+
+    if let Some(index) = state.held.iter().position(|(_, held)| *held == vk) {
+        let shortcut = state.held[index].0;
+        if !pressed {
+            state.held.remove(index);
+        }
+        return Some((shortcut, !pressed));
+    }
+
+    if !pressed {
+        return None;
+    }
+    let modifiers = current_modifiers();
+    let (shortcut, _) = *state
+        .shortcuts
+        .iter()
+        .find(|(shortcut, watched)| *watched == vk && shortcut.mods == modifiers)?;
+    state.held.push((shortcut, vk));
+    Some((shortcut, true))
+}
+
+#[cfg(target_os = "windows")]
+fn current_modifiers() -> Modifiers {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+    };
+
+    let down = |vk: u16| unsafe { GetAsyncKeyState(vk as i32) } < 0;
+    let mut modifiers = Modifiers::empty();
+    if down(VK_CONTROL) {
+        modifiers |= Modifiers::CONTROL;
+    }
+    if down(VK_SHIFT) {
+        modifiers |= Modifiers::SHIFT;
+    }
+    if down(VK_MENU) {
+        modifiers |= Modifiers::ALT;
+    }
+    if down(VK_LWIN) || down(VK_RWIN) {
+        modifiers |= Modifiers::SUPER;
+    }
+    modifiers
+}
+
+/// The Windows virtual key for a key `parse_hotkey` understands
+#[cfg(target_os = "windows")]
+fn virtual_key(code: Code) -> Option<u16> {
+    let name = code.to_string();
+    if let Some(letter) = name.strip_prefix("Key") {
+        return letter.bytes().next().map(u16::from);
+    }
+    if let Some(digit) = name.strip_prefix("Digit") {
+        return digit.bytes().next().map(u16::from);
+    }
+    if let Some(Ok(n)) = name.strip_prefix("Numpad").map(str::parse::<u16>) {
+        return Some(0x60 + n);
+    }
+    if let Some(Ok(n)) = name.strip_prefix('F').map(str::parse::<u16>) {
+        return Some(0x6F + n);
+    }
+
+    Some(match code {
+        Code::Space => 0x20,
+        Code::Enter | Code::NumpadEnter => 0x0D,
+        Code::Tab => 0x09,
+        Code::Escape => 0x1B,
+        Code::ScrollLock => 0x91,
+        Code::PrintScreen => 0x2C,
+        Code::Pause => 0x13,
+        Code::Insert => 0x2D,
+        Code::Delete => 0x2E,
+        Code::Home => 0x24,
+        Code::End => 0x23,
+        Code::PageUp => 0x21,
+        Code::PageDown => 0x22,
+        Code::ArrowLeft => 0x25,
+        Code::ArrowUp => 0x26,
+        Code::ArrowRight => 0x27,
+        Code::ArrowDown => 0x28,
+        Code::NumpadMultiply => 0x6A,
+        Code::NumpadAdd => 0x6B,
+        Code::NumpadSubtract => 0x6D,
+        Code::NumpadDecimal => 0x6E,
+        Code::NumpadDivide => 0x6F,
+        Code::Semicolon => 0xBA,
+        Code::Equal => 0xBB,
+        Code::Comma => 0xBC,
+        Code::Minus => 0xBD,
+        Code::Period => 0xBE,
+        Code::Slash => 0xBF,
+        Code::Backquote => 0xC0,
+        Code::BracketLeft => 0xDB,
+        Code::Backslash => 0xDC,
+        Code::BracketRight => 0xDD,
+        Code::Quote => 0xDE,
+        Code::AudioVolumeMute => 0xAD,
+        Code::AudioVolumeDown => 0xAE,
+        Code::AudioVolumeUp => 0xAF,
+        Code::MediaTrackNext => 0xB0,
+        Code::MediaTrackPrevious => 0xB1,
+        Code::MediaStop => 0xB2,
+        Code::MediaPlayPause => 0xB3,
+        Code::MediaPlay => 0xFA,
+        _ => return None,
+    })
+}
@@ -0,0 +1,264 @@
+//! Hotkey strings: parsing, canonical formatting, and the keys the settings
+//! UI can offer
+
+use crate::settings::Settings;
+use tauri_plugin_global_shortcut::{Code, Modifiers, Shortcut};
+
+/// Every supported key as (canonical name, other accepted names, code).
+/// Names are matched case-insensitively.
+const KEYS: &[(&str, &[&str], Code)] = &[
+    ("Space", &[], Code::Space),
+    ("Enter", &[], Code::Enter),
+    ("Tab", &[], Code::Tab),
+    ("Escape", &["Esc"], Code::Escape),
+    ("ScrollLock", &["Scroll"], Code::ScrollLock),
+    ("PrintScreen", &["Print", "PrtSc"], Code::PrintScreen),
+    ("Pause", &["PauseBreak"], Code::Pause),
+    ("Insert", &["Ins"], Code::Insert),
+    ("Delete", &["Del"], Code::Delete),
+    ("Home", &[], Code::Home),
+    ("End", &[], Code::End),
+    ("PageUp", &["PgUp"], Code::PageUp),
+    ("PageDown", &["PgDn"], Code::PageDown),
+    ("Up", &["ArrowUp"], Code::ArrowUp),
+    ("Down", &["ArrowDown"], Code::ArrowDown),
+    ("Left", &["ArrowLeft"], Code::ArrowLeft),
+    ("Right", &["ArrowRight"], Code::ArrowRight),
+    ("A", &[], Code::KeyA),
+    ("B", &[], Code::KeyB),
+    ("C", &[], Code::KeyC),
+    ("D", &[], Code::KeyD),
+    ("E", &[], Code::KeyE),
+    ("F", &[], Code::KeyF),
+    ("G", &[], Code::KeyG),
+    ("H", &[], Code::KeyH),
+    ("I", &[], Code::KeyI),
+    ("J", &[], Code::KeyJ),
+    ("K", &[], Code::KeyK),
+    ("L", &[], Code::KeyL),
+    ("M", &[], Code::KeyM),
+    ("N", &[], Code::KeyN),
+    ("O", &[], Code::KeyO),
+    ("P", &[], Code::KeyP),
+    ("Q", &[], Code::KeyQ),
+    ("R", &[], Code::KeyR),
+    ("S", &[], Code::KeyS),
+    ("T", &[], Code::KeyT),
+    ("U", &[], Code::KeyU),
+    ("V", &[], Code::KeyV),
+    ("W", &[], Code::KeyW),
+    ("X", &[], Code::KeyX),
+    ("Y", &[], Code::KeyY),
+    ("Z", &[], Code::KeyZ),
+    ("0", &[], Code::Digit0),
+    ("1", &[], Code::Digit1),
+    ("2", &[], Code::Digit2),
+    ("3", &[], Code::Digit3),
+    ("4", &[], Code::Digit4),
+    ("5", &[], Code::Digit5),
+    ("6", &[], Code::Digit6),
+    ("7", &[], Code::Digit7),
+    ("8", &[], Code::Digit8),
+    ("9", &[], Code::Digit9),
+    ("F1", &[], Code::F1),
+    ("F2", &[], Code::F2),
+    ("F3", &[], Code::F3),
+    ("F4", &[], Code::F4),
+    ("F5", &[], Code::F5),
+    ("F6", &[], Code::F6),
+    ("F7", &[], Code::F7),
+    ("F8", &[], Code::F8),
+    ("F9", &[], Code::F9),
+    ("F10", &[], Code::F10),
+    ("F11", &[], Code::F11),
+    ("F12", &[], Code::F12),
+    ("F13", &[], Code::F13),
+    ("F14", &[], Code::F14),
+    ("F15", &[], Code::F15),
+    ("F16", &[], Code::F16),
+    ("F17", &[], Code::F17),
+    ("F18", &[], Code::F18),
+    ("F19", &[], Code::F19),
+    ("F20", &[], Code::F20),
+    ("F21", &[], Code::F21),
+    ("F22", &[], Code::F22),
+    ("F23", &[], Code::F23),
+    ("F24", &[], Code::F24),
+    ("Numpad0", &[], Code::Numpad0),
+    ("Numpad1", &[], Code::Numpad1),
+    ("Numpad2", &[], Code::Numpad2),
+    ("Numpad3", &[], Code::Numpad3),
+    ("Numpad4", &[], Code::Numpad4),
+    ("Numpad5", &[], Code::Numpad5),
+    ("Numpad6", &[], Code::Numpad6),
+    ("Numpad7", &[], Code::Numpad7),
+    ("Numpad8", &[], Code::Numpad8),
+    ("Numpad9", &[], Code::Numpad9),
+    ("NumpadEnter", &[], Code::NumpadEnter),
+    ("NumpadAdd", &[], Code::NumpadAdd),
+    ("NumpadSubtract", &[], Code::NumpadSubtract),
+    ("NumpadMultiply", &[], Code::NumpadMultiply),
+    ("NumpadDivide", &[], Code::NumpadDivide),
+    ("NumpadDecimal", &[], Code::NumpadDecimal),
+    // OEM keys, named by their unshifted US-layout character
+    (";", &[], Code::Semicolon),
+    ("=", &[], Code::Equal),
+    (",", &[], Code::Comma),
+    ("-", &[], Code::Minus),
+    (".", &[], Code::Period),
+    ("/", &[], Code::Slash),
+    ("`", &[], Code::Backquote),
+    ("[", &[], Code::BracketLeft),
+    ("\\", &[], Code::Backslash),
+    ("]", &[], Code::BracketRight),
+    ("'", &[], Code::Quote),
+    ("MediaPlayPause", &["PlayPause"], Code::MediaPlayPause),
+    ("MediaPlay", &["Play"], Code::MediaPlay),
+    ("MediaStop", &["Stop"], Code::MediaStop),
+    (
+        "MediaTrackNext",
+        &["MediaNext", "NextTrack"],
+        Code::MediaTrackNext,
+    ),
+    (
+        "MediaTrackPrevious",
+        &["MediaPrev", "PrevTrack"],
+        Code::MediaTrackPrevious,
+    ),
+    ("AudioVolumeUp", &["VolumeUp"], Code::AudioVolumeUp),
+    ("AudioVolumeDown", &["VolumeDown"], Code::AudioVolumeDown),
+    (
+        "AudioVolumeMute",
+        &["VolumeMute", "Mute"],
+        Code::AudioVolumeMute,
+    ),
+];
+
+/// Parse a hotkey string like `Ctrl+Shift+Space`. Unknown parts are ignored;
+/// `None` means no key was found.
+pub fn parse_hotkey(hotkey_str: &str) -> Option<Shortcut> {
+    let mut modifiers = Modifiers::empty();
+    let mut key_code = None;
+
+    for part in hotkey_str.split('+').map(|s| s.trim()) {
+        match part.to_uppercase().as_str() {
+            "CTRL" | "CONTROL" => modifiers |= Modifiers::CONTROL,
+            "SHIFT" => modifiers |= Modifiers::SHIFT,
+            "ALT" => modifiers |= Modifiers::ALT,
+            "WIN" | "SUPER" | "META" => modifiers |= Modifiers::SUPER,
+            _ => {
+                if let Some(code) = parse_key(part) {
+                    key_code = Some(code);
+                }
+            }
+        }
+    }
+
+    key_code.map(|code| Shortcut::new(Some(modifiers), code))
+}
+
+fn parse_key(name: &str) -> Option<Code> {
+    KEYS.iter()
+        .find(|(canonical, aliases, _)| {
+            canonical.eq_ignore_ascii_case(name)
+                || aliases.iter().any(|alias| alias.eq_ignore_ascii_case(name))
+        })
+        .map(|(_, _, code)| *code)
+}
+
+/// Format a shortcut in the canonical form `parse_hotkey` accepts, e.g.
+/// `Ctrl+Shift+Numpad5`, so stored hotkeys round-trip cleanly.
+pub fn format_hotkey(shortcut: &Shortcut) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    if shortcut.mods.contains(Modifiers::CONTROL) {
+        parts.push("Ctrl".to_string());
+    }
+    if shortcut.mods.contains(Modifiers::SHIFT) {
+        parts.push("Shift".to_string());
+    }
+    if shortcut.mods.contains(Modifiers::ALT) {
+        parts.push("Alt".to_string());
+    }
+    if shortcut.mods.contains(Modifiers::SUPER) {
+        parts.push("Win".to_string());
+    }
+    let key = KEYS
+        .iter()
+        .find(|(_, _, code)| *code == shortcut.key)
+        .map(|(canonical, _, _)| canonical.to_string())
+        .unwrap_or_else(|| shortcut.key.to_string());
+    parts.push(key);
+    parts.join("+")
+}
+
+/// Canonical names of every key a hotkey can use, for the settings UI
+pub fn supported_keys() -> Vec<&'static str> {
+    KEYS.iter().map(|(canonical, _, _)| *canonical).collect()
+}
+
+/// Rewrite every configured hotkey in canonical form. Mouse triggers and
+/// strings that don't parse are left alone for validation to report.
+pub fn canonicalize_hotkeys(settings: &mut Settings) {
+    let canonical = |hotkey: &mut String| {
+        if let Some(shortcut) = parse_hotkey(hotkey) {
+            *hotkey = format_hotkey(&shortcut);
+        }
+    };
+    canonical(&mut settings.hotkey);
+    canonical(&mut settings.undo_hotkey);
+    canonical(&mut settings.repaste_hotkey);
+    canonical(&mut settings.pause_hotkey);
+    canonical(&mut settings.cancel_hotkey);
+    for paste in &mut settings.history_paste_hotkeys {
+        canonical(&mut paste.hotkey);
+    }
+    for binding in &mut settings.hotkey_bindings {
+        canonical(&mut binding.hotkey);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_aliases_case_insensitively() {
+        let expected = Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SUPER), Code::PageDown);
+        assert_eq!(parse_hotkey("control+meta+pgdn"), Some(expected));
+        assert_eq!(parse_hotkey("Ctrl + Win + PageDown"), Some(expected));
+        assert_eq!(
+            parse_hotkey("prtsc"),
+            Some(Shortcut::new(None, Code::PrintScreen))
+        );
+        assert_eq!(
+            parse_hotkey("Mute"),
+            Some(Shortcut::new(None, Code::AudioVolumeMute))
+        );
+    }
+
+    #[test]
+    fn parses_numpad_oem_and_extended_keys() {
+        assert_eq!(parse_hotkey("Numpad5").map(|s| s.key), Some(Code::Numpad5));
+        assert_eq!(parse_hotkey("5").map(|s| s.key), Some(Code::Digit5));
+        assert_eq!(parse_hotkey("Ctrl+;").map(|s| s.key), Some(Code::Semicolon));
+        assert_eq!(parse_hotkey("\\").map(|s| s.key), Some(Code::Backslash));
+        assert_eq!(parse_hotkey("F24").map(|s| s.key), Some(Code::F24));
+    }
+
+    #[test]
+    fn rejects_modifier_only_hotkeys() {
+        assert_eq!(parse_hotkey("Ctrl+Shift"), None);
+        assert_eq!(parse_hotkey(""), None);
+    }
+
+    #[test]
+    fn formats_canonically_and_round_trips() {
+        let shortcut = parse_hotkey("shift+ctrl+esc").unwrap();
+        assert_eq!(format_hotkey(&shortcut), "Ctrl+Shift+Escape");
+
+        for name in supported_keys() {
+            let shortcut = parse_hotkey(&format!("Alt+{}", name)).unwrap();
+            assert_eq!(format_hotkey(&shortcut), format!("Alt+{}", name));
+        }
+    }
+}
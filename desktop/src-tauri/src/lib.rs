@@ -1,34 +1,113 @@
 //! Windows Whisper - Library exports
 
+// First, so every module below can use `log!`
+#[macro_use]
+mod logs;
+
 pub mod audio;
+pub mod audio_file;
+pub mod automation;
+pub mod ax;
+pub mod batch;
+pub mod cli;
 pub mod commands;
+pub mod crash;
+pub mod credentials;
+pub mod diagnostics;
+pub mod dispatch;
+pub mod gestures;
+pub mod hid_trigger;
 pub mod history;
+pub mod injection;
+pub mod keyboard_hook;
+pub mod keymap;
+pub mod meeting;
+pub mod mouse_hook;
+pub mod output;
+pub mod paths;
+pub mod playback;
+pub mod popup;
 pub mod postprocessing;
+pub mod recordings;
+pub mod redact;
 pub mod settings;
+pub mod statistics;
+pub mod subtitles;
+pub mod target;
+pub mod uia;
+pub mod updates;
+pub mod wipe;
 
 use audio::AudioRecorder;
-use base64::{engine::general_purpose::STANDARD, Engine};
-use history::TranscriptionHistory;
+use history::{EntryMetadata, TranscriptionHistory, TranscriptionLog};
 use image::EncodableLayout;
+use keymap::parse_hotkey;
 use serde::{Deserialize, Serialize};
-use settings::Settings;
+use settings::{
+    CancelTrigger, FocusChangePolicy, HotkeyBinding, OnboardingState, OnboardingStep, OutputMode,
+    OutputTarget, PopupPosition, ReviewHotkeys, Settings, SettingsError, SettingsRecovery,
+    TriggerMode, UndoMethod,
+};
 use std::sync::Mutex;
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::TrayIconBuilder,
-    AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder,
+    AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder, WindowEvent,
 };
-use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 use tokio::sync::{mpsc, watch};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct TranscribeResponse {
     success: bool,
     text: Option<String>,
+    /// Timed segments, when asked for and the server supports them
+    #[serde(default)]
+    segments: Vec<subtitles::Cue>,
     error: Option<String>,
 }
 
+/// Payload of the `paste-fallback` event
+#[derive(Debug, Clone, Serialize)]
+struct PasteFallback {
+    reason: String,
+    fallback: OutputMode,
+    success: bool,
+}
+
+/// What to do with a transcript held for review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewAction {
+    Paste,
+    CopyOnly,
+    Discard,
+}
+
+/// The last transcript inserted into another app, kept for undo
+pub struct LastInsertion {
+    text: String,
+    hwnd: Option<isize>,
+}
+
+/// A transcript held back until the user reviews it
+pub struct PendingReview {
+    raw_text: String,
+    text: String,
+    /// When the review began, telling it apart from later ones
+    started: std::time::Instant,
+    /// Chords registered for answering it, until they time out
+    shortcuts: Vec<(ReviewAction, Shortcut)>,
+}
+
+/// What the popup shows while a transcript waits for review
+#[derive(Clone, Serialize)]
+struct ReviewPrompt {
+    text: String,
+    hotkeys: ReviewHotkeys,
+}
+
 struct ChunkControl {
     stop_tx: watch::Sender<bool>,
     chunk_tx: mpsc::Sender<Vec<u8>>,
@@ -43,17 +122,145 @@ pub struct AppState {
     pub is_recording: Mutex<bool>,
     pub chunk_texts: Mutex<Vec<String>>,
     pub chunk_control: Mutex<Option<ChunkControl>>,
+    /// Window that had focus when the current recording started
+    pub recording_target: Mutex<Option<target::TargetWindow>>,
+    /// Transcript waiting for the user to paste, copy, or discard it
+    pub pending_review: Mutex<Option<PendingReview>>,
+    pub last_insertion: Mutex<Option<LastInsertion>>,
+    pub recording_started: Mutex<Option<std::time::Instant>>,
+    /// Length of the most recently finished recording
+    pub last_recording_duration: Mutex<Option<std::time::Duration>>,
+    /// WAV audio of the most recent recording, kept for its history entry
+    pub last_recording_audio: Mutex<Option<Vec<u8>>>,
+    /// Details of the most recent recording session, for its history entry
+    pub last_session_metadata: Mutex<Option<EntryMetadata>>,
+    /// Profile of the hotkey binding that started the current recording
+    pub session_binding: Mutex<Option<HotkeyBinding>>,
+    /// Cancel shortcut grabbed for the current recording
+    pub cancel_shortcut: Mutex<Option<Shortcut>>,
+    /// A stop press waiting to see if a second press cancels instead
+    pub pending_stop: Mutex<Option<std::time::Instant>>,
+    /// When a recording trigger was last pressed, for debouncing
+    pub last_trigger_press: Mutex<Option<std::time::Instant>>,
+    /// Ids of the recording hotkeys held down right now
+    pub held_hotkeys: Mutex<gestures::HeldHotkeys>,
+    /// Tap / double-tap / long-press state for the main hotkey
+    pub gestures: Mutex<gestures::GestureDetector>,
+    /// Cleared by the pause switch, which releases every recording trigger
+    pub enabled: Mutex<bool>,
+    /// Set when settings.json was corrupted at startup, until it's reported
+    pub settings_recovery: Mutex<Option<SettingsRecovery>>,
+    /// What the tray icon shows about the current dictation
+    pub tray_status: Mutex<TrayStatus>,
+    /// The automation API's listener, while it's switched on
+    pub automation_server: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Moves the popup with the caret while recording in follow-caret mode
+    pub caret_watch: Mutex<Option<uia::CaretWatch>>,
+    /// The meeting being recorded, whose text goes to a file instead of
+    /// being pasted
+    pub meeting: Mutex<Option<meeting::Meeting>>,
+    /// The server turned down the current recording's key or quota
+    pub api_problem: Mutex<Option<ApiProblem>>,
+    /// A history entry's recording being played back
+    pub playback: Mutex<Option<playback::Playback>>,
+}
+
+/// Dictation state shown on the tray icon, so it's visible even when the
+/// popup is hidden or off-screen
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrayStatus {
+    Idle,
+    Recording,
+    Processing,
+    Error(String),
+}
+
+/// A refusal from the server that only the user can fix, so it's raised as
+/// its own notification instead of being logged with other chunk failures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApiProblem {
+    /// HTTP 401 or 403
+    KeyRejected,
+    /// HTTP 429
+    QuotaExceeded,
+}
+
+impl ApiProblem {
+    fn from_status(status: u16) -> Option<Self> {
+        match status {
+            401 | 403 => Some(Self::KeyRejected),
+            429 => Some(Self::QuotaExceeded),
+            _ => None,
+        }
+    }
+
+    /// The problem `error` reports, if it came from `message`
+    fn from_error(error: &str) -> Option<Self> {
+        [Self::KeyRejected, Self::QuotaExceeded]
+            .into_iter()
+            .find(|problem| problem.message() == error)
+    }
+
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::KeyRejected => "API key rejected - open Settings",
+            Self::QuotaExceeded => "API quota reached - open Settings",
+        }
+    }
 }
 
+/// The tray's pause item, kept to reflect pauses from the hotkey or command
+struct PauseMenuItem(CheckMenuItem<tauri::Wry>);
+
+/// The tray's start/stop dictation item, relabelled as recording starts and
+/// stops
+struct DictationMenuItem(MenuItem<tauri::Wry>);
+
+/// The tray's meeting mode item, checked while a meeting is recorded
+struct MeetingMenuItem(CheckMenuItem<tauri::Wry>);
+
+/// The tray's update item, offering to install once an update is found
+struct UpdateMenuItem(MenuItem<tauri::Wry>);
+
+/// The tray's recent transcriptions submenu, rebuilt as history changes
+struct RecentSubmenu(Submenu<tauri::Wry>);
+
+/// The tray's profile switcher, rebuilt when profiles change
+struct ProfileSubmenu(Submenu<tauri::Wry>);
+
 impl Default for AppState {
     fn default() -> Self {
+        let (settings, settings_recovery) = Settings::load();
+        let history = TranscriptionHistory::load(settings.history_retention.clone());
+        let enabled = !settings.paused;
         Self {
             recorder: Mutex::new(None),
-            settings: Mutex::new(Settings::load()),
-            history: Mutex::new(TranscriptionHistory::load()),
+            settings: Mutex::new(settings),
+            history: Mutex::new(history),
             is_recording: Mutex::new(false),
             chunk_texts: Mutex::new(Vec::new()),
             chunk_control: Mutex::new(None),
+            recording_target: Mutex::new(None),
+            pending_review: Mutex::new(None),
+            last_insertion: Mutex::new(None),
+            recording_started: Mutex::new(None),
+            last_recording_duration: Mutex::new(None),
+            last_recording_audio: Mutex::new(None),
+            last_session_metadata: Mutex::new(None),
+            session_binding: Mutex::new(None),
+            cancel_shortcut: Mutex::new(None),
+            pending_stop: Mutex::new(None),
+            last_trigger_press: Mutex::new(None),
+            held_hotkeys: Mutex::new(gestures::HeldHotkeys::default()),
+            gestures: Mutex::new(gestures::GestureDetector::default()),
+            enabled: Mutex::new(enabled),
+            settings_recovery: Mutex::new(settings_recovery),
+            tray_status: Mutex::new(TrayStatus::Idle),
+            automation_server: Mutex::new(None),
+            caret_watch: Mutex::new(None),
+            meeting: Mutex::new(None),
+            api_problem: Mutex::new(None),
+            playback: Mutex::new(None),
         }
     }
 }
@@ -63,23 +270,166 @@ const CHUNK_OVERLAP_SECONDS: u32 = 1;
 const CHUNK_TRIM_WORDS: usize = 3;
 const CHUNK_MAX_OVERLAP_WORDS: usize = 12;
 const CHUNK_MAX_REPEAT_PHRASE_WORDS: usize = 4;
+const CLIPBOARD_RESTORE_DELAY_MS: u64 = 500;
+const CLIPBOARD_POLL_INTERVAL_MS: u64 = 10;
+const PASTE_VERIFY_DELAY_MS: u64 = 150;
+/// Presses of a recording trigger closer together than this are ignored
+const TRIGGER_DEBOUNCE_MS: u64 = 300;
+const QUIET_HOURS_CHECK_SECONDS: u64 = 30;
+const SETTINGS_RECOVERY_NOTICE_DELAY_SECONDS: u64 = 2;
+const SETTINGS_RELOAD_DELAY_MS: u64 = 200;
+/// How long onboarding listens to the microphone
+const MIC_TEST_DURATION: std::time::Duration = std::time::Duration::from_secs(3);
+/// How long the tray keeps showing an error before returning to idle
+const TRAY_ERROR_SECONDS: u64 = 10;
+/// How often the recording time in the tray tooltip is updated
+const TRAY_TIMER_TICK_SECONDS: u64 = 2;
+/// Popup size while it shows a transcript for review, in logical pixels
+const REVIEW_POPUP_SIZE: (u32, u32) = (420, 160);
+/// How long the review shortcuts stay registered; the popup's buttons keep
+/// working after that
+const REVIEW_SHORTCUT_SECONDS: u64 = 15;
+/// Caption bar size, in logical pixels
+const CAPTION_BAR_SIZE: (f64, f64) = (720.0, 96.0);
+/// Gap between the caption bar and the bottom of the screen, in logical pixels
+const CAPTION_BAR_MARGIN: f64 = 48.0;
+const TRAY_ID: &str = "main";
+const TRAY_TOOLTIP: &str = "Windows Whisper - Push to Talk";
+/// Transcriptions listed in the tray's recent submenu
+const RECENT_MENU_ENTRIES: usize = 5;
+const RECENT_MENU_LABEL_CHARS: usize = 40;
+
+/// The transcription endpoint under `api_url`, however it was typed
+fn transcribe_endpoint(api_url: &str) -> String {
+    format!("{}/transcribe", settings::normalize_api_url(api_url))
+}
+
+fn translate_endpoint(api_url: &str) -> String {
+    format!("{}/translate", settings::normalize_api_url(api_url))
+}
+
+/// Translate `text` into `target` with the server. `source` is the
+/// dictation language, or empty when it's detected.
+pub(crate) async fn translate_text(
+    api_url: &str,
+    api_key: &str,
+    text: &str,
+    source: &str,
+    target: &str,
+) -> Result<String, String> {
+    let mut body = serde_json::json!({ "text": text, "target": target });
+    if !source.is_empty() {
+        body["source"] = serde_json::Value::from(source);
+    }
+    let response = reqwest::Client::new()
+        .post(translate_endpoint(api_url))
+        .header("X-API-Key", api_key)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    if let Some(problem) = ApiProblem::from_status(response.status().as_u16()) {
+        return Err(problem.message().to_string());
+    }
+    if response.status().as_u16() == 404 {
+        return Err("The server can't translate. Redeploy the worker to translate.".to_string());
+    }
+
+    let result: TranscribeResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    match (result.success, result.text) {
+        (true, Some(text)) => Ok(text.trim().to_string()),
+        (true, None) => Err("No text in response".to_string()),
+        (false, _) => Err(result.error.unwrap_or_else(|| "Unknown error".to_string())),
+    }
+}
+
+/// Check that `api_url` reaches a transcription server that accepts
+/// `api_key`, without transcribing anything. Returns the normalized URL.
+pub(crate) async fn test_connection(api_url: &str, api_key: &str) -> Result<String, String> {
+    let api_url = settings::normalize_api_url(api_url);
+    if !settings::is_http_url(&api_url) {
+        return Err("Enter the worker URL, e.g. https://your-worker.workers.dev".to_string());
+    }
+
+    // An empty upload is rejected as bad input only once the key is accepted
+    let response = reqwest::Client::new()
+        .post(transcribe_endpoint(&api_url))
+        .header("X-API-Key", api_key)
+        .header("Content-Type", "audio/wav")
+        .body(Vec::new())
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+    let status = response.status().as_u16();
+    if let Some(problem) = ApiProblem::from_status(status) {
+        return Err(problem.message().to_string());
+    }
+    match status {
+        200 | 400 => Ok(api_url),
+        404 => Err("No transcription endpoint at this URL".to_string()),
+        status => Err(format!(
+            "Unexpected response from the server: HTTP {}",
+            status
+        )),
+    }
+}
 
 async fn transcribe_audio_chunk(
     api_url: &str,
     api_key: &str,
-    audio_data: &[u8],
+    language: &str,
+    boosted_terms: &[String],
+    audio_data: Vec<u8>,
 ) -> Result<String, String> {
+    request_transcription(api_url, api_key, language, boosted_terms, audio_data, false)
+        .await?
+        .text
+        .ok_or_else(|| "No text in response".to_string())
+}
+
+/// Send one chunk to the server, asking for timed segments when `timestamps`
+/// is set and for `boosted_terms` to be favored. Only successful responses
+/// are returned.
+///
+/// The WAV is the request body as it is, handed over rather than copied or
+/// base64-encoded into JSON, so a chunk is held once while it uploads.
+async fn request_transcription(
+    api_url: &str,
+    api_key: &str,
+    language: &str,
+    boosted_terms: &[String],
+    audio_data: Vec<u8>,
+    timestamps: bool,
+) -> Result<TranscribeResponse, String> {
     let client = reqwest::Client::new();
-    let audio_base64 = STANDARD.encode(audio_data);
+    let mut query = Vec::new();
+    if !language.is_empty() {
+        query.push(("language", language));
+    }
+    for term in boosted_terms.iter().map(|term| term.trim()) {
+        if !term.is_empty() {
+            query.push(("keyword", term));
+        }
+    }
+    if timestamps {
+        query.push(("timestamps", "1"));
+    }
 
     let response = client
-        .post(format!("{}/transcribe", api_url))
+        .post(transcribe_endpoint(api_url))
+        .query(&query)
         .header("X-API-Key", api_key)
-        .header("Content-Type", "application/json")
-        .json(&serde_json::json!({ "audio": audio_base64 }))
+        .header("Content-Type", "audio/wav")
+        .body(audio_data)
         .send()
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
+    if let Some(problem) = ApiProblem::from_status(response.status().as_u16()) {
+        return Err(problem.message().to_string());
+    }
 
     let result: TranscribeResponse = response
         .json()
@@ -87,7 +437,7 @@ async fn transcribe_audio_chunk(
         .map_err(|e| format!("Failed to parse response: {}", e))?;
 
     if result.success {
-        result.text.ok_or_else(|| "No text in response".to_string())
+        Ok(result)
     } else {
         Err(result.error.unwrap_or_else(|| "Unknown error".to_string()))
     }
@@ -231,32 +581,31 @@ fn consolidate_chunk_texts(chunks: &[String]) -> String {
     collapse_duplicate_words(&merged)
 }
 
+// The recorder is locked only to send it a request, never while the audio
+// thread resamples and encodes the reply, so starting, stopping, and the
+// hotkey don't queue up behind a chunk.
 async fn drain_chunk_from_recorder(app: AppHandle) -> Result<Vec<u8>, String> {
-    tauri::async_runtime::spawn_blocking(move || {
+    let reply = {
         let state = app.state::<AppState>();
-        let mut recorder = state.recorder.lock().unwrap();
-        if let Some(ref mut rec) = *recorder {
-            rec.drain_chunk()
-        } else {
-            Err("No recorder available".to_string())
+        let recorder = state.recorder.lock().unwrap();
+        match recorder.as_ref() {
+            Some(rec) => rec.drain_chunk(),
+            None => return Err("No recorder available".to_string()),
         }
-    })
-    .await
-    .map_err(|_| "Failed to drain audio chunk".to_string())?
+    };
+    reply.await
 }
 
-async fn stop_recorder(app: AppHandle) -> Result<(), String> {
-    tauri::async_runtime::spawn_blocking(move || {
+async fn stop_recorder(app: AppHandle) -> Result<Option<Vec<u8>>, String> {
+    let reply = {
         let state = app.state::<AppState>();
         let mut recorder = state.recorder.lock().unwrap();
-        if let Some(ref mut rec) = *recorder {
-            rec.stop_recording()
-        } else {
-            Err("No recorder available".to_string())
+        match recorder.as_mut() {
+            Some(rec) => rec.stop_recording(),
+            None => return Err("No recorder available".to_string()),
         }
-    })
-    .await
-    .map_err(|_| "Failed to stop recorder".to_string())?
+    };
+    reply.await
 }
 
 async fn shutdown_chunking(app: AppHandle, final_chunk: Option<Vec<u8>>) -> Vec<String> {
@@ -283,488 +632,2653 @@ async fn shutdown_chunking(app: AppHandle, final_chunk: Option<Vec<u8>>) -> Vec<
     collected
 }
 
-#[cfg(target_os = "windows")]
-fn paste_text() -> Result<(), String> {
-    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
-
-    let mut enigo =
-        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
-
-    enigo
-        .key(Key::Control, Direction::Press)
-        .map_err(|e| format!("Failed to press Control: {}", e))?;
-    enigo
-        .key(Key::Unicode('v'), Direction::Click)
-        .map_err(|e| format!("Failed to press V: {}", e))?;
-    enigo
-        .key(Key::Control, Direction::Release)
-        .map_err(|e| format!("Failed to release Control: {}", e))?;
-
-    Ok(())
-}
-
-#[cfg(target_os = "macos")]
-fn paste_text() -> Result<(), String> {
-    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
-
-    let mut enigo =
-        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
-
-    enigo
-        .key(Key::Meta, Direction::Press)
-        .map_err(|e| format!("Failed to press Command: {}", e))?;
-    enigo
-        .key(Key::Unicode('v'), Direction::Click)
-        .map_err(|e| format!("Failed to press V: {}", e))?;
-    enigo
-        .key(Key::Meta, Direction::Release)
-        .map_err(|e| format!("Failed to release Command: {}", e))?;
-
-    Ok(())
-}
-
-#[cfg(all(unix, not(target_os = "macos")))]
-fn paste_text() -> Result<(), String> {
-    use enigo::{Direction, Enigo, Key, Keyboard, Settings};
-
-    let mut enigo =
-        Enigo::new(&Settings::default()).map_err(|e| format!("Failed to create enigo: {}", e))?;
-
-    enigo
-        .key(Key::Control, Direction::Press)
-        .map_err(|e| format!("Failed to press Control: {}", e))?;
-    enigo
-        .key(Key::Unicode('v'), Direction::Click)
-        .map_err(|e| format!("Failed to press V: {}", e))?;
-    enigo
-        .key(Key::Control, Direction::Release)
-        .map_err(|e| format!("Failed to release Control: {}", e))?;
-
-    Ok(())
-}
-
-fn position_popup_bottom_center(window: &tauri::WebviewWindow) {
-    let (width, height) = window
-        .outer_size()
-        .map(|size| (size.width as i32, size.height as i32))
-        .unwrap_or((120, 50));
+/// Size the popup to `size` logical pixels and move it to `position`, both
+/// scaled for the screen holding the caret or mouse
+fn position_popup(window: &tauri::WebviewWindow, position: PopupPosition, size: (u32, u32)) {
     use device_query::{DeviceQuery, DeviceState};
-    let device_state = DeviceState::new();
-    let mouse = device_state.get_mouse();
+
+    let mouse = DeviceState::new().get_mouse().coords;
+    let anchor = match position {
+        PopupPosition::FollowCaret => target::caret_position(),
+        PopupPosition::FollowMouse => Some(mouse),
+        _ => None,
+    };
+    let (x, y) = anchor.unwrap_or(mouse);
     let monitor = window
-        .monitor_from_point(mouse.coords.0 as f64, mouse.coords.1 as f64)
+        .monitor_from_point(x as f64, y as f64)
         .ok()
         .flatten()
         .or_else(|| window.monitor_from_point(0.0, 0.0).ok().flatten());
 
     if let Some(monitor) = monitor {
-        let pos = monitor.position();
-        let size = monitor.size();
-        let monitor_width = size.width as i32;
-        let monitor_height = size.height as i32;
-        let min_x = pos.x as i32;
-        let min_y = pos.y as i32;
-        let max_x = min_x + monitor_width - width;
-        let max_y = min_y + monitor_height - height;
-        let mut x = min_x + (monitor_width - width) / 2;
-        // Place the popup around 75% height (about a quarter up from the bottom).
-        let mut y = min_y + (monitor_height * 3 / 4) - (height / 2);
-        x = x.clamp(min_x, max_x.max(min_x));
-        y = y.clamp(min_y, max_y.max(min_y));
-
+        let scale = monitor.scale_factor();
+        let size = popup::scaled_size(size, scale);
+        let _ = window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
+            width: size.0 as u32,
+            height: size.1 as u32,
+        }));
+        let area = popup::Rect {
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width as i32,
+            height: monitor.size().height as i32,
+        };
+        let (x, y) = popup::origin(position, area, size, anchor, scale);
         let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
     }
 }
 
-async fn cancel_recording(app: AppHandle) {
-    let state = app.state::<AppState>();
-
-    // Stop recording state
-    {
-        *state.is_recording.lock().unwrap() = false;
-    }
-
-    let _ = shutdown_chunking(app.clone(), None).await;
-    let _ = stop_recorder(app.clone()).await;
-
-    // Unregister Escape logic
-    let escape_shortcut = Shortcut::new(Some(Modifiers::empty()), Code::Escape);
-    let _ = app.global_shortcut().unregister(escape_shortcut);
-
-    // Hide window
+/// Show the popup window at the size and place configured in settings
+fn show_popup(app: &AppHandle) {
     if let Some(window) = app.get_webview_window("main") {
-        let _ = window.hide();
+        let appearance = app
+            .state::<AppState>()
+            .settings
+            .lock()
+            .unwrap()
+            .popup
+            .clone();
+        // Keep the popup from stealing focus when it appears.
+        let _ = window.set_focusable(false);
+        let _ = window.set_ignore_cursor_events(appearance.click_through);
+        // Uses SetWindowDisplayAffinity(WDA_EXCLUDEFROMCAPTURE) on Windows
+        let _ = window.set_content_protected(appearance.hide_from_capture);
+        position_popup(
+            &window,
+            appearance.position,
+            (appearance.width, appearance.height),
+        );
+        let _ = window.show();
     }
-
-    let _ = app.emit("show-idle", ());
 }
 
-async fn handle_hotkey_press(app: AppHandle) {
+/// Keep the popup under the caret as focus and the caret move, when it's set
+/// to follow the caret
+fn follow_caret(app: &AppHandle) {
     let state = app.state::<AppState>();
-
-    // Check if recording with a scoped lock
-    let is_recording_val = {
-        let is_rec = state.is_recording.lock().unwrap();
-        *is_rec
+    let appearance = state.settings.lock().unwrap().popup.clone();
+    let watch = if appearance.position == PopupPosition::FollowCaret {
+        let app = app.clone();
+        let size = (appearance.width, appearance.height);
+        uia::watch_caret(move || {
+            if let Some(window) = app.get_webview_window("main") {
+                position_popup(&window, PopupPosition::FollowCaret, size);
+            }
+        })
+    } else {
+        None
     };
+    *state.caret_watch.lock().unwrap() = watch;
+}
 
-    let escape_shortcut = Shortcut::new(Some(Modifiers::empty()), Code::Escape);
-
-    if is_recording_val {
-        // STOP RECORDING
+/// Open the caption bar at the bottom of the primary screen, if live
+/// captions are on. It never takes focus or clicks.
+fn show_captions(app: &AppHandle) {
+    let (live_captions, hide_from_capture) = {
+        let settings = app.state::<AppState>().settings.lock().unwrap();
+        (settings.live_captions, settings.popup.hide_from_capture)
+    };
+    if !live_captions {
+        return;
+    }
+    if app.get_webview_window("captions").is_some() {
+        return;
+    }
+    let (width, height) = CAPTION_BAR_SIZE;
+    let window =
+        match WebviewWindowBuilder::new(app, "captions", WebviewUrl::App("captions.html".into()))
+            .title("Live Captions")
+            .inner_size(width, height)
+            .decorations(false)
+            .transparent(true)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .shadow(false)
+            .focused(false)
+            .content_protected(hide_from_capture)
+            .build()
         {
-            *state.is_recording.lock().unwrap() = false;
-        }
-
-        // Unregister Escape
-        let _ = app.global_shortcut().unregister(escape_shortcut);
+            Ok(window) => window,
+            Err(e) => {
+                log!("Failed to open captions: {}", e);
+                return;
+            }
+        };
+    let _ = window.set_focusable(false);
+    let _ = window.set_ignore_cursor_events(true);
+    if let Ok(Some(monitor)) = window.primary_monitor() {
+        let scale = monitor.scale_factor();
+        let screen = monitor.size().to_logical::<f64>(scale);
+        let origin = monitor.position().to_logical::<f64>(scale);
+        let _ = window.set_position(tauri::Position::Logical(tauri::LogicalPosition {
+            x: origin.x + (screen.width - width) / 2.0,
+            y: origin.y + screen.height - height - CAPTION_BAR_MARGIN,
+        }));
+    }
+}
 
-        // Stop chunking and finalize transcription
-        let settings = state.settings.lock().unwrap().clone();
-        let has_api = !settings.api_url.is_empty() && !settings.api_key.is_empty();
+fn close_captions(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("captions") {
+        let _ = window.close();
+    }
+}
 
-        // Show processing state
-        let _ = app.emit("show-processing", ());
+/// Flash a short message in the popup, which hides itself again
+fn show_notice(app: &AppHandle, message: &str) {
+    show_popup(app);
+    let _ = app.emit("show-error", message.to_string());
+}
 
-        let control = {
-            let mut guard = state.chunk_control.lock().unwrap();
-            guard.take()
-        };
+/// Note that the server turned down a chunk of the current recording. Every
+/// other chunk would be turned down too, so a recording still going is
+/// stopped and thrown away; one being finished reports it once it's done.
+fn report_api_problem(app: &AppHandle, problem: ApiProblem) {
+    let state = app.state::<AppState>();
+    if state.api_problem.lock().unwrap().replace(problem).is_some() {
+        return;
+    }
+    if !*state.is_recording.lock().unwrap() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        cancel_recording(app.clone()).await;
+        show_api_problem(&app, problem);
+    });
+}
 
-        if let Some(control) = control {
-            let _ = control.stop_tx.send(true);
-            let _ = control.timer_handle.await;
+/// Show `problem` in the popup, which opens the Transcription settings when
+/// clicked
+fn show_api_problem(app: &AppHandle, problem: ApiProblem) {
+    show_popup(app);
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.set_ignore_cursor_events(false);
+    }
+    let _ = app.emit("show-api-problem", problem.message());
+    set_tray_status(app, TrayStatus::Error(problem.message().to_string()));
+}
 
-            let final_chunk = if has_api {
-                drain_chunk_from_recorder(app.clone()).await.ok()
-            } else {
-                None
-            };
+/// Skip delivery, leaving the transcript on the clipboard and telling the user why.
+fn keep_on_clipboard(app: &AppHandle, text: &str, reason: &str) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
 
-            let _ = stop_recorder(app.clone()).await;
+    if let Err(e) = app.clipboard().write_text(text) {
+        log!("Failed to write clipboard: {}", e);
+    }
+    let _ = app.emit("show-error", reason.to_string());
+}
 
-            if let Some(chunk) = final_chunk {
-                let _ = control.chunk_tx.send(chunk).await;
-            }
-            drop(control.chunk_tx);
-            let _ = control.worker_handle.await;
-        } else {
-            let _ = stop_recorder(app.clone()).await;
+/// Wait until the clipboard reads back `text` (bounded by the configured
+/// min/max delays) so the paste never races the clipboard write.
+async fn wait_for_clipboard(app: &AppHandle, text: &str, settings: &Settings) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let min_delay = std::time::Duration::from_millis(settings.paste_min_delay_ms);
+    let max_delay = std::time::Duration::from_millis(
+        settings.paste_max_delay_ms.max(settings.paste_min_delay_ms),
+    );
+    let started = std::time::Instant::now();
+
+    loop {
+        let elapsed = started.elapsed();
+        if elapsed >= max_delay {
+            log!("Clipboard not ready after {:?}, pasting anyway", elapsed);
+            return;
         }
-
-        if !has_api {
-            let _ = app.emit(
-                "show-error",
-                "API not configured. Right-click tray to configure.",
-            );
+        if elapsed >= min_delay && app.clipboard().read_text().ok().as_deref() == Some(text) {
             return;
         }
+        tokio::time::sleep(tokio::time::Duration::from_millis(
+            CLIPBOARD_POLL_INTERVAL_MS,
+        ))
+        .await;
+    }
+}
 
-        let chunk_texts = {
-            let mut texts = state.chunk_texts.lock().unwrap();
-            let collected = texts.clone();
-            texts.clear();
-            collected
-        };
+/// Deliver text by writing it to the clipboard and sending the paste chord.
+async fn paste_via_clipboard(
+    app: &AppHandle,
+    text: &str,
+    settings: &Settings,
+    target: Option<&target::TargetWindow>,
+) -> Result<(), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    // Remember what the user had copied so it can be put back after pasting
+    let previous_clipboard = if settings.restore_clipboard {
+        app.clipboard().read_text().ok()
+    } else {
+        None
+    };
 
-        let raw_text = consolidate_chunk_texts(&chunk_texts);
+    let mut failure = app
+        .clipboard()
+        .write_text(text)
+        .err()
+        .map(|e| format!("Failed to write clipboard: {}", e));
+
+    if failure.is_none() {
+        wait_for_clipboard(app, text, settings).await;
+
+        // Note where the caret is so we can tell whether the paste landed
+        let caret_before = if settings.paste_fallback_to_typing && settings.verify_paste {
+            tauri::async_runtime::spawn_blocking(uia::caret_position)
+                .await
+                .ok()
+                .flatten()
+        } else {
+            None
+        };
 
-        // Apply post-processing transformations
-        let text = postprocessing::apply_postprocessing(&raw_text, &settings);
+        // Paste via clipboard to avoid simulated typing glitches
+        let chord = settings.paste_chord_for(target.and_then(|t| t.process_name.as_deref()));
+        failure = match injection::paste_text(chord) {
+            Err(e) => Some(format!("Paste chord failed: {}", e)),
+            Ok(()) => match caret_before {
+                Some(before) => {
+                    tokio::time::sleep(tokio::time::Duration::from_millis(PASTE_VERIFY_DELAY_MS))
+                        .await;
+                    let after = tauri::async_runtime::spawn_blocking(uia::caret_position)
+                        .await
+                        .ok()
+                        .flatten();
+                    (after == Some(before)).then(|| "Caret didn't move after pasting".to_string())
+                }
+                None => None,
+            },
+        };
+    }
 
-        if text.is_empty() {
+    let result = match failure {
+        Some(reason) if settings.paste_fallback_to_typing => {
+            log!("{}; falling back to typing", reason);
+            let typed = text.to_string();
+            let result =
+                tauri::async_runtime::spawn_blocking(move || injection::send_unicode_text(&typed))
+                    .await
+                    .map_err(|_| "Injection task failed".to_string())
+                    .and_then(|r| r);
             let _ = app.emit(
-                "show-error",
-                "No text returned from transcription".to_string(),
+                "paste-fallback",
+                PasteFallback {
+                    reason,
+                    fallback: OutputMode::SendInput,
+                    success: result.is_ok(),
+                },
             );
-            return;
+            result
         }
+        Some(reason) => Err(reason),
+        None => Ok(()),
+    };
 
-        // Log to history
-        {
-            let mut history = state.history.lock().unwrap();
-            history.add_entry(raw_text.clone(), text.clone());
+    if let Some(previous) = previous_clipboard {
+        // Give the target app time to read the clipboard before restoring it
+        tokio::time::sleep(tokio::time::Duration::from_millis(
+            CLIPBOARD_RESTORE_DELAY_MS,
+        ))
+        .await;
+        if let Err(e) = app.clipboard().write_text(previous) {
+            log!("Failed to restore clipboard: {}", e);
         }
+    }
 
-        if let Some(window) = app.get_webview_window("main") {
-            let _ = window.hide();
-        }
+    result
+}
 
-        use tauri_plugin_clipboard_manager::ClipboardExt;
-        if let Err(e) = app.clipboard().write_text(&text) {
-            eprintln!("Failed to write clipboard: {}", e);
-        }
+/// Insert a finished transcript into the window that was focused when
+/// recording started, honoring the focus, elevation, and password checks.
+async fn paste_transcript(app: &AppHandle, text: &str, settings: &Settings) {
+    let state = app.state::<AppState>();
 
-        // Small delay before pasting
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
+    let original_target = state.recording_target.lock().unwrap().take();
+    let target = match original_target {
+        Some(original) if !original.is_foreground() => match settings.focus_change_policy {
+            FocusChangePolicy::PasteAnyway => target::TargetWindow::foreground(),
+            FocusChangePolicy::Refocus if original.focus() => Some(original),
+            FocusChangePolicy::Refocus | FocusChangePolicy::Abort => {
+                keep_on_clipboard(app, text, "Focus changed. Transcript copied to clipboard.");
+                return;
+            }
+        },
+        Some(original) => Some(original),
+        None => target::TargetWindow::foreground(),
+    };
 
-        // Paste via clipboard to avoid simulated typing glitches
-        if let Err(e) = paste_text() {
-            eprintln!("Failed to paste text: {}", e);
-        }
+    if target.as_ref().is_some_and(|t| t.elevation_mismatch()) {
+        keep_on_clipboard(
+            app,
+            text,
+            "Target app runs as administrator. Transcript copied to clipboard.",
+        );
+        return;
+    }
 
-        let _ = app.emit("show-success", text);
-    } else {
-        // START RECORDING
-        {
-            *state.is_recording.lock().unwrap() = true;
+    if settings.block_password_fields {
+        let is_password = tauri::async_runtime::spawn_blocking(target::focus_is_password_field)
+            .await
+            .unwrap_or(false);
+        if is_password {
+            keep_on_clipboard(
+                app,
+                text,
+                "Password field focused. Transcript copied to clipboard.",
+            );
+            return;
         }
+    }
 
-        // Initialize recorder if needed
-        {
-            let mut recorder = state.recorder.lock().unwrap();
-            if recorder.is_none() {
-                *recorder = Some(AudioRecorder::new());
-            }
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+    }
 
-            if let Some(ref mut rec) = *recorder {
-                // Create volume channel
-                let (vol_tx, vol_rx) = std::sync::mpsc::channel();
+    if let Err(e) = insert_into_target(app, text, settings, target.as_ref()).await {
+        log!("Failed to insert text: {}", e);
+        let _ = app.emit("show-error", "Couldn't insert the transcript.".to_string());
+        return;
+    }
 
-                // Spawn listener with throttling
-                let app_handle = app.clone();
-                std::thread::spawn(move || {
-                    let mut max_level: f32 = 0.0;
-                    let mut last_emit = std::time::Instant::now();
+    *state.last_insertion.lock().unwrap() = Some(LastInsertion {
+        text: text.to_string(),
+        hwnd: target.as_ref().map(|t| t.hwnd),
+    });
 
-                    while let Ok(level) = vol_rx.recv() {
-                        if level > max_level {
-                            max_level = level;
-                        }
+    let _ = app.emit("show-success", text.to_string());
+    announce(app, &format!("Inserted: {}", text));
+}
 
-                        if last_emit.elapsed() >= std::time::Duration::from_millis(20) {
-                            let _ = app_handle.emit("audio-level", max_level);
-                            max_level = 0.0;
-                            last_emit = std::time::Instant::now();
-                        }
-                    }
-                });
+/// Insert text into the target window using the output mode configured for it.
+async fn insert_into_target(
+    app: &AppHandle,
+    text: &str,
+    settings: &Settings,
+    target: Option<&target::TargetWindow>,
+) -> Result<(), String> {
+    let mode = settings.output_mode_for(target.and_then(|t| t.process_name.as_deref()));
+
+    // An open IME composition window can swallow the paste chord and typed keys
+    let _ime = if target.is_some_and(|t| t.is_console()) {
+        None
+    } else {
+        tauri::async_runtime::spawn_blocking(target::suspend_ime)
+            .await
+            .ok()
+            .flatten()
+    };
 
-                if let Err(e) = rec.start_recording(Some(vol_tx), CHUNK_OVERLAP_SECONDS) {
-                    let _ = app.emit("show-error", format!("Failed to start recording: {}", e));
-                    *state.is_recording.lock().unwrap() = false;
-                    return;
+    match mode {
+        OutputMode::Paste if target.is_some_and(|t| t.is_console()) => {
+            let hwnd = target.map(|t| t.hwnd).unwrap_or_default();
+            let injected = text.to_string();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                injection::write_console_input(hwnd, &injected)
+            })
+            .await
+            .map_err(|_| "Injection task failed".to_string())
+            .and_then(|r| r);
+            match result {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    log!("Console input injection failed, pasting instead: {}", e);
+                    paste_via_clipboard(app, text, settings, target).await
                 }
             }
         }
-
-        {
-            let mut texts = state.chunk_texts.lock().unwrap();
-            texts.clear();
-        }
-        {
-            let mut control = state.chunk_control.lock().unwrap();
-            *control = None;
-        }
-
-        let (chunk_tx, mut chunk_rx) = mpsc::channel::<Vec<u8>>(4);
-        let (stop_tx, mut stop_rx) = watch::channel(false);
-
-        let worker_app = app.clone();
-        let worker_handle = tauri::async_runtime::spawn(async move {
-            while let Some(chunk) = chunk_rx.recv().await {
-                let settings = {
-                    let state = worker_app.state::<AppState>();
-                    let settings = state.settings.lock().unwrap().clone();
-                    settings
-                };
-                if settings.api_url.is_empty() || settings.api_key.is_empty() {
-                    continue;
-                }
-                match transcribe_audio_chunk(&settings.api_url, &settings.api_key, &chunk).await {
-                    Ok(text) => {
-                        let trimmed = text.trim();
-                        if !trimmed.is_empty() {
-                            let state = worker_app.state::<AppState>();
-                            state.chunk_texts.lock().unwrap().push(trimmed.to_string());
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to transcribe chunk: {}", e);
-                    }
+        OutputMode::Paste => paste_via_clipboard(app, text, settings, target).await,
+        OutputMode::Type | OutputMode::SendInput | OutputMode::UiAutomation => {
+            let injected = text.to_string();
+            let chars_per_second = settings.typing_chars_per_second;
+            let result = tauri::async_runtime::spawn_blocking(move || match mode {
+                OutputMode::SendInput => injection::send_unicode_text(&injected),
+                OutputMode::UiAutomation => uia::insert_text(&injected),
+                _ => injection::type_text_paced(&injected, chars_per_second),
+            })
+            .await
+            .map_err(|_| "Injection task failed".to_string())
+            .and_then(|r| r);
+            match result {
+                Err(e) if mode == OutputMode::UiAutomation => {
+                    // The focused control doesn't expose a writable value
+                    log!("UI Automation insert failed, pasting instead: {}", e);
+                    paste_via_clipboard(app, text, settings, target).await
                 }
+                Err(e) => Err(format!("{:?} failed: {}", mode, e)),
+                Ok(()) => Ok(()),
             }
-        });
+        }
+    }
+}
 
-        let timer_app = app.clone();
-        let timer_tx = chunk_tx.clone();
-        let timer_handle = tauri::async_runtime::spawn(async move {
-            let mut interval =
-                tokio::time::interval(tokio::time::Duration::from_secs(CHUNK_SECONDS));
-            loop {
-                tokio::select! {
-                    _ = interval.tick() => {
-                        if *stop_rx.borrow() {
-                            break;
-                        }
-                        match drain_chunk_from_recorder(timer_app.clone()).await {
-                            Ok(chunk) => {
-                                if !chunk.is_empty() {
-                                    if timer_tx.send(chunk).await.is_err() {
-                                        break;
-                                    }
-                                }
-                            }
-                            Err(_) => {}
-                        }
-                    }
-                    _ = stop_rx.changed() => {
-                        if *stop_rx.borrow() {
-                            break;
-                        }
+/// Executable name of the window the current recording is aimed at
+pub(crate) fn target_app_name(state: &AppState) -> Option<String> {
+    state
+        .recording_target
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|target| target.process_name.clone())
+}
+
+/// Run `work` on the history from an async task. SQLite and the recordings
+/// folder block, so it runs on a blocking thread rather than holding the
+/// history lock on the async runtime.
+pub(crate) async fn with_history<T: Send + 'static>(
+    app: &AppHandle,
+    work: impl FnOnce(&mut TranscriptionHistory) -> T + Send + 'static,
+) -> Result<T, String> {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        let mut history = state.history.lock().unwrap();
+        work(&mut history)
+    })
+    .await
+    .map_err(|_| "History task failed".to_string())
+}
+
+/// Add a transcript to history, saving the recording alongside it when
+/// keeping audio is enabled
+pub(crate) async fn record_history(
+    app: &AppHandle,
+    raw_text: String,
+    text: String,
+    app_name: Option<String>,
+) {
+    let state = app.state::<AppState>();
+    let audio = state.last_recording_audio.lock().unwrap().take();
+    let metadata = EntryMetadata {
+        app: app_name,
+        ..state
+            .last_session_metadata
+            .lock()
+            .unwrap()
+            .take()
+            .unwrap_or_default()
+    };
+    let (keep_audio, max_days, max_mb, sync_folder) = {
+        let settings = state.settings.lock().unwrap();
+        (
+            settings.keep_audio,
+            settings.audio_retention_days,
+            settings.audio_max_mb,
+            settings.history_sync_folder.trim().to_string(),
+        )
+    };
+
+    let recent = with_history(app, move |history| {
+        let id = history.add_entry(raw_text, text, metadata);
+        if let (Some(id), Some(wav)) = (id, audio.filter(|_| keep_audio)) {
+            match recordings::save(id, &wav) {
+                Ok(path) => {
+                    if let Err(e) = history.set_audio_path(id, &path) {
+                        log!("Failed to link recording to history: {}", e);
                     }
                 }
+                Err(e) => log!("{}", e),
             }
-        });
+            let pruned = recordings::prune(max_days, max_mb);
+            history.clear_audio_paths(&pruned);
+        }
+        // Repeats of the newest entry were already mirrored the first time
+        let new_entry = id
+            .filter(|_| !sync_folder.is_empty())
+            .and_then(|id| history.get(id))
+            .filter(|entry| entry.repeat_count == 1);
+        if let Some(entry) = new_entry {
+            if let Err(e) = output::mirror_to_folder(&sync_folder, &entry) {
+                log!("Failed to mirror history entry: {}", e);
+            }
+        }
+        history.newest(RECENT_MENU_ENTRIES)
+    })
+    .await
+    .and_then(|recent| recent);
+    match recent {
+        Ok(entries) => fill_recent_menu(app, entries),
+        Err(e) => log!("Failed to refresh recent transcriptions: {}", e),
+    }
+}
 
+/// List the latest transcriptions in the tray, newest first. The query
+/// runs in the background, so this returns straight away.
+pub(crate) fn refresh_recent_menu(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match with_history(&app, |history| history.newest(RECENT_MENU_ENTRIES))
+            .await
+            .and_then(|recent| recent)
         {
-            let mut control = state.chunk_control.lock().unwrap();
-            *control = Some(ChunkControl {
-                stop_tx,
-                chunk_tx,
-                timer_handle,
-                worker_handle,
-            });
+            Ok(entries) => fill_recent_menu(&app, entries),
+            Err(e) => log!("Failed to refresh recent transcriptions: {}", e),
         }
+    });
+}
 
-        // Register Escape to cancel
-        let _ = app.global_shortcut().register(escape_shortcut);
-
-        // Show and position the popup window near the bottom center of the active screen
-        if let Some(window) = app.get_webview_window("main") {
-            // Keep the popup from stealing focus when it appears.
-            let _ = window.set_focusable(false);
-            let _ = window.set_size(tauri::Size::Physical(tauri::PhysicalSize {
-                width: 150,
-                height: 48,
-            }));
-            position_popup_bottom_center(&window);
-            let _ = window.show();
-        }
-
-        let _ = app.emit("show-recording", ());
-    }
-}
-
-fn parse_hotkey(hotkey_str: &str) -> Option<Shortcut> {
-    let parts: Vec<&str> = hotkey_str.split('+').map(|s| s.trim()).collect();
-    let mut modifiers = Modifiers::empty();
-    let mut key_code = None;
-
-    for part in parts {
-        match part.to_uppercase().as_str() {
-            "CTRL" | "CONTROL" => modifiers |= Modifiers::CONTROL,
-            "SHIFT" => modifiers |= Modifiers::SHIFT,
-            "ALT" => modifiers |= Modifiers::ALT,
-            "WIN" | "SUPER" | "META" => modifiers |= Modifiers::SUPER,
-            "SPACE" => key_code = Some(Code::Space),
-            "ENTER" => key_code = Some(Code::Enter),
-            "TAB" => key_code = Some(Code::Tab),
-            "ESCAPE" | "ESC" => key_code = Some(Code::Escape),
-            "SCROLLLOCK" | "SCROLL" => key_code = Some(Code::ScrollLock),
-            "PRINTSCREEN" | "PRINT" | "PRTSC" => key_code = Some(Code::PrintScreen),
-            "PAUSE" | "PAUSEBREAK" => key_code = Some(Code::Pause),
-            "INSERT" | "INS" => key_code = Some(Code::Insert),
-            "DELETE" | "DEL" => key_code = Some(Code::Delete),
-            "HOME" => key_code = Some(Code::Home),
-            "END" => key_code = Some(Code::End),
-            "PAGEUP" | "PGUP" => key_code = Some(Code::PageUp),
-            "PAGEDOWN" | "PGDN" => key_code = Some(Code::PageDown),
-            "UP" | "ARROWUP" => key_code = Some(Code::ArrowUp),
-            "DOWN" | "ARROWDOWN" => key_code = Some(Code::ArrowDown),
-            "LEFT" | "ARROWLEFT" => key_code = Some(Code::ArrowLeft),
-            "RIGHT" | "ARROWRIGHT" => key_code = Some(Code::ArrowRight),
-            k if k.len() == 1 => {
-                // Single character keys
-                let c = k.chars().next().unwrap();
-                key_code = match c {
-                    'A' => Some(Code::KeyA),
-                    'B' => Some(Code::KeyB),
-                    'C' => Some(Code::KeyC),
-                    'D' => Some(Code::KeyD),
-                    'E' => Some(Code::KeyE),
-                    'F' => Some(Code::KeyF),
-                    'G' => Some(Code::KeyG),
-                    'H' => Some(Code::KeyH),
-                    'I' => Some(Code::KeyI),
-                    'J' => Some(Code::KeyJ),
-                    'K' => Some(Code::KeyK),
-                    'L' => Some(Code::KeyL),
-                    'M' => Some(Code::KeyM),
-                    'N' => Some(Code::KeyN),
-                    'O' => Some(Code::KeyO),
-                    'P' => Some(Code::KeyP),
-                    'Q' => Some(Code::KeyQ),
-                    'R' => Some(Code::KeyR),
-                    'S' => Some(Code::KeyS),
-                    'T' => Some(Code::KeyT),
-                    'U' => Some(Code::KeyU),
-                    'V' => Some(Code::KeyV),
-                    'W' => Some(Code::KeyW),
-                    'X' => Some(Code::KeyX),
-                    'Y' => Some(Code::KeyY),
-                    'Z' => Some(Code::KeyZ),
-                    '0' => Some(Code::Digit0),
-                    '1' => Some(Code::Digit1),
-                    '2' => Some(Code::Digit2),
-                    '3' => Some(Code::Digit3),
-                    '4' => Some(Code::Digit4),
-                    '5' => Some(Code::Digit5),
-                    '6' => Some(Code::Digit6),
-                    '7' => Some(Code::Digit7),
-                    '8' => Some(Code::Digit8),
-                    '9' => Some(Code::Digit9),
-                    _ => None,
-                };
-            }
-            k if k.starts_with('F') && k.len() <= 3 => {
-                // Function keys F1-F12
-                if let Ok(num) = k[1..].parse::<u8>() {
-                    key_code = match num {
-                        1 => Some(Code::F1),
-                        2 => Some(Code::F2),
-                        3 => Some(Code::F3),
-                        4 => Some(Code::F4),
-                        5 => Some(Code::F5),
-                        6 => Some(Code::F6),
-                        7 => Some(Code::F7),
-                        8 => Some(Code::F8),
-                        9 => Some(Code::F9),
-                        10 => Some(Code::F10),
-                        11 => Some(Code::F11),
-                        12 => Some(Code::F12),
-                        _ => None,
-                    };
-                }
-            }
-            _ => {}
+/// Show `entries`, the latest transcriptions, in the tray
+fn fill_recent_menu(app: &AppHandle, entries: Vec<TranscriptionLog>) {
+    let Some(submenu) = app.try_state::<RecentSubmenu>() else {
+        return;
+    };
+    let submenu = &submenu.0;
+    while let Ok(Some(_)) = submenu.remove_at(0) {}
+
+    if entries.is_empty() {
+        if let Ok(item) = MenuItem::with_id(
+            app,
+            "recent-empty",
+            "No transcriptions yet",
+            false,
+            None::<&str>,
+        ) {
+            let _ = submenu.append(&item);
+        }
+        return;
+    }
+    for entry in entries {
+        let label = history::preview(&entry.processed_text, RECENT_MENU_LABEL_CHARS);
+        let id = format!("recent:{}", entry.id);
+        if let Ok(item) = MenuItem::with_id(app, id, label, true, None::<&str>) {
+            let _ = submenu.append(&item);
         }
     }
-
-    key_code.map(|code| Shortcut::new(Some(modifiers), code))
 }
 
-pub fn run() {
-    tauri::Builder::default()
+/// Rebuild the tray's profile submenu, checking the active profile
+pub(crate) fn refresh_profile_menu(app: &AppHandle) {
+    let Some(submenu) = app.try_state::<ProfileSubmenu>() else {
+        return;
+    };
+    let submenu = &submenu.0;
+    while let Ok(Some(_)) = submenu.remove_at(0) {}
+
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    let active = settings
+        .active_profile()
+        .map(|profile| profile.name.clone());
+    // "profile:" with no name switches back to plain settings
+    let mut items = vec![(
+        "profile:".to_string(),
+        "Default".to_string(),
+        active.is_none(),
+    )];
+    for profile in &settings.profiles {
+        let checked = active.as_ref() == Some(&profile.name);
+        items.push((
+            format!("profile:{}", profile.name),
+            profile.name.clone(),
+            checked,
+        ));
+    }
+    for (id, label, checked) in items {
+        if let Ok(item) = CheckMenuItem::with_id(app, id, label, true, checked, None::<&str>) {
+            let _ = submenu.append(&item);
+        }
+    }
+}
+
+/// Save `settings` and switch to them: hotkeys, history retention, the tray,
+/// and `settings-changed` listeners all pick them up
+pub(crate) fn apply_settings(app: &AppHandle, settings: Settings) -> Result<(), String> {
+    settings.save()?;
+    use_settings(app, settings);
+    Ok(())
+}
+
+/// Switch to `settings` without saving them
+fn use_settings(app: &AppHandle, settings: Settings) {
+    let state = app.state::<AppState>();
+    logs::set_secrets(redact::secrets(&settings));
+    // Applying retention can delete entries, so the menu waits for it
+    let retention = settings.history_retention.clone();
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = with_history(&app_handle, move |history| history.set_retention(retention)).await;
+        refresh_recent_menu(&app_handle);
+    });
+    let previous = std::mem::replace(&mut *state.settings.lock().unwrap(), settings.clone());
+    unregister_hotkeys(app, &previous);
+    register_hotkeys(app, &settings);
+    if previous.automation_api != settings.automation_api {
+        automation::restart(app, &settings.automation_api);
+    }
+    if previous.warm_microphone != settings.warm_microphone
+        || previous.preferred_input_device != settings.preferred_input_device
+    {
+        warm_microphone(app);
+    }
+    refresh_tray(app);
+    refresh_profile_menu(app);
+    emit_settings_changed(app, &settings);
+}
+
+/// Go back to default settings, keeping the server URL and API key if
+/// `keep_credentials` is set
+pub(crate) fn reset_settings(app: &AppHandle, keep_credentials: bool) -> Result<Settings, String> {
+    let previous = app.state::<AppState>().settings.lock().unwrap().clone();
+    let mut settings = Settings {
+        first_run: false,
+        onboarding_steps: previous.onboarding_steps.clone(),
+        paused: previous.paused,
+        ..Settings::default()
+    };
+    if keep_credentials {
+        settings.api_url = previous.api_url;
+        settings.api_key = previous.api_key;
+    }
+    apply_settings(app, settings.clone())?;
+    Ok(settings)
+}
+
+/// Tell every window and listener that settings changed, with the new values
+pub(crate) fn emit_settings_changed(app: &AppHandle, settings: &Settings) {
+    let _ = app.emit("settings-changed", settings);
+}
+
+/// Make `name` the active profile (empty for none) and save settings
+pub(crate) fn set_active_profile(app: &AppHandle, name: &str) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let settings = {
+        let mut settings = state.settings.lock().unwrap();
+        if !name.is_empty() && !settings.profiles.iter().any(|p| p.name == name) {
+            return Err(format!("No profile named \"{}\"", name));
+        }
+        settings.active_profile = name.to_string();
+        settings.clone()
+    };
+    settings.save()?;
+    refresh_profile_menu(app);
+    refresh_tray(app);
+    emit_settings_changed(app, &settings);
+    Ok(())
+}
+
+/// Copy a history entry's processed text to the clipboard
+pub(crate) async fn copy_history_entry(app: &AppHandle, id: i64) -> Result<(), String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    let entry = with_history(app, move |history| history.get(id))
+        .await?
+        .ok_or_else(|| format!("No history entry with id {}", id))?;
+    app.clipboard()
+        .write_text(&entry.processed_text)
+        .map_err(|e| format!("Failed to copy: {}", e))
+}
+
+/// Play history entry `id`'s saved recording, stopping any other. Emits
+/// `history-playback-ended` with the id once it finishes or is stopped.
+pub(crate) async fn play_history_audio(app: &AppHandle, id: i64) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let entry = with_history(app, move |history| history.get(id))
+        .await?
+        .ok_or_else(|| format!("No history entry with id {}", id))?;
+    let path = entry
+        .audio_path
+        .ok_or_else(|| "This entry has no saved audio".to_string())?;
+    // Stop the one playing first, so two never overlap
+    *state.playback.lock().unwrap() = None;
+    let app_handle = app.clone();
+    let playback = playback::play(std::path::Path::new(&path), move || {
+        let _ = app_handle.emit("history-playback-ended", id);
+    })?;
+    *state.playback.lock().unwrap() = Some(playback);
+    Ok(())
+}
+
+/// Delete history, recordings, and everything else the app keeps on disk,
+/// shredding files on the way. Settings go back to defaults only when
+/// `include_settings` is set, optionally keeping the API key.
+pub(crate) async fn wipe_all_data(
+    app: &AppHandle,
+    include_settings: bool,
+    keep_api_key: bool,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut errors = Vec::new();
+
+    if let Err(e) = with_history(app, |history| history.wipe())
+        .await
+        .and_then(|wiped| wiped)
+    {
+        errors.push(format!("history: {}", e));
+    }
+    *state.last_recording_audio.lock().unwrap() = None;
+    *state.last_session_metadata.lock().unwrap() = None;
+    *state.last_insertion.lock().unwrap() = None;
+
+    // Shredding the whole data folder takes a while
+    let shredded = tauri::async_runtime::spawn_blocking(move || {
+        // The open database was scrubbed in place above
+        let keep = |path: &std::path::Path| {
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default();
+            name.starts_with("history.db")
+                || (!include_settings && name.starts_with("settings.json"))
+        };
+        let mut errors = wipe::shred_dir(&paths::data_dir(), &keep);
+        if include_settings {
+            let settings_path = paths::data_dir().join("settings.json");
+            if let Err(e) = wipe::shred_file(&settings_path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    errors.push(format!("{}: {}", settings_path.display(), e));
+                }
+            }
+        }
+        errors
+    })
+    .await;
+    match shredded {
+        Ok(shred_errors) => errors.extend(shred_errors),
+        Err(_) => errors.push("Wipe task failed".to_string()),
+    }
+
+    if include_settings {
+        let previous = state.settings.lock().unwrap().clone();
+        let mut settings = Settings::default();
+        if keep_api_key {
+            settings.api_key = previous.api_key.clone();
+        }
+        if let Err(e) = apply_settings(app, settings) {
+            errors.push(format!("settings: {}", e));
+        }
+    }
+    refresh_recent_menu(app);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Some data couldn't be removed: {}",
+            errors.join("; ")
+        ))
+    }
+}
+
+/// Host of the transcription API, recorded as the backend in history
+fn backend_name(api_url: &str) -> Option<String> {
+    let url = tauri::Url::parse(api_url.trim()).ok()?;
+    url.host_str().map(str::to_string)
+}
+
+/// Re-insert the most recent history entry into whatever window has focus now.
+pub(crate) async fn repaste_last_transcription(app: AppHandle) -> Result<(), String> {
+    paste_history_entry(app, 0).await
+}
+
+/// Insert the transcription `offset` entries back from the latest (0 is the
+/// latest) into whatever window has focus now
+pub(crate) async fn paste_history_entry(app: AppHandle, offset: usize) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let text = with_history(&app, move |history| history.newest(offset + 1))
+        .await??
+        .into_iter()
+        .nth(offset)
+        .map(|entry| entry.processed_text)
+        .ok_or_else(|| "No transcription to re-paste".to_string())?;
+    let settings = state.settings.lock().unwrap().clone();
+
+    let target = target::TargetWindow::foreground();
+    insert_into_target(&app, &text, &settings, target.as_ref()).await?;
+
+    *state.last_insertion.lock().unwrap() = Some(LastInsertion {
+        text,
+        hwnd: target.as_ref().map(|t| t.hwnd),
+    });
+    Ok(())
+}
+
+/// Remove the most recently inserted transcript from the window it went to.
+pub(crate) async fn undo_last_dictation(app: AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let insertion = state
+        .last_insertion
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "Nothing to undo".to_string())?;
+
+    let still_focused = match (insertion.hwnd, target::TargetWindow::foreground()) {
+        (Some(hwnd), Some(current)) => current.hwnd == hwnd,
+        (None, _) => true,
+        (Some(_), None) => false,
+    };
+    if !still_focused {
+        // Put it back so the user can retry from the right window
+        *state.last_insertion.lock().unwrap() = Some(insertion);
+        return Err("The window that received the dictation is no longer focused".to_string());
+    }
+
+    let method = state.settings.lock().unwrap().undo_method;
+    let count = insertion.text.chars().count();
+    tauri::async_runtime::spawn_blocking(move || match method {
+        UndoMethod::Backspace => injection::send_backspaces(count),
+        UndoMethod::CtrlZ => injection::send_undo(),
+    })
+    .await
+    .map_err(|_| "Undo task failed".to_string())?
+}
+
+/// Hold the transcript and wait for the user to pick an action, either in
+/// the popup or, when editing is enabled, in a dedicated review window.
+fn begin_review(app: &AppHandle, raw_text: String, text: String, settings: &Settings) {
+    let state = app.state::<AppState>();
+    let started = std::time::Instant::now();
+    let shortcuts = if settings.review_editable {
+        Vec::new()
+    } else {
+        review_shortcuts(settings)
+    };
+    *state.pending_review.lock().unwrap() = Some(PendingReview {
+        raw_text,
+        text: text.clone(),
+        started,
+        shortcuts: shortcuts.clone(),
+    });
+    set_tray_status(app, TrayStatus::Idle);
+
+    if settings.review_editable {
+        if let Some(window) = app.get_webview_window("main") {
+            let _ = window.hide();
+        }
+        let _ = app.emit("show-idle", ());
+
+        if let Some(window) = app.get_webview_window("review") {
+            let _ = window.set_focus();
+        } else {
+            let _ = WebviewWindowBuilder::new(app, "review", WebviewUrl::App("review.html".into()))
+                .title("Review Transcript")
+                .inner_size(480.0, 240.0)
+                .always_on_top(true)
+                .center()
+                .build();
+        }
+        return;
+    }
+
+    for (_, shortcut) in &shortcuts {
+        let _ = app.global_shortcut().register(*shortcut);
+    }
+    // Don't hold the shortcuts from other apps while a review sits unanswered
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(REVIEW_SHORTCUT_SECONDS)).await;
+        let state = app_handle.state::<AppState>();
+        let expired = match state.pending_review.lock().unwrap().as_mut() {
+            Some(pending) if pending.started == started => std::mem::take(&mut pending.shortcuts),
+            _ => Vec::new(),
+        };
+        release_review_shortcuts(&app_handle, &expired);
+    });
+
+    if let Some(window) = app.get_webview_window("main") {
+        // The review has buttons, so it always takes clicks
+        let _ = window.set_ignore_cursor_events(false);
+        let position = app
+            .state::<AppState>()
+            .settings
+            .lock()
+            .unwrap()
+            .popup
+            .position;
+        position_popup(&window, position, REVIEW_POPUP_SIZE);
+    }
+
+    let _ = app.emit(
+        "show-review",
+        ReviewPrompt {
+            text,
+            hotkeys: settings.review_hotkeys.clone(),
+        },
+    );
+}
+
+/// Shortcuts active while a review is pending, from `review_hotkeys`
+fn review_shortcuts(settings: &Settings) -> Vec<(ReviewAction, Shortcut)> {
+    let hotkeys = &settings.review_hotkeys;
+    [
+        (ReviewAction::Paste, &hotkeys.paste),
+        (ReviewAction::CopyOnly, &hotkeys.copy_only),
+        (ReviewAction::Discard, &hotkeys.discard),
+    ]
+    .into_iter()
+    .filter_map(|(action, hotkey)| parse_hotkey(hotkey).map(|shortcut| (action, shortcut)))
+    .collect()
+}
+
+fn release_review_shortcuts(app: &AppHandle, shortcuts: &[(ReviewAction, Shortcut)]) {
+    for (_, shortcut) in shortcuts {
+        let _ = app.global_shortcut().unregister(*shortcut);
+    }
+}
+
+/// Map a pressed shortcut to the review action it triggers
+fn review_action_for(
+    shortcuts: &[(ReviewAction, Shortcut)],
+    shortcut: &Shortcut,
+) -> Option<ReviewAction> {
+    shortcuts
+        .iter()
+        .find(|(_, candidate)| candidate == shortcut)
+        .map(|(action, _)| *action)
+}
+
+/// Finish a pending review with the chosen action, using `edited` in place
+/// of the transcript when the user corrected it.
+pub(crate) async fn resolve_review(app: AppHandle, action: ReviewAction, edited: Option<String>) {
+    let state = app.state::<AppState>();
+    let Some(pending) = state.pending_review.lock().unwrap().take() else {
+        return;
+    };
+
+    release_review_shortcuts(&app, &pending.shortcuts);
+
+    let edited_in_window = if let Some(window) = app.get_webview_window("review") {
+        let _ = window.close();
+        true
+    } else {
+        false
+    };
+
+    let text = edited.map(|t| t.trim().to_string()).unwrap_or(pending.text);
+    if text.is_empty() && action != ReviewAction::Discard {
+        *state.recording_target.lock().unwrap() = None;
+        let message = "Transcript is empty".to_string();
+        let _ = app.emit("show-error", message.clone());
+        set_tray_status(&app, TrayStatus::Error(message));
+        return;
+    }
+
+    match action {
+        ReviewAction::Paste => {
+            let settings = state.settings.lock().unwrap().clone();
+            if edited_in_window {
+                // The review window took focus; hand it back to the original target
+                let target = state.recording_target.lock().unwrap().clone();
+                if let Some(target) = target {
+                    target.focus();
+                }
+            }
+            dispatch::dispatch_output(app.clone(), pending.raw_text, text, settings).await;
+        }
+        ReviewAction::CopyOnly => {
+            use tauri_plugin_clipboard_manager::ClipboardExt;
+            let app_name = target_app_name(&state);
+            *state.recording_target.lock().unwrap() = None;
+            *state.session_binding.lock().unwrap() = None;
+            if state
+                .settings
+                .lock()
+                .unwrap()
+                .outputs
+                .contains(&OutputTarget::History)
+            {
+                record_history(&app, pending.raw_text, text.clone(), app_name).await;
+            }
+            if let Err(e) = app.clipboard().write_text(&text) {
+                let message = format!("Failed to copy: {}", e);
+                let _ = app.emit("show-error", message.clone());
+                set_tray_status(&app, TrayStatus::Error(message));
+                return;
+            }
+            let _ = app.emit("show-success", text);
+        }
+        ReviewAction::Discard => {
+            *state.recording_target.lock().unwrap() = None;
+            *state.session_binding.lock().unwrap() = None;
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.hide();
+            }
+            let _ = app.emit("show-idle", ());
+        }
+    }
+}
+
+async fn cancel_recording(app: AppHandle) {
+    let state = app.state::<AppState>();
+
+    // Stop recording state
+    {
+        *state.is_recording.lock().unwrap() = false;
+    }
+
+    let _ = shutdown_chunking(app.clone(), None).await;
+    let _ = stop_recorder(app.clone()).await;
+    *state.recording_target.lock().unwrap() = None;
+    *state.recording_started.lock().unwrap() = None;
+    *state.session_binding.lock().unwrap() = None;
+    *state.pending_stop.lock().unwrap() = None;
+    release_cancel_shortcut(&app);
+    *state.caret_watch.lock().unwrap() = None;
+    close_captions(&app);
+    if state.meeting.lock().unwrap().take().is_some() {
+        refresh_tray(&app);
+    }
+
+    // Hide window
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+    }
+
+    let _ = app.emit("show-idle", ());
+    set_tray_status(&app, TrayStatus::Idle);
+}
+
+/// If a trigger matched by `matches` starts recordings, the profile it uses:
+/// `Some(None)` for the main hotkey, `Some(Some(_))` for a hotkey binding.
+fn recording_trigger_binding(
+    state: &AppState,
+    matches: impl Fn(&str) -> bool,
+) -> Option<Option<HotkeyBinding>> {
+    let settings = state.settings.lock().unwrap();
+    if matches(&settings.hotkey) {
+        return Some(None);
+    }
+    settings
+        .hotkey_bindings
+        .iter()
+        .find(|binding| matches(&binding.hotkey))
+        .map(|binding| Some(binding.clone()))
+}
+
+/// Note a recording hotkey going down or up, returning whether to pass it on.
+/// Key repeat while held is dropped, and a release only counts once every
+/// recording hotkey is up, so letting go of one of two held hotkeys doesn't
+/// end a hold-to-talk recording.
+fn track_held_hotkey(state: &AppState, shortcut: &Shortcut, pressed: bool) -> bool {
+    let recording = *state.is_recording.lock().unwrap();
+    let mut held = state.held_hotkeys.lock().unwrap();
+    if pressed {
+        held.press(shortcut.id(), recording, std::time::Instant::now())
+    } else {
+        held.release(shortcut.id())
+    }
+}
+
+fn recording_hotkey_binding(
+    state: &AppState,
+    shortcut: &Shortcut,
+) -> Option<Option<HotkeyBinding>> {
+    recording_trigger_binding(state, |hotkey| {
+        parse_hotkey(hotkey).as_ref() == Some(shortcut)
+    })
+}
+
+/// Settings for the current recording, with the active profile and then the
+/// starting hotkey's profile applied
+fn session_settings(state: &AppState) -> Settings {
+    let settings = state.settings.lock().unwrap().with_active_profile();
+    match state.session_binding.lock().unwrap().as_ref() {
+        Some(binding) => settings.for_binding(binding),
+        None => settings,
+    }
+}
+
+/// Why recording hotkeys are paused altogether, if they are
+fn paused_reason(state: &AppState) -> Option<&'static str> {
+    if !*state.enabled.lock().unwrap() {
+        return Some("Paused");
+    }
+    let settings = state.settings.lock().unwrap();
+    if settings.quiet_hours.is_active_now() {
+        return Some("Paused for quiet hours");
+    }
+    None
+}
+
+/// Turn every recording trigger on or off, remembering the choice across
+/// restarts. Pausing cancels a recording in progress, since its hotkey can no
+/// longer stop it, and lets go of the microphone.
+pub(crate) fn set_enabled(app: &AppHandle, enabled: bool) {
+    let state = app.state::<AppState>();
+    *state.enabled.lock().unwrap() = enabled;
+
+    let settings = {
+        let mut settings = state.settings.lock().unwrap();
+        settings.paused = !enabled;
+        settings.clone()
+    };
+    if let Err(e) = settings.save() {
+        log!("Failed to save pause state: {}", e);
+    }
+    emit_settings_changed(app, &settings);
+    unregister_hotkeys(app, &settings);
+    register_hotkeys(app, &settings);
+
+    if let Some(item) = app.try_state::<PauseMenuItem>() {
+        let _ = item.0.set_checked(!enabled);
+    }
+    refresh_tray(app);
+
+    if enabled {
+        warm_microphone(app);
+        return;
+    }
+    let app_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<AppState>();
+        if *state.is_recording.lock().unwrap() {
+            cancel_recording(app_handle.clone()).await;
+        }
+        *state.recorder.lock().unwrap() = None;
+    });
+}
+
+/// Open the microphone ahead of recordings, or close it between them, to
+/// match the settings. Paused, it stays closed until recording is resumed.
+fn warm_microphone(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let (warm, device) = {
+        let settings = state.settings.lock().unwrap();
+        (
+            settings.warm_microphone && *state.enabled.lock().unwrap(),
+            settings.preferred_input_device.clone(),
+        )
+    };
+    let mut recorder = state.recorder.lock().unwrap();
+    let recorder = recorder.get_or_insert_with(AudioRecorder::new);
+    recorder.set_input_device(&device);
+    if warm {
+        recorder.warm_up();
+    } else {
+        recorder.cool_down();
+    }
+}
+
+fn toggle_enabled(app: &AppHandle) {
+    let enabled = *app.state::<AppState>().enabled.lock().unwrap();
+    set_enabled(app, !enabled);
+}
+
+/// The tray icon, greyed out while paused, with a colored dot in the corner
+/// while recording, transcribing, or after an error
+fn tray_icon(paused: bool, status: &TrayStatus) -> Result<Image<'static>, String> {
+    let icon = include_bytes!("../icons/icon.png");
+    let mut image_buffer = image::load_from_memory(icon)
+        .map_err(|e| e.to_string())?
+        .to_rgba8();
+    if paused {
+        for pixel in image_buffer.pixels_mut() {
+            let [r, g, b, a] = pixel.0;
+            let grey = ((r as u32 * 30 + g as u32 * 59 + b as u32 * 11) / 100) as u8;
+            pixel.0 = [grey, grey, grey, a / 2];
+        }
+    }
+    let (width, height) = image_buffer.dimensions();
+    let dot = match status {
+        TrayStatus::Idle => None,
+        TrayStatus::Recording => Some([229, 57, 53, 255]),
+        TrayStatus::Processing => Some([251, 192, 45, 255]),
+        TrayStatus::Error(_) => Some([245, 124, 0, 255]),
+    };
+    if let Some(color) = dot {
+        // Outlined in white so it stands out on dark and light taskbars
+        let radius = width.min(height) as f32 / 4.0;
+        let outline = radius * 1.2;
+        let (cx, cy) = (width as f32 - outline, height as f32 - outline);
+        for (x, y, pixel) in image_buffer.enumerate_pixels_mut() {
+            let distance = (x as f32 + 0.5 - cx).hypot(y as f32 + 0.5 - cy);
+            if distance <= radius {
+                pixel.0 = color;
+            } else if distance <= outline {
+                pixel.0 = [255, 255, 255, 255];
+            }
+        }
+    }
+    let rgba = image_buffer.as_bytes().to_vec();
+    Ok(Image::new_owned(rgba, width, height))
+}
+
+/// Show in the tray whether recording hotkeys are paused, which profile is
+/// active, and what the current dictation is doing
+pub(crate) fn refresh_tray(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let reason = paused_reason(&app.state::<AppState>());
+    let status = app.state::<AppState>().tray_status.lock().unwrap().clone();
+    if let Some(item) = app.try_state::<DictationMenuItem>() {
+        let recording = *app.state::<AppState>().is_recording.lock().unwrap();
+        let label = if recording {
+            "Stop Dictation"
+        } else {
+            "Start Dictation"
+        };
+        let _ = item.0.set_text(label);
+        let _ = item.0.set_enabled(status != TrayStatus::Processing);
+    }
+    if let Some(item) = app.try_state::<MeetingMenuItem>() {
+        let meeting = app.state::<AppState>().meeting.lock().unwrap().is_some();
+        let _ = item.0.set_checked(meeting);
+    }
+    if let Ok(icon) = tray_icon(reason.is_some(), &status) {
+        let _ = tray.set_icon(Some(icon));
+    }
+    let mut tooltip = TRAY_TOOLTIP.to_string();
+    if let Some(profile) = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .active_profile()
+    {
+        tooltip.push_str(&format!(" [{}]", profile.name));
+    }
+    if let Some(reason) = reason {
+        tooltip.push_str(&format!(" ({})", reason));
+    }
+    match status {
+        TrayStatus::Idle => {}
+        TrayStatus::Recording => {
+            let elapsed = app
+                .state::<AppState>()
+                .recording_started
+                .lock()
+                .unwrap()
+                .map(|started| started.elapsed().as_secs())
+                .unwrap_or(0);
+            tooltip.push_str(&format!(
+                " - Recording\u{2026} {}:{:02}",
+                elapsed / 60,
+                elapsed % 60
+            ));
+        }
+        TrayStatus::Processing => tooltip.push_str(" - Transcribing"),
+        TrayStatus::Error(message) => tooltip.push_str(&format!(" - {}", message)),
+    }
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+/// Tell screen reader users what the popup is showing, since it never takes
+/// focus for them to read it
+fn announce(app: &AppHandle, text: &str) {
+    #[cfg(target_os = "windows")]
+    if let Some(Ok(hwnd)) = app.get_webview_window("main").map(|window| window.hwnd()) {
+        uia::announce(hwnd.0 as isize, text);
+    }
+    #[cfg(not(target_os = "windows"))]
+    let _ = (app, text);
+}
+
+/// Show `status` on the tray icon. Errors fall back to idle after a while
+/// unless something else has happened since.
+pub(crate) fn set_tray_status(app: &AppHandle, status: TrayStatus) {
+    *app.state::<AppState>().tray_status.lock().unwrap() = status.clone();
+    refresh_tray(app);
+    match &status {
+        TrayStatus::Idle => {}
+        TrayStatus::Recording => announce(app, "Recording started"),
+        TrayStatus::Processing => announce(app, "Processing"),
+        TrayStatus::Error(message) => announce(app, message),
+    }
+
+    if let TrayStatus::Error(_) = status {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_secs(TRAY_ERROR_SECONDS)).await;
+            let state = app.state::<AppState>();
+            let mut current = state.tray_status.lock().unwrap();
+            if *current != status {
+                return;
+            }
+            *current = TrayStatus::Idle;
+            drop(current);
+            refresh_tray(&app);
+        });
+    }
+}
+
+/// Label the tray's update item with the pending update, if any
+pub(crate) fn refresh_update_menu(app: &AppHandle) {
+    let Some(item) = app.try_state::<UpdateMenuItem>() else {
+        return;
+    };
+    let label = match app
+        .state::<updates::PendingUpdate>()
+        .0
+        .lock()
+        .unwrap()
+        .as_ref()
+    {
+        Some(update) => format!("Install Update {}...", update.version),
+        None => "Check for Updates".to_string(),
+    };
+    let _ = item.0.set_text(label);
+}
+
+/// Install the pending update, or check for one when there's none
+async fn on_update_menu(app: AppHandle) {
+    let pending = app
+        .state::<updates::PendingUpdate>()
+        .0
+        .lock()
+        .unwrap()
+        .is_some();
+    if pending {
+        if let Err(e) = updates::install(app.clone()).await {
+            show_notice(&app, &format!("Update failed: {}", e));
+        }
+        return;
+    }
+    match updates::check(&app).await {
+        Ok(Some(version)) => show_notice(
+            &app,
+            &format!(
+                "Version {} is available. Install it from the tray.",
+                version
+            ),
+        ),
+        Ok(None) => show_notice(&app, "Windows Whisper is up to date"),
+        Err(e) => show_notice(&app, &format!("Couldn't check for updates: {}", e)),
+    }
+}
+
+/// Keep the tray in step with quiet hours as they begin and end
+fn watch_quiet_hours(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut was_paused = false;
+        loop {
+            let paused = paused_reason(&app.state::<AppState>()).is_some();
+            if paused != was_paused {
+                refresh_tray(&app);
+                was_paused = paused;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(QUIET_HOURS_CHECK_SECONDS)).await;
+        }
+    });
+}
+
+/// Pick up hand edits to settings.json while the app runs: reload it,
+/// re-register hotkeys, and emit `settings-reloaded`
+fn watch_settings_file(app: AppHandle) {
+    use notify::Watcher;
+
+    std::thread::spawn(move || {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(tx) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log!("Failed to watch settings: {}", e);
+                return;
+            }
+        };
+        // The directory, since saves replace the file by renaming over it
+        if let Err(e) = watcher.watch(&paths::data_dir(), notify::RecursiveMode::NonRecursive) {
+            log!("Failed to watch settings: {}", e);
+            return;
+        }
+
+        while let Ok(event) = rx.recv() {
+            let touches_settings = event.is_ok_and(|event: notify::Event| {
+                event
+                    .paths
+                    .iter()
+                    .any(|path| path.ends_with("settings.json"))
+            });
+            if !touches_settings {
+                continue;
+            }
+            // Let editors that save in several steps finish first
+            std::thread::sleep(std::time::Duration::from_millis(SETTINGS_RELOAD_DELAY_MS));
+            while rx.try_recv().is_ok() {}
+
+            let Some(mut settings) = Settings::reload_if_changed() else {
+                continue;
+            };
+            keymap::canonicalize_hotkeys(&mut settings);
+            settings.normalize_urls();
+            let errors = settings.validate();
+            let rejected = if errors.is_empty() {
+                validate_hotkeys(&app, &settings).err()
+            } else {
+                Some(SettingsError::Invalid { errors })
+            };
+            // Keep running on the current settings until the file is fixed
+            if let Some(error) = rejected {
+                let message = error.message();
+                log!("Ignoring edited settings.json: {}", message);
+                show_notice(&app, &format!("settings.json not applied: {}", message));
+                continue;
+            }
+            use_settings(&app, settings.clone());
+            let _ = app.emit("settings-reloaded", &settings);
+        }
+    });
+}
+
+/// Tell the user, and any open settings window, that settings.json was
+/// corrupted at startup. Waits for the popup's page to load first.
+fn report_settings_recovery(app: AppHandle) {
+    let Some(recovery) = app
+        .state::<AppState>()
+        .settings_recovery
+        .lock()
+        .unwrap()
+        .take()
+    else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(
+            SETTINGS_RECOVERY_NOTICE_DELAY_SECONDS,
+        ))
+        .await;
+        let _ = app.emit("settings-recovered", recovery);
+        show_notice(&app, recovery.message());
+    });
+}
+
+/// Point the user at the crash report from the last run, if there is one.
+/// Waits for the popup's page to load first.
+fn report_previous_crash(app: AppHandle) {
+    let Some(report) = crash::take_unreported() else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(
+            SETTINGS_RECOVERY_NOTICE_DELAY_SECONDS,
+        ))
+        .await;
+        show_notice(
+            &app,
+            &format!(
+                "Windows Whisper ran into a problem last time. A crash report was saved to {}",
+                report.display()
+            ),
+        );
+    });
+}
+
+/// Why a recording shouldn't start right now, if it shouldn't
+fn trigger_ignored_reason(state: &AppState) -> Option<&'static str> {
+    if let Some(reason) = paused_reason(state) {
+        return Some(reason);
+    }
+    let ignore_in_fullscreen = state.settings.lock().unwrap().ignore_in_fullscreen;
+    if ignore_in_fullscreen && target::TargetWindow::foreground().is_some_and(|t| t.is_fullscreen())
+    {
+        return Some("Hotkey ignored in full-screen app");
+    }
+    None
+}
+
+/// React to a recording trigger (hotkey or mouse button) going down or up.
+/// Toggle modes act on press; hold mode starts on press and stops on release.
+fn on_recording_trigger(app: &AppHandle, binding: Option<HotkeyBinding>, pressed: bool) {
+    let state = app.state::<AppState>();
+    let (hold, gestures) = {
+        let settings = state.settings.lock().unwrap();
+        (
+            settings.trigger_mode == TriggerMode::Hold,
+            settings.hotkey_gestures,
+        )
+    };
+    let recording = *state.is_recording.lock().unwrap();
+
+    if pressed && !recording {
+        if let Some(reason) = trigger_ignored_reason(&state) {
+            show_notice(app, reason);
+            return;
+        }
+    }
+
+    // While idle, the main hotkey's taps, double taps, and long presses
+    // are told apart first; stopping a recording stays immediate
+    if gestures && !hold && binding.is_none() {
+        if pressed && !recording {
+            on_gesture_press(app);
+            return;
+        }
+        if !pressed {
+            on_gesture_release(app);
+            return;
+        }
+    }
+
+    let double_press_cancels =
+        state.settings.lock().unwrap().cancel_trigger == CancelTrigger::DoublePress;
+    if pressed && recording && !hold && double_press_cancels {
+        on_stop_press(app);
+        return;
+    }
+
+    if pressed {
+        // Keyboards that double-fire shouldn't start and instantly stop
+        let now = std::time::Instant::now();
+        let previous = state.last_trigger_press.lock().unwrap().replace(now);
+        let debounce = std::time::Duration::from_millis(TRIGGER_DEBOUNCE_MS);
+        if previous.is_some_and(|p| now.duration_since(p) < debounce) {
+            return;
+        }
+    }
+
+    if recording {
+        let min_duration =
+            std::time::Duration::from_millis(state.settings.lock().unwrap().min_recording_ms);
+        let too_short = state
+            .recording_started
+            .lock()
+            .unwrap()
+            .is_some_and(|started| started.elapsed() < min_duration);
+        if too_short {
+            if !pressed && hold {
+                // A quick tap in hold mode has nothing worth transcribing
+                let app_handle = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    cancel_recording(app_handle).await;
+                });
+            }
+            // Otherwise keep recording
+            return;
+        }
+    }
+
+    // Key repeat while holding shouldn't stop the recording
+    let act = if pressed {
+        !(hold && recording)
+    } else {
+        hold && recording
+    };
+    if act {
+        let app_handle = app.clone();
+        let binding = if pressed { binding } else { None };
+        tauri::async_runtime::spawn(async move {
+            handle_hotkey_press(app_handle, binding).await;
+        });
+    }
+}
+
+/// Stop the recording unless a second press follows quickly, which cancels it
+fn on_stop_press(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if state.pending_stop.lock().unwrap().take().is_some() {
+        let app_handle = app.clone();
+        tauri::async_runtime::spawn(async move {
+            cancel_recording(app_handle).await;
+        });
+        return;
+    }
+
+    let id = std::time::Instant::now();
+    *state.pending_stop.lock().unwrap() = Some(id);
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(gestures::DOUBLE_TAP_WINDOW).await;
+        let state = app.state::<AppState>();
+        let stop = {
+            let mut pending = state.pending_stop.lock().unwrap();
+            if *pending == Some(id) {
+                *pending = None;
+                true
+            } else {
+                false
+            }
+        };
+        if stop && *state.is_recording.lock().unwrap() {
+            handle_hotkey_press(app.clone(), None).await;
+        }
+    });
+}
+
+/// Release the cancel shortcut grabbed when recording started
+fn release_cancel_shortcut(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let cancel = state.cancel_shortcut.lock().unwrap().take();
+    if let Some(shortcut) = cancel {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+}
+
+fn on_gesture_press(app: &AppHandle) {
+    let id = app.state::<AppState>().gestures.lock().unwrap().press();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(gestures::LONG_PRESS_THRESHOLD).await;
+        let gesture = app
+            .state::<AppState>()
+            .gestures
+            .lock()
+            .unwrap()
+            .long_press_elapsed(id);
+        if let Some(gesture) = gesture {
+            run_gesture(&app, gesture);
+        }
+    });
+}
+
+fn on_gesture_release(app: &AppHandle) {
+    let release = app.state::<AppState>().gestures.lock().unwrap().release();
+    match release {
+        gestures::Release::Gesture(gesture) => run_gesture(app, gesture),
+        gestures::Release::PendingTap(id) => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(gestures::DOUBLE_TAP_WINDOW).await;
+                let gesture = app
+                    .state::<AppState>()
+                    .gestures
+                    .lock()
+                    .unwrap()
+                    .tap_elapsed(id);
+                if let Some(gesture) = gesture {
+                    run_gesture(&app, gesture);
+                }
+            });
+        }
+        gestures::Release::Ignored => {}
+    }
+}
+
+fn run_gesture(app: &AppHandle, gesture: gestures::Gesture) {
+    let app_handle = app.clone();
+    match gesture {
+        gestures::Gesture::Tap => {
+            tauri::async_runtime::spawn(async move {
+                handle_hotkey_press(app_handle, None).await;
+            });
+        }
+        gestures::Gesture::DoubleTap => {
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = repaste_last_transcription(app_handle).await {
+                    log!("Failed to re-paste transcription: {}", e);
+                }
+            });
+        }
+        gestures::Gesture::LongPress => open_history_window(app),
+    }
+}
+
+/// Open (or focus) the settings window, optionally on a specific tab
+fn open_settings_window(app: &AppHandle, tab: Option<&str>) {
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.set_focus();
+        if let Some(tab) = tab {
+            let _ = window.eval(&format!(
+                "document.querySelector('.tab-btn[data-tab=\"{}\"]')?.click()",
+                tab
+            ));
+        }
+        return;
+    }
+
+    let url = match tab {
+        Some(tab) => format!("settings.html#{}", tab),
+        None => "settings.html".to_string(),
+    };
+    let _ = WebviewWindowBuilder::new(app, "settings", WebviewUrl::App(url.into()))
+        .title("Settings")
+        .inner_size(550.0, 420.0)
+        .resizable(false)
+        .center()
+        .build();
+}
+
+/// Open (or focus) the first-run walkthrough
+fn open_onboarding_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("onboarding") {
+        let _ = window.set_focus();
+        return;
+    }
+
+    let _ = WebviewWindowBuilder::new(app, "onboarding", WebviewUrl::App("onboarding.html".into()))
+        .title("Welcome to Windows Whisper")
+        .inner_size(480.0, 420.0)
+        .resizable(false)
+        .center()
+        .build();
+}
+
+/// Mark an onboarding step done and save, returning the new progress
+pub(crate) fn complete_onboarding_step(
+    app: &AppHandle,
+    step: OnboardingStep,
+) -> Result<OnboardingState, String> {
+    let mut settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    settings.complete_onboarding_step(step);
+    let progress = settings.onboarding_state();
+    apply_settings(app, settings)?;
+    Ok(progress)
+}
+
+/// Record from the chosen microphone for a moment and return the loudest
+/// level heard (0.0 to 1.0), so onboarding can check the mic works
+pub(crate) async fn test_microphone(app: AppHandle) -> Result<f32, String> {
+    if *app.state::<AppState>().is_recording.lock().unwrap() {
+        return Err("A recording is in progress".to_string());
+    }
+    let device = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .preferred_input_device
+        .clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let (level_tx, level_rx) = std::sync::mpsc::channel();
+        let mut recorder = AudioRecorder::new();
+        recorder.set_input_device(&device);
+        recorder.start_recording(Some(level_tx), 0, false)?;
+        let deadline = std::time::Instant::now() + MIC_TEST_DURATION;
+        let mut peak: f32 = 0.0;
+        while let Some(remaining) = deadline.checked_duration_since(std::time::Instant::now()) {
+            match level_rx.recv_timeout(remaining) {
+                Ok(level) => peak = peak.max(level),
+                Err(_) => break,
+            }
+        }
+        tauri::async_runtime::block_on(recorder.stop_recording())?;
+        Ok(peak)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Open (or focus) the history window
+fn open_history_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("history") {
+        let _ = window.set_focus();
+        return;
+    }
+
+    let _ = WebviewWindowBuilder::new(app, "history", WebviewUrl::App("history.html".into()))
+        .title("History")
+        .inner_size(600.0, 520.0)
+        .center()
+        .build();
+}
+
+/// Toggle recording. A `binding` starting a new recording applies its
+/// profile to that recording.
+async fn handle_hotkey_press(app: AppHandle, binding: Option<HotkeyBinding>) {
+    let recording = *app.state::<AppState>().is_recording.lock().unwrap();
+    if recording {
+        finish_recording(app).await;
+    } else {
+        let _ = begin_recording(&app, binding);
+    }
+}
+
+/// Stop the current recording, transcribe it, and send the transcript on
+async fn finish_recording(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let stopped_at = std::time::Instant::now();
+    {
+        *state.is_recording.lock().unwrap() = false;
+    }
+    let duration = state
+        .recording_started
+        .lock()
+        .unwrap()
+        .take()
+        .map(|started| started.elapsed());
+    *state.last_recording_duration.lock().unwrap() = duration;
+
+    release_cancel_shortcut(&app);
+    *state.caret_watch.lock().unwrap() = None;
+    close_captions(&app);
+
+    // Stop chunking and finalize transcription
+    let settings = session_settings(&state);
+    let has_api = !settings.api_url.is_empty() && !settings.api_key.is_empty();
+
+    // Show processing state
+    let _ = app.emit("show-processing", ());
+    set_tray_status(&app, TrayStatus::Processing);
+
+    let control = {
+        let mut guard = state.chunk_control.lock().unwrap();
+        guard.take()
+    };
+
+    if let Some(control) = control {
+        let _ = control.stop_tx.send(true);
+        let _ = control.timer_handle.await;
+
+        let final_chunk = if has_api {
+            drain_chunk_from_recorder(app.clone()).await.ok()
+        } else {
+            None
+        };
+
+        let audio = stop_recorder(app.clone()).await.ok().flatten();
+        *state.last_recording_audio.lock().unwrap() = audio;
+
+        if let Some(chunk) = final_chunk {
+            let _ = control.chunk_tx.send(chunk).await;
+        }
+        drop(control.chunk_tx);
+        let _ = control.worker_handle.await;
+    } else {
+        let audio = stop_recorder(app.clone()).await.ok().flatten();
+        *state.last_recording_audio.lock().unwrap() = audio;
+    }
+
+    let meeting = state.meeting.lock().unwrap().take();
+    if let Some(meeting) = meeting {
+        meeting::finish(&app, meeting);
+        let _ = app.emit("show-idle", ());
+        set_tray_status(&app, TrayStatus::Idle);
+        return;
+    }
+
+    if !has_api {
+        let message = "API not configured. Right-click tray to configure.";
+        let _ = app.emit("show-error", message);
+        set_tray_status(&app, TrayStatus::Error(message.to_string()));
+        return;
+    }
+
+    let chunk_texts = {
+        let mut texts = state.chunk_texts.lock().unwrap();
+        let collected = texts.clone();
+        texts.clear();
+        collected
+    };
+
+    let raw_text = consolidate_chunk_texts(&chunk_texts);
+
+    // Apply post-processing transformations
+    let text = postprocessing::apply_postprocessing(&raw_text, &settings);
+
+    if text.is_empty() {
+        if let Some(problem) = state.api_problem.lock().unwrap().take() {
+            show_api_problem(&app, problem);
+            return;
+        }
+        let message = "No text returned from transcription".to_string();
+        let _ = app.emit("show-error", message.clone());
+        set_tray_status(&app, TrayStatus::Error(message));
+        return;
+    }
+
+    *state.last_session_metadata.lock().unwrap() = Some(EntryMetadata {
+        app: None,
+        duration_ms: duration.map(|d| d.as_millis() as u32),
+        language: Some(settings.language.clone()).filter(|l| !l.is_empty()),
+        backend: backend_name(&settings.api_url),
+        latency_ms: Some(stopped_at.elapsed().as_millis() as u32),
+    });
+
+    if settings.review_before_paste {
+        // History records whatever the user confirms after reviewing
+        begin_review(&app, raw_text, text, &settings);
+        return;
+    }
+
+    dispatch::dispatch_output(app.clone(), raw_text, text, settings).await;
+}
+
+/// Start recording, with `binding`'s profile applied if given
+fn begin_recording(app: &AppHandle, binding: Option<HotkeyBinding>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    {
+        *state.is_recording.lock().unwrap() = true;
+        *state.recording_started.lock().unwrap() = Some(std::time::Instant::now());
+        *state.session_binding.lock().unwrap() = binding;
+        *state.api_problem.lock().unwrap() = None;
+    }
+
+    // Initialize recorder if needed
+    {
+        let mut recorder = state.recorder.lock().unwrap();
+        if recorder.is_none() {
+            *recorder = Some(AudioRecorder::new());
+        }
+
+        if let Some(ref mut rec) = *recorder {
+            // Create volume channel
+            let (vol_tx, vol_rx) = std::sync::mpsc::channel();
+
+            // Hold mode ends on release, and meetings run through pauses
+            let silence_stop = {
+                let settings = state.settings.lock().unwrap();
+                let seconds = settings.silence_stop_seconds;
+                (seconds > 0
+                    && settings.trigger_mode != TriggerMode::Hold
+                    && state.meeting.lock().unwrap().is_none())
+                .then(|| std::time::Duration::from_secs(seconds))
+            };
+            let started = *state.recording_started.lock().unwrap();
+
+            // Spawn listener with throttling
+            let app_handle = app.clone();
+            std::thread::spawn(move || {
+                let mut max_level: f32 = 0.0;
+                let mut last_emit = std::time::Instant::now();
+                let mut silence = silence_stop.map(audio::SilenceDetector::new);
+
+                while let Ok(level) = vol_rx.recv() {
+                    if level > max_level {
+                        max_level = level;
+                    }
+                    let silent = silence
+                        .as_mut()
+                        .is_some_and(|detector| detector.hear(level, std::time::Instant::now()));
+                    if let (true, Some(started)) = (silent, started) {
+                        log!("Stopping recording after silence");
+                        silence = None;
+                        tauri::async_runtime::spawn(stop_recording_started_at(
+                            app_handle.clone(),
+                            started,
+                        ));
+                    }
+
+                    if last_emit.elapsed() >= std::time::Duration::from_millis(20) {
+                        let _ = app_handle.emit("audio-level", max_level);
+                        max_level = 0.0;
+                        last_emit = std::time::Instant::now();
+                    }
+                }
+            });
+
+            // A meeting's audio would be hours long; only its text is kept
+            let keep_audio = state.settings.lock().unwrap().keep_audio
+                && state.meeting.lock().unwrap().is_none();
+            rec.set_input_device(&state.settings.lock().unwrap().preferred_input_device);
+            if let Err(e) = rec.start_recording(Some(vol_tx), CHUNK_OVERLAP_SECONDS, keep_audio) {
+                let message = format!("Failed to start recording: {}", e);
+                let _ = app.emit("show-error", message.clone());
+                set_tray_status(app, TrayStatus::Error(message.clone()));
+                *state.is_recording.lock().unwrap() = false;
+                return Err(message);
+            }
+        }
+    }
+
+    {
+        let mut texts = state.chunk_texts.lock().unwrap();
+        texts.clear();
+    }
+    {
+        let mut control = state.chunk_control.lock().unwrap();
+        *control = None;
+    }
+
+    let (chunk_tx, mut chunk_rx) = mpsc::channel::<Vec<u8>>(4);
+    let (stop_tx, mut stop_rx) = watch::channel(false);
+
+    let worker_app = app.clone();
+    let worker_handle = tauri::async_runtime::spawn(async move {
+        while let Some(chunk) = chunk_rx.recv().await {
+            let settings = {
+                let state = worker_app.state::<AppState>();
+                session_settings(&state)
+            };
+            if settings.api_url.is_empty() || settings.api_key.is_empty() {
+                continue;
+            }
+            match transcribe_audio_chunk(
+                &settings.api_url,
+                &settings.api_key,
+                &settings.language,
+                &settings.boosted_terms,
+                chunk,
+            )
+            .await
+            {
+                Ok(text) => {
+                    let trimmed = text.trim();
+                    if !trimmed.is_empty() {
+                        let state = worker_app.state::<AppState>();
+                        let mut texts = state.chunk_texts.lock().unwrap();
+                        texts.push(trimmed.to_string());
+                        let _ = worker_app.emit("caption-text", consolidate_chunk_texts(&texts));
+                    }
+                }
+                Err(e) => {
+                    log!("Failed to transcribe chunk: {}", e);
+                    if let Some(problem) = ApiProblem::from_error(&e) {
+                        report_api_problem(&worker_app, problem);
+                    }
+                }
+            }
+        }
+    });
+
+    let timer_app = app.clone();
+    let timer_tx = chunk_tx.clone();
+    let timer_handle = tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(CHUNK_SECONDS));
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if *stop_rx.borrow() {
+                        break;
+                    }
+                    match drain_chunk_from_recorder(timer_app.clone()).await {
+                        Ok(chunk) => {
+                            if !chunk.is_empty() {
+                                if timer_tx.send(chunk).await.is_err() {
+                                    break;
+                                }
+                            }
+                        }
+                        Err(_) => {}
+                    }
+                }
+                _ = stop_rx.changed() => {
+                    if *stop_rx.borrow() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    {
+        let mut control = state.chunk_control.lock().unwrap();
+        *control = Some(ChunkControl {
+            stop_tx,
+            chunk_tx,
+            timer_handle,
+            worker_handle,
+        });
+    }
+
+    // Remember where the transcript should go before the popup appears
+    *state.recording_target.lock().unwrap() = target::TargetWindow::foreground();
+
+    // Grab the cancel key only while recording
+    let cancel = {
+        let settings = state.settings.lock().unwrap();
+        match settings.cancel_trigger {
+            CancelTrigger::Key => parse_hotkey(&settings.cancel_hotkey),
+            CancelTrigger::DoublePress | CancelTrigger::None => None,
+        }
+    };
+    if let Some(shortcut) = cancel {
+        if let Err(e) = app.global_shortcut().register(shortcut) {
+            log!("Failed to register cancel hotkey: {}", e);
+        }
+    }
+    *state.cancel_shortcut.lock().unwrap() = cancel;
+
+    show_popup(app);
+    let _ = app.emit("show-recording", ());
+    set_tray_status(app, TrayStatus::Recording);
+    follow_caret(app);
+    show_captions(app);
+
+    let (trigger_mode, max_seconds) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.trigger_mode, settings.max_recording_seconds)
+    };
+    let started = *state.recording_started.lock().unwrap();
+    if let Some(started) = started {
+        tick_recording_timer(app, started);
+    }
+    let meeting = state.meeting.lock().unwrap().is_some();
+    if let (TriggerMode::ToggleWithLimit, Some(started), false) = (trigger_mode, started, meeting) {
+        if max_seconds > 0 {
+            schedule_recording_limit(app, started, std::time::Duration::from_secs(max_seconds));
+        }
+    }
+    Ok(())
+}
+
+/// Start a recording for a command or integration, as the hotkey would
+pub(crate) fn start_recording(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if *state.is_recording.lock().unwrap() {
+        return Err("Already recording".to_string());
+    }
+    if let Some(reason) = trigger_ignored_reason(&state) {
+        show_notice(app, reason);
+        return Err(reason.to_string());
+    }
+    begin_recording(app, None)
+}
+
+/// Stop and transcribe the recording in progress, for a command or
+/// integration
+pub(crate) async fn stop_recording(app: AppHandle) -> Result<(), String> {
+    if !*app.state::<AppState>().is_recording.lock().unwrap() {
+        return Err("Not recording".to_string());
+    }
+    finish_recording(app).await;
+    Ok(())
+}
+
+/// Throw away the recording in progress, for a command or integration
+pub(crate) async fn discard_recording(app: AppHandle) -> Result<(), String> {
+    if !*app.state::<AppState>().is_recording.lock().unwrap() {
+        return Err("Not recording".to_string());
+    }
+    cancel_recording(app).await;
+    Ok(())
+}
+
+/// Start a meeting from the tray, or stop the one being recorded
+fn toggle_meeting(app: &AppHandle) {
+    if app.state::<AppState>().meeting.lock().unwrap().is_some() {
+        tauri::async_runtime::spawn(stop_recording(app.clone()));
+        return;
+    }
+    if let Err(e) = meeting::start(app) {
+        log!("Failed to start meeting: {}", e);
+        show_notice(app, &e);
+    }
+    // A check menu item toggles itself when clicked; put it back in step
+    refresh_tray(app);
+}
+
+/// Stop the recording that began at `started` once `limit` has elapsed,
+/// unless the user already stopped it.
+fn schedule_recording_limit(
+    app: &AppHandle,
+    started: std::time::Instant,
+    limit: std::time::Duration,
+) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(limit).await;
+        stop_recording_started_at(app, started).await;
+    });
+}
+
+/// Stop the recording that began at `started`, if it's still going
+async fn stop_recording_started_at(app: AppHandle, started: std::time::Instant) {
+    let state = app.state::<AppState>();
+    let still_running = *state.is_recording.lock().unwrap()
+        && *state.recording_started.lock().unwrap() == Some(started);
+    if still_running {
+        handle_hotkey_press(app.clone(), None).await;
+    }
+}
+
+/// Keep the recording time in the tray tooltip current until the recording
+/// that began at `started` ends
+fn tick_recording_timer(app: &AppHandle, started: std::time::Instant) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(TRAY_TIMER_TICK_SECONDS)).await;
+            let state = app.state::<AppState>();
+            let still_running = *state.is_recording.lock().unwrap()
+                && *state.recording_started.lock().unwrap() == Some(started);
+            if !still_running {
+                break;
+            }
+            refresh_tray(&app);
+        }
+    });
+}
+
+/// Transcribe an audio file with the configured server and post-processing,
+/// for `--transcribe`
+async fn transcribe_file(path: &std::path::Path) -> Result<String, String> {
+    let (settings, _) = Settings::load();
+    transcribe_audio_file(path, &settings.with_active_profile()).await
+}
+
+/// Transcribe an audio file in the same overlapping chunks as a recording,
+/// then post-process the joined text
+pub(crate) async fn transcribe_audio_file(
+    path: &std::path::Path,
+    settings: &Settings,
+) -> Result<String, String> {
+    let chunks = decode_file_chunks(path, settings).await?;
+    let mut texts = Vec::new();
+    for chunk in chunks {
+        let text = transcribe_audio_chunk(
+            &settings.api_url,
+            &settings.api_key,
+            &settings.language,
+            &settings.boosted_terms,
+            chunk,
+        )
+        .await?;
+        let text = text.trim();
+        if !text.is_empty() {
+            texts.push(text.to_string());
+        }
+    }
+    let raw_text = consolidate_chunk_texts(&texts);
+    Ok(postprocessing::apply_postprocessing(&raw_text, settings))
+}
+
+/// Transcribe an audio file into timed, post-processed subtitle cues
+pub(crate) async fn transcribe_audio_file_cues(
+    path: &std::path::Path,
+    settings: &Settings,
+) -> Result<Vec<subtitles::Cue>, String> {
+    let chunks = decode_file_chunks(path, settings).await?;
+    // Each chunk starts the overlap before the previous one ends
+    let stride = (CHUNK_SECONDS as u32 - CHUNK_OVERLAP_SECONDS) as f64;
+    let mut cues = Vec::new();
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        let response = request_transcription(
+            &settings.api_url,
+            &settings.api_key,
+            &settings.language,
+            &settings.boosted_terms,
+            chunk,
+            true,
+        )
+        .await?;
+        let has_text = response
+            .text
+            .as_deref()
+            .is_some_and(|text| !text.trim().is_empty());
+        if response.segments.is_empty() && has_text {
+            return Err(
+                "The server didn't return timestamps. Redeploy the worker to get subtitles."
+                    .to_string(),
+            );
+        }
+        subtitles::append_chunk(&mut cues, index as f64 * stride, response.segments);
+    }
+    for cue in &mut cues {
+        cue.text = postprocessing::apply_postprocessing(&cue.text, settings);
+    }
+    cues.retain(|cue| !cue.text.is_empty());
+    Ok(cues)
+}
+
+/// Decode `path` into recording-sized WAV chunks off the async runtime
+async fn decode_file_chunks(
+    path: &std::path::Path,
+    settings: &Settings,
+) -> Result<Vec<Vec<u8>>, String> {
+    if settings.api_url.is_empty() || settings.api_key.is_empty() {
+        return Err("API not configured. Set the worker URL and API key in Settings.".to_string());
+    }
+    let owned_path = path.to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        audio_file::decode_chunks(&owned_path, CHUNK_SECONDS as u32, CHUNK_OVERLAP_SECONDS)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Transcribe audio files one after another and copy the combined text, for
+/// voice memos and other recordings made elsewhere
+pub(crate) async fn transcribe_files(
+    app: &AppHandle,
+    paths: Vec<std::path::PathBuf>,
+) -> Result<String, String> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+
+    if paths.is_empty() {
+        return Err("No files to transcribe".to_string());
+    }
+    if let Some(path) = paths.iter().find(|path| !audio_file::is_supported(path)) {
+        return Err(format!(
+            "{} isn't a supported audio file ({})",
+            path.display(),
+            audio_file::EXTENSIONS.join(", ")
+        ));
+    }
+    let settings = app
+        .state::<AppState>()
+        .settings
+        .lock()
+        .unwrap()
+        .with_active_profile();
+
+    let mut texts = Vec::new();
+    for path in &paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let text = match settings.subtitle_format {
+            // Subtitles go next to the file, and their text to the clipboard
+            Some(format) => {
+                let cues = transcribe_audio_file_cues(path, &settings)
+                    .await
+                    .map_err(|e| format!("{}: {}", name, e))?;
+                let subtitle_path = path.with_extension(format.extension());
+                std::fs::write(&subtitle_path, subtitles::render(format, &cues))
+                    .map_err(|e| format!("Failed to write {}: {}", subtitle_path.display(), e))?;
+                cues.iter()
+                    .map(|cue| cue.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            None => transcribe_audio_file(path, &settings)
+                .await
+                .map_err(|e| format!("{}: {}", name, e))?,
+        };
+        if !text.is_empty() {
+            texts.push(text);
+        }
+    }
+    let text = texts.join("\n\n");
+    if text.is_empty() {
+        return Err("No text returned from transcription".to_string());
+    }
+    app.clipboard()
+        .write_text(text.clone())
+        .map_err(|e| format!("Failed to copy: {}", e))?;
+    Ok(text)
+}
+
+/// Open the window audio files are dropped on to transcribe them
+fn open_transcribe_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("transcribe") {
+        let _ = window.set_focus();
+        return;
+    }
+
+    let _ = WebviewWindowBuilder::new(app, "transcribe", WebviewUrl::App("transcribe.html".into()))
+        .title("Transcribe Files")
+        .inner_size(420.0, 320.0)
+        .center()
+        .build();
+}
+
+/// Act on `--start`, `--stop`, or `--toggle` the way the hotkey would
+fn run_cli_command(app: &AppHandle, command: cli::CliCommand) {
+    let recording = *app.state::<AppState>().is_recording.lock().unwrap();
+    match command {
+        cli::CliCommand::Start if !recording => {
+            let _ = start_recording(app);
+        }
+        cli::CliCommand::Stop | cli::CliCommand::Toggle if recording => {
+            tauri::async_runtime::spawn(stop_recording(app.clone()));
+        }
+        cli::CliCommand::Toggle => {
+            let _ = start_recording(app);
+        }
+        // Nothing to do, or handled in the launching process without
+        // starting the app
+        cli::CliCommand::Start | cli::CliCommand::Stop | cli::CliCommand::Transcribe(_) => {}
+    }
+}
+
+/// A parsed recording or action trigger, for comparing hotkeys
+#[derive(PartialEq)]
+enum Trigger {
+    Key(Shortcut),
+    Mouse(mouse_hook::MouseButton),
+}
+
+/// Check that every configured hotkey parses, is unique, and can be
+/// registered, using a trial registration for ones we don't already own.
+pub(crate) fn validate_hotkeys(app: &AppHandle, settings: &Settings) -> Result<(), SettingsError> {
+    let mut seen: Vec<(String, Trigger)> = Vec::new();
+
+    for (field, hotkey) in settings.configured_hotkeys() {
+        let trigger = match mouse_hook::parse_mouse_trigger(&hotkey) {
+            Some(button) => Trigger::Mouse(button),
+            None => match parse_hotkey(&hotkey) {
+                Some(shortcut) => Trigger::Key(shortcut),
+                None => return Err(SettingsError::UnparseableHotkey { field, hotkey }),
+            },
+        };
+
+        if let Some((other_field, _)) = seen.iter().find(|(_, t)| *t == trigger) {
+            return Err(SettingsError::DuplicateHotkey {
+                field,
+                other_field: other_field.clone(),
+                hotkey,
+            });
+        }
+
+        if let Trigger::Key(shortcut) = trigger {
+            if !can_register(app, shortcut) {
+                return Err(SettingsError::HotkeyInUse { field, hotkey });
+            }
+        }
+
+        seen.push((field, trigger));
+    }
+
+    if settings.cancel_trigger == CancelTrigger::Key {
+        let field = "cancel_hotkey".to_string();
+        let hotkey = settings.cancel_hotkey.clone();
+        let Some(shortcut) = parse_hotkey(&hotkey) else {
+            return Err(SettingsError::UnparseableHotkey { field, hotkey });
+        };
+        if let Some((other_field, _)) = seen.iter().find(|(_, t)| *t == Trigger::Key(shortcut)) {
+            return Err(SettingsError::DuplicateHotkey {
+                field,
+                other_field: other_field.clone(),
+                hotkey,
+            });
+        }
+        // Only grabbed while recording, when failing to would go unnoticed
+        if !can_register(app, shortcut) {
+            return Err(SettingsError::HotkeyInUse { field, hotkey });
+        }
+    }
+
+    // Only registered during a review, but they'd shadow a hotkey meanwhile
+    if settings.review_before_paste && !settings.review_editable {
+        for (field, hotkey) in settings.review_hotkeys.configured() {
+            let Some(shortcut) = parse_hotkey(&hotkey) else {
+                return Err(SettingsError::UnparseableHotkey { field, hotkey });
+            };
+            let trigger = Trigger::Key(shortcut);
+            if let Some((other_field, _)) = seen.iter().find(|(_, t)| *t == trigger) {
+                return Err(SettingsError::DuplicateHotkey {
+                    field,
+                    other_field: other_field.clone(),
+                    hotkey,
+                });
+            }
+            if !can_register(app, shortcut) {
+                return Err(SettingsError::HotkeyInUse { field, hotkey });
+            }
+            seen.push((field, trigger));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `shortcut` is already ours or free, found by registering it briefly
+fn can_register(app: &AppHandle, shortcut: Shortcut) -> bool {
+    let shortcuts = app.global_shortcut();
+    if shortcuts.is_registered(shortcut) {
+        return true;
+    }
+    if shortcuts.register(shortcut).is_err() {
+        return false;
+    }
+    let _ = shortcuts.unregister(shortcut);
+    true
+}
+
+/// Register every hotkey in `settings` and point the mouse hook at the
+/// configured mouse-button triggers.
+pub(crate) fn register_hotkeys(app: &AppHandle, settings: &Settings) {
+    // While paused only the non-recording hotkeys (pause, undo...) stay
+    let enabled = *app.state::<AppState>().enabled.lock().unwrap();
+    let mut hooked_shortcuts = Vec::new();
+    for (field, hotkey) in settings.configured_hotkeys() {
+        if mouse_hook::parse_mouse_trigger(&hotkey).is_some() {
+            continue;
+        }
+        let recording_trigger = field == "hotkey" || field.starts_with("hotkey_bindings");
+        if recording_trigger && !enabled {
+            continue;
+        }
+        match parse_hotkey(&hotkey) {
+            Some(shortcut) if settings.swallow_trigger_keys && recording_trigger => {
+                hooked_shortcuts.push(shortcut);
+            }
+            Some(shortcut) => {
+                if let Err(e) = app.global_shortcut().register(shortcut) {
+                    log!("Failed to register {} ({}): {}", field, hotkey, e);
+                }
+            }
+            None => log!("Invalid {}: {}", field, hotkey),
+        }
+    }
+
+    // Only recording triggers are routed from the hook, so other buttons pass through
+    let mouse_buttons: Vec<_> = settings
+        .configured_hotkeys()
+        .iter()
+        .filter(|_| enabled)
+        .filter(|(field, _)| field == "hotkey" || field.starts_with("hotkey_bindings"))
+        .filter_map(|(_, hotkey)| mouse_hook::parse_mouse_trigger(hotkey))
+        .collect();
+    let app_handle = app.clone();
+    mouse_hook::install(&mouse_buttons, move |button, pressed| {
+        let state = app_handle.state::<AppState>();
+        let binding = recording_trigger_binding(&state, |hotkey| {
+            mouse_hook::parse_mouse_trigger(hotkey) == Some(button)
+        });
+        if let Some(binding) = binding {
+            on_recording_trigger(&app_handle, binding, pressed);
+        }
+    });
+
+    let app_handle = app.clone();
+    let hid_trigger = settings.hid_trigger.clone().filter(|_| enabled);
+    hid_trigger::install(hid_trigger, move |pressed| {
+        on_recording_trigger(&app_handle, None, pressed);
+    });
+
+    let app_handle = app.clone();
+    keyboard_hook::install(&hooked_shortcuts, move |shortcut, pressed| {
+        let state = app_handle.state::<AppState>();
+        if let Some(binding) = recording_hotkey_binding(&state, &shortcut) {
+            if track_held_hotkey(&state, &shortcut, pressed) {
+                on_recording_trigger(&app_handle, binding, pressed);
+            }
+        }
+    });
+}
+
+/// Release every hotkey registered for `settings`
+pub(crate) fn unregister_hotkeys(app: &AppHandle, settings: &Settings) {
+    // Releases of unregistered hotkeys never arrive
+    app.state::<AppState>().held_hotkeys.lock().unwrap().clear();
+    for (_, hotkey) in settings.configured_hotkeys() {
+        if let Some(shortcut) = parse_hotkey(&hotkey) {
+            let _ = app.global_shortcut().unregister(shortcut);
+        }
+    }
+}
+
+pub fn run() {
+    crash::install_panic_hook();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::parse(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            cli::attach_parent_console();
+            eprintln!("{}\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Some(cli::CliCommand::Transcribe(path)) = &command {
+        cli::attach_parent_console();
+        match tauri::async_runtime::block_on(transcribe_file(path)) {
+            Ok(text) => println!("{}", text),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    tauri::Builder::default()
+        // Launching again hands the command line to the running instance
+        .plugin(tauri_plugin_single_instance::init(
+            |app, argv, _cwd| match cli::parse(argv.get(1..).unwrap_or_default()) {
+                Ok(Some(command)) => run_cli_command(app, command),
+                Ok(None) => open_settings_window(app, None),
+                Err(e) => log!("{}", e),
+            },
+        ))
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
                 .with_handler(move |app, shortcut, event| {
+                    if event.state == ShortcutState::Released {
+                        let state = app.state::<AppState>();
+                        if let Some(binding) = recording_hotkey_binding(&state, shortcut) {
+                            if track_held_hotkey(&state, shortcut, false) {
+                                on_recording_trigger(app, binding, false);
+                            }
+                        }
+                        return;
+                    }
+
                     if event.state == ShortcutState::Pressed {
-                        // Check if it's the configured hotkey
                         let state = app.state::<AppState>();
-                        let hotkey_str = state.settings.lock().unwrap().hotkey.clone();
-                        if let Some(cfg_shortcut) = parse_hotkey(&hotkey_str) {
-                            if shortcut == &cfg_shortcut {
+
+                        // Review shortcuts only answer while a transcript is pending
+                        let review_action = state
+                            .pending_review
+                            .lock()
+                            .unwrap()
+                            .as_ref()
+                            .and_then(|pending| review_action_for(&pending.shortcuts, shortcut));
+                        if let Some(action) = review_action {
+                            let app_handle = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                resolve_review(app_handle, action, None).await;
+                            });
+                            return;
+                        }
+
+                        if let Some(binding) = recording_hotkey_binding(&state, shortcut) {
+                            if track_held_hotkey(&state, shortcut, true) {
+                                on_recording_trigger(app, binding, true);
+                            }
+                            return;
+                        }
+
+                        let pause_str = state.settings.lock().unwrap().pause_hotkey.clone();
+                        if parse_hotkey(&pause_str).as_ref() == Some(shortcut) {
+                            // Re-registering hotkeys from inside their own handler
+                            // is left to another thread
+                            let app_handle = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                toggle_enabled(&app_handle);
+                            });
+                            return;
+                        }
+
+                        let repaste_str = state.settings.lock().unwrap().repaste_hotkey.clone();
+                        if let Some(repaste_shortcut) = parse_hotkey(&repaste_str) {
+                            if shortcut == &repaste_shortcut {
+                                let app_handle = app.clone();
+                                tauri::async_runtime::spawn(async move {
+                                    if let Err(e) = repaste_last_transcription(app_handle).await {
+                                        log!("Failed to re-paste transcription: {}", e);
+                                    }
+                                });
+                                return;
+                            }
+                        }
+
+                        let history_offset = state
+                            .settings
+                            .lock()
+                            .unwrap()
+                            .history_paste_hotkeys
+                            .iter()
+                            .find(|paste| parse_hotkey(&paste.hotkey).as_ref() == Some(shortcut))
+                            .map(|paste| paste.offset);
+                        if let Some(offset) = history_offset {
+                            let app_handle = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = paste_history_entry(app_handle, offset).await {
+                                    log!("Failed to paste transcription: {}", e);
+                                }
+                            });
+                            return;
+                        }
+
+                        let undo_str = state.settings.lock().unwrap().undo_hotkey.clone();
+                        if let Some(undo_shortcut) = parse_hotkey(&undo_str) {
+                            if shortcut == &undo_shortcut {
                                 let app_handle = app.clone();
                                 tauri::async_runtime::spawn(async move {
-                                    handle_hotkey_press(app_handle).await;
+                                    if let Err(e) = undo_last_dictation(app_handle).await {
+                                        log!("Failed to undo dictation: {}", e);
+                                    }
                                 });
                                 return;
                             }
                         }
 
-                        // Check if it is Escape
-                        if shortcut.matches(Modifiers::empty(), Code::Escape) {
+                        let cancel = *state.cancel_shortcut.lock().unwrap();
+                        if cancel.as_ref() == Some(shortcut) {
                             let app_handle = app.clone();
                             tauri::async_runtime::spawn(async move {
                                 cancel_recording(app_handle).await;
@@ -776,65 +3290,201 @@ pub fn run() {
         )
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_store::Builder::new().build())
+        .plugin(tauri_plugin_updater::Builder::new().build())
         .manage(AppState::default())
+        .manage(updates::PendingUpdate(Mutex::new(None)))
+        .on_window_event(|window, event| {
+            // Closing the review window without choosing counts as discarding
+            if window.label() == "review" && matches!(event, WindowEvent::Destroyed) {
+                let app_handle = window.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    resolve_review(app_handle, ReviewAction::Discard, None).await;
+                });
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             commands::hide_popup,
             commands::get_settings,
             commands::save_settings,
+            commands::list_supported_keys,
+            commands::list_audio_devices,
+            commands::set_enabled,
+            commands::start_recording,
+            commands::stop_recording,
+            commands::cancel_recording,
             commands::get_history,
-            commands::clear_history
+            commands::get_onboarding_state,
+            commands::complete_onboarding_step,
+            commands::test_microphone,
+            commands::test_connection,
+            commands::open_settings,
+            commands::reset_settings,
+            commands::set_active_profile,
+            commands::get_history_page,
+            commands::search_history,
+            commands::export_history,
+            commands::export_diagnostics,
+            commands::run_diagnostics,
+            commands::transcribe_files,
+            commands::transcribe_folder,
+            commands::pin_entry,
+            commands::unpin_entry,
+            commands::copy_history_entry,
+            commands::play_history_audio,
+            commands::stop_history_audio,
+            commands::delete_entry,
+            commands::restore_entry,
+            commands::get_trash,
+            commands::get_statistics,
+            commands::clear_history,
+            commands::wipe_all_data,
+            commands::resolve_review,
+            commands::get_pending_transcript,
+            commands::confirm_paste,
+            commands::discard_transcript,
+            commands::undo_last_dictation,
+            commands::repaste_last_transcription,
+            commands::paste_history_entry
         ])
-        .setup(|app| {
+        .setup(move |app| {
             // Create tray menu
             let settings_item =
                 MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
+            let dictation_item =
+                MenuItem::with_id(app, "dictation", "Start Dictation", true, None::<&str>)?;
+            let repaste_item = MenuItem::with_id(
+                app,
+                "repaste",
+                "Re-paste Last Transcription",
+                true,
+                None::<&str>,
+            )?;
+            let recent_submenu = Submenu::with_id(app, "recent", "Recent Transcriptions", true)?;
+            let profile_submenu = Submenu::with_id(app, "profiles", "Profile", true)?;
+            let history_item = MenuItem::with_id(app, "history", "History...", true, None::<&str>)?;
+            let transcribe_item =
+                MenuItem::with_id(app, "transcribe", "Transcribe Files...", true, None::<&str>)?;
+            let meeting_item =
+                CheckMenuItem::with_id(app, "meeting", "Meeting Mode", true, false, None::<&str>)?;
+            let paused = !*app.state::<AppState>().enabled.lock().unwrap();
+            let pause_item = CheckMenuItem::with_id(
+                app,
+                "pause",
+                "Pause Windows Whisper",
+                true,
+                paused,
+                None::<&str>,
+            )?;
+            let update_item =
+                MenuItem::with_id(app, "update", "Check for Updates", true, None::<&str>)?;
             let quit_item = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&settings_item, &quit_item])?;
-
-            let icon = include_bytes!("../icons/icon.png");
-            let image_buffer = image::load_from_memory(icon)
-                .map_err(|e| e.to_string())?
-                .to_rgba8();
-            let (width, height) = image_buffer.dimensions();
-            let rgba = image_buffer.as_bytes().to_vec();
-            let icon_image = Image::new(&rgba, width, height);
+            let menu = Menu::with_items(
+                app,
+                &[
+                    &dictation_item,
+                    &PredefinedMenuItem::separator(app)?,
+                    &repaste_item,
+                    &recent_submenu,
+                    &history_item,
+                    &transcribe_item,
+                    &meeting_item,
+                    &PredefinedMenuItem::separator(app)?,
+                    &profile_submenu,
+                    &pause_item,
+                    &settings_item,
+                    &update_item,
+                    &quit_item,
+                ],
+            )?;
+            app.manage(PauseMenuItem(pause_item));
+            app.manage(DictationMenuItem(dictation_item));
+            app.manage(MeetingMenuItem(meeting_item));
+            app.manage(UpdateMenuItem(update_item));
+            app.manage(RecentSubmenu(recent_submenu));
+            refresh_recent_menu(app.handle());
+            app.manage(ProfileSubmenu(profile_submenu));
+            refresh_profile_menu(app.handle());
 
             // Create tray icon
-            let _tray = TrayIconBuilder::new()
-                .icon(icon_image)
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
+                .icon(tray_icon(paused, &TrayStatus::Idle)?)
                 .menu(&menu)
-                .tooltip("Windows Whisper - Push to Talk")
-                .on_menu_event(|app, event| {
-                    match event.id.as_ref() {
-                        "settings" => {
-                            // Open settings window
-                            if app.get_webview_window("settings").is_none() {
-                                let _ = WebviewWindowBuilder::new(
-                                    app,
-                                    "settings",
-                                    WebviewUrl::App("settings.html".into()),
-                                )
-                                .title("Settings")
-                                .inner_size(550.0, 420.0)
-                                .resizable(false)
-                                .center()
-                                .build();
+                .tooltip(TRAY_TOOLTIP)
+                .on_menu_event(|app, event| match event.id.as_ref() {
+                    "settings" => open_settings_window(app, None),
+                    "history" => open_history_window(app),
+                    "transcribe" => open_transcribe_window(app),
+                    "pause" => toggle_enabled(app),
+                    "meeting" => toggle_meeting(app),
+                    "update" => {
+                        tauri::async_runtime::spawn(on_update_menu(app.clone()));
+                    }
+                    // Same flow as the hotkey, for when it can't be pressed
+                    // (e.g. a remote desktop session grabbing keys)
+                    "dictation" => {
+                        // Starting is checked against pausing and quiet
+                        // hours, as the hotkey is
+                        let app_handle = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            let recording =
+                                *app_handle.state::<AppState>().is_recording.lock().unwrap();
+                            let result = if recording {
+                                stop_recording(app_handle.clone()).await
+                            } else {
+                                start_recording(&app_handle)
+                            };
+                            if let Err(e) = result {
+                                log!("Tray dictation: {}", e);
+                            }
+                        });
+                    }
+                    "repaste" => {
+                        let app_handle = app.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = repaste_last_transcription(app_handle).await {
+                                log!("Failed to re-paste transcription: {}", e);
+                            }
+                        });
+                    }
+                    "quit" => {
+                        app.exit(0);
+                    }
+                    id => {
+                        let entry = id.strip_prefix("recent:").and_then(|id| id.parse().ok());
+                        if let Some(entry) = entry {
+                            let app_handle = app.clone();
+                            tauri::async_runtime::spawn(async move {
+                                if let Err(e) = copy_history_entry(&app_handle, entry).await {
+                                    log!("{}", e);
+                                }
+                            });
+                        } else if let Some(profile) = id.strip_prefix("profile:") {
+                            if let Err(e) = set_active_profile(app, profile) {
+                                log!("Failed to switch profile: {}", e);
                             }
                         }
-                        "quit" => {
-                            app.exit(0);
-                        }
-                        _ => {}
                     }
                 })
                 .build(app)?;
-
-            // Register global shortcut
-            let state = app.state::<AppState>();
-            let hotkey_str = state.settings.lock().unwrap().hotkey.clone();
-
-            if let Some(shortcut) = parse_hotkey(&hotkey_str) {
-                app.global_shortcut().register(shortcut)?;
+            refresh_tray(app.handle());
+
+            // Register global shortcuts
+            let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+            register_hotkeys(app.handle(), &settings);
+
+            automation::restart(app.handle(), &settings.automation_api);
+            warm_microphone(app.handle());
+            watch_quiet_hours(app.handle().clone());
+            updates::watch(app.handle().clone());
+            report_settings_recovery(app.handle().clone());
+            report_previous_crash(app.handle().clone());
+            watch_settings_file(app.handle().clone());
+
+            if settings.first_run {
+                open_onboarding_window(app.handle());
+            }
+            if let Some(command) = command {
+                run_cli_command(app.handle(), command);
             }
 
             Ok(())
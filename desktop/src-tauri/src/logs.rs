@@ -0,0 +1,82 @@
+//! The app's log: stderr, a file in the data folder, and the most recent
+//! lines in memory for crash reports and diagnostics
+
+use crate::paths;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, TryLockError};
+
+/// Lines kept in memory
+const RECENT_LINES: usize = 200;
+/// Size at which the log file is moved aside to `.old` and started afresh
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+static RECENT: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+/// Secrets from the settings in use, masked in every line
+static SECRETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Log a formatted line, like `eprintln!`
+macro_rules! log {
+    ($($arg:tt)*) => {
+        $crate::logs::record(&format!($($arg)*))
+    };
+}
+
+/// The log file in the data folder
+pub fn file_path() -> PathBuf {
+    paths::data_dir().join("windows-whisper.log")
+}
+
+/// Mask `secrets` in every line logged from now on, as well as anything
+/// that looks like a credential
+pub fn set_secrets(secrets: Vec<String>) {
+    *SECRETS.lock().unwrap_or_else(|e| e.into_inner()) = secrets;
+}
+
+/// Write `line` to stderr and the log file, and keep it for `recent`.
+/// Secrets are masked first, so none reach the file or a diagnostics
+/// bundle.
+pub fn record(line: &str) {
+    let line = crate::redact::text(line, &SECRETS.lock().unwrap_or_else(|e| e.into_inner()));
+    eprintln!("{}", line);
+    let line = format!(
+        "{} {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        line
+    );
+    append_to_file(&line);
+
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    if recent.len() == RECENT_LINES {
+        recent.pop_front();
+    }
+    recent.push_back(line);
+}
+
+fn append_to_file(line: &str) {
+    let path = file_path();
+    if std::fs::metadata(&path).is_ok_and(|m| m.len() > MAX_FILE_BYTES) {
+        let _ = std::fs::rename(&path, path.with_extension("log.old"));
+    }
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// The latest logged lines, oldest first. Empty if they're being written
+/// right now, so a panic while logging can't deadlock its crash report.
+pub fn recent() -> Vec<String> {
+    match RECENT.try_lock() {
+        Ok(recent) => recent.iter().cloned().collect(),
+        Err(TryLockError::Poisoned(e)) => e.into_inner().iter().cloned().collect(),
+        Err(TryLockError::WouldBlock) => Vec::new(),
+    }
+}
@@ -0,0 +1,186 @@
+//! Meeting mode: one long recording, transcribed in chunks as usual, whose
+//! text is appended to a transcript file every minute instead of pasted at
+//! the end, so hours of speech never pile up in memory
+
+use crate::{paths, AppState};
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// How often new text is written to the transcript
+const FLUSH_SECONDS: u64 = 60;
+
+/// The meeting being recorded
+pub struct Meeting {
+    pub path: PathBuf,
+    /// Latest chunk, held back from the file so the next chunk's overlap can
+    /// be merged into it
+    carry: Option<String>,
+    flusher: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl Drop for Meeting {
+    fn drop(&mut self) {
+        if let Some(flusher) = self.flusher.take() {
+            flusher.abort();
+        }
+    }
+}
+
+impl Meeting {
+    /// Append the text of `chunks` to the transcript. `last` writes out
+    /// everything, at the end of the meeting.
+    fn flush(&mut self, chunks: &[String], last: bool) -> Result<(), String> {
+        let (text, carry) = flush_text(self.carry.as_deref(), chunks, last);
+        self.carry = carry;
+        if text.is_empty() {
+            return Ok(());
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open {}: {}", self.path.display(), e))?;
+        writeln!(
+            file,
+            "[{}] {}\n",
+            chrono::Local::now().format("%H:%M"),
+            text
+        )
+        .map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+}
+
+/// Where meeting transcripts are saved
+pub fn transcript_dir() -> PathBuf {
+    dirs::document_dir()
+        .map(|dir| dir.join("Windows Whisper").join("Meetings"))
+        .unwrap_or_else(|| paths::data_dir().join("meetings"))
+}
+
+/// Start recording a meeting into a new transcript file
+pub(crate) fn start(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    if *state.is_recording.lock().unwrap() {
+        return Err("Stop the current dictation before starting a meeting".to_string());
+    }
+    let dir = transcript_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let now = chrono::Local::now();
+    let path = dir.join(format!("meeting-{}.txt", now.format("%Y-%m-%d-%H%M%S")));
+    std::fs::write(
+        &path,
+        format!("Meeting started {}\n\n", now.format("%Y-%m-%d %H:%M")),
+    )
+    .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+
+    let app_handle = app.clone();
+    let flusher = tauri::async_runtime::spawn(async move {
+        let period = std::time::Duration::from_secs(FLUSH_SECONDS);
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            let state = app_handle.state::<AppState>();
+            let chunks = std::mem::take(&mut *state.chunk_texts.lock().unwrap());
+            if let Some(meeting) = state.meeting.lock().unwrap().as_mut() {
+                if let Err(e) = meeting.flush(&chunks, false) {
+                    log!("Failed to save meeting transcript: {}", e);
+                }
+            }
+        }
+    });
+    *state.meeting.lock().unwrap() = Some(Meeting {
+        path,
+        carry: None,
+        flusher: Some(flusher),
+    });
+
+    if let Err(e) = crate::start_recording(app) {
+        *state.meeting.lock().unwrap() = None;
+        return Err(e);
+    }
+    crate::refresh_tray(app);
+    Ok(())
+}
+
+/// Write the rest of `meeting` once its recording has stopped and its last
+/// chunks are transcribed
+pub(crate) fn finish(app: &AppHandle, mut meeting: Meeting) {
+    if let Some(flusher) = meeting.flusher.take() {
+        flusher.abort();
+    }
+    let chunks = std::mem::take(&mut *app.state::<AppState>().chunk_texts.lock().unwrap());
+    match meeting.flush(&chunks, true) {
+        Ok(()) => crate::show_notice(
+            app,
+            &format!("Meeting transcript saved to {}", meeting.path.display()),
+        ),
+        Err(e) => crate::show_notice(app, &e),
+    }
+    crate::refresh_tray(app);
+}
+
+/// Text to append for `chunks`, and the chunk to hold back for next time.
+/// `carry` was held back last time; all but its trailing words, which the
+/// next chunk's overlap repeats, are already in the file.
+fn flush_text(carry: Option<&str>, chunks: &[String], last: bool) -> (String, Option<String>) {
+    let mut all: Vec<String> = carry
+        .map(str::to_string)
+        .into_iter()
+        .chain(chunks.iter().cloned())
+        .filter(|chunk| !chunk.trim().is_empty())
+        .collect();
+    let Some(newest) = all.last().cloned() else {
+        return (String::new(), None);
+    };
+    let next_carry = (!last).then(|| newest.clone());
+    if !last {
+        // Leave off the words the next chunk will repeat, as for any chunk
+        // but the final one
+        *all.last_mut().unwrap() =
+            crate::trim_trailing_words(newest.trim(), crate::CHUNK_TRIM_WORDS);
+    }
+
+    let text = crate::consolidate_chunk_texts(&all);
+    let text = match carry {
+        Some(carry) => {
+            let written = crate::trim_trailing_words(carry.trim(), crate::CHUNK_TRIM_WORDS);
+            text.strip_prefix(&written)
+                .map(|rest| rest.trim().to_string())
+                .unwrap_or(text)
+        }
+        None => text,
+    };
+    (text, next_carry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| text.to_string()).collect()
+    }
+
+    #[test]
+    fn flushes_join_up_without_repeating_the_overlap() {
+        let (first, carry) = flush_text(
+            None,
+            &chunks(&["we should ship the new build on friday after the review"]),
+            false,
+        );
+        assert_eq!(first, "we should ship the new build on friday");
+        let (second, carry) = flush_text(
+            carry.as_deref(),
+            &chunks(&["after the review meeting we can tag it"]),
+            true,
+        );
+        assert_eq!(second, "after the review meeting we can tag it");
+        assert_eq!(carry, None);
+    }
+
+    #[test]
+    fn nothing_new_writes_nothing() {
+        assert_eq!(flush_text(None, &[], false), (String::new(), None));
+    }
+}
@@ -0,0 +1,150 @@
+//! Mouse buttons as recording triggers, watched with a low-level mouse hook
+
+#[cfg(target_os = "windows")]
+use std::sync::atomic::{AtomicU8, Ordering};
+#[cfg(target_os = "windows")]
+use std::sync::mpsc::SyncSender;
+#[cfg(target_os = "windows")]
+use std::sync::OnceLock;
+
+/// A mouse button that can start and stop recordings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    /// Mouse3, the wheel button
+    Middle,
+    /// Mouse4 (XButton1), usually "back"
+    Back,
+    /// Mouse5 (XButton2), usually "forward"
+    Forward,
+}
+
+impl MouseButton {
+    #[cfg(target_os = "windows")]
+    fn bit(self) -> u8 {
+        match self {
+            MouseButton::Middle => 1,
+            MouseButton::Back => 2,
+            MouseButton::Forward => 4,
+        }
+    }
+}
+
+/// Parse a mouse trigger such as `Mouse4`. Mouse buttons can't be combined
+/// with modifier keys.
+pub fn parse_mouse_trigger(hotkey: &str) -> Option<MouseButton> {
+    match hotkey.trim().to_uppercase().as_str() {
+        "MOUSE3" | "MIDDLECLICK" => Some(MouseButton::Middle),
+        "MOUSE4" | "XBUTTON1" => Some(MouseButton::Back),
+        "MOUSE5" | "XBUTTON2" => Some(MouseButton::Forward),
+        _ => None,
+    }
+}
+
+/// Buttons bound to a trigger, as a bitmask; other clicks pass through untouched
+#[cfg(target_os = "windows")]
+static WATCHED: AtomicU8 = AtomicU8::new(0);
+/// Forwards button events from the hook thread, which must return quickly
+#[cfg(target_os = "windows")]
+static EVENTS: OnceLock<SyncSender<(MouseButton, bool)>> = OnceLock::new();
+
+/// Watch `buttons`, calling `on_event(button, pressed)` on each press and
+/// release. Clicks of watched buttons are swallowed so the focused app (e.g.
+/// a browser navigating back) doesn't see them. Calling again replaces the
+/// watched buttons but keeps the first callback.
+#[cfg(target_os = "windows")]
+pub fn install<F>(buttons: &[MouseButton], on_event: F)
+where
+    F: Fn(MouseButton, bool) + Send + 'static,
+{
+    use windows_sys::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        DispatchMessageW, GetMessageW, SetWindowsHookExW, TranslateMessage, MSG, WH_MOUSE_LL,
+    };
+
+    let mask = buttons.iter().fold(0, |mask, button| mask | button.bit());
+    WATCHED.store(mask, Ordering::Relaxed);
+
+    // Once installed, the hook stays; later calls only update the mask
+    if mask == 0 || EVENTS.get().is_some() {
+        return;
+    }
+    let (tx, rx) = std::sync::mpsc::sync_channel(16);
+    if EVENTS.set(tx).is_err() {
+        return;
+    }
+
+    std::thread::spawn(move || {
+        for (button, pressed) in rx {
+            on_event(button, pressed);
+        }
+    });
+
+    std::thread::spawn(|| unsafe {
+        let hook = SetWindowsHookExW(
+            WH_MOUSE_LL,
+            Some(hook_proc),
+            GetModuleHandleW(std::ptr::null()),
+            0,
+        );
+        if hook == 0 {
+            log!("Failed to install mouse hook");
+            return;
+        }
+
+        // Low-level hooks are called on this thread, which needs a message loop
+        let mut msg: MSG = std::mem::zeroed();
+        while GetMessageW(&mut msg, 0, 0, 0) > 0 {
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+        }
+    });
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn install<F>(buttons: &[MouseButton], _on_event: F)
+where
+    F: Fn(MouseButton, bool) + Send + 'static,
+{
+    if !buttons.is_empty() {
+        log!("Mouse button triggers are only supported on Windows");
+    }
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn hook_proc(code: i32, wparam: usize, lparam: isize) -> isize {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, HC_ACTION, LLMHF_INJECTED, MSLLHOOKSTRUCT, WM_MBUTTONDOWN, WM_MBUTTONUP,
+        WM_XBUTTONDOWN, WM_XBUTTONUP,
+    };
+
+    const XBUTTON1: u32 = 0x0001;
+
+    if code == HC_ACTION as i32 {
+        let info = &*(lparam as *const MSLLHOOKSTRUCT);
+        let message = wparam as u32;
+        let event = match message {
+            WM_MBUTTONDOWN | WM_MBUTTONUP => Some((MouseButton::Middle, message == WM_MBUTTONDOWN)),
+            WM_XBUTTONDOWN | WM_XBUTTONUP => {
+                let button = if info.mouseData >> 16 == XBUTTON1 {
+                    MouseButton::Back
+                } else {
+                    MouseButton::Forward
+                };
+                Some((button, message == WM_XBUTTONDOWN))
+            }
+            _ => None,
+        };
+
+        if let Some((button, pressed)) = event {
+            let watched = WATCHED.load(Ordering::Relaxed) & button.bit() != 0;
+            if watched && info.flags & LLMHF_INJECTED == 0 {
+                if let Some(tx) = EVENTS.get() {
+                    let _ = tx.try_send((button, pressed));
+                }
+                return 1;
+            }
+        }
+    }
+
+    CallNextHookEx(0, code, wparam, lparam)
+}
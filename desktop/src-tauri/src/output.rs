@@ -0,0 +1,198 @@
+//! Output targets other than the focused application
+
+use crate::history::TranscriptionLog;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// JSON body posted to the user's webhook after each dictation
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    pub text: String,
+    /// ISO 8601 timestamp
+    pub timestamp: String,
+    /// Executable name of the window the dictation was meant for
+    pub app: Option<String>,
+    pub duration_seconds: Option<f64>,
+    /// `text` in the translation language, when one is set
+    pub translation: Option<String>,
+}
+
+/// POST the payload as JSON to `url`.
+pub async fn send_webhook(url: &str, payload: &WebhookPayload) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(payload)
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Webhook returned {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Append a timestamped transcript line to `path`, creating the file and its
+/// parent directories if needed.
+pub fn append_to_file(path: &str, text: &str) -> Result<(), String> {
+    let path = Path::new(path);
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+    writeln!(file, "[{}] {}", timestamp, text)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Add a timestamped bullet to today's `YYYY-MM-DD.md` in `folder`, under
+/// `heading`. Missing notes are created from `template`, where `{{date}}` is
+/// replaced with today's date.
+pub fn append_to_daily_note(
+    folder: &str,
+    heading: &str,
+    template: &str,
+    text: &str,
+) -> Result<(), String> {
+    let now = chrono::Local::now();
+    let date = now.format("%Y-%m-%d").to_string();
+    let folder = Path::new(folder);
+    std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+    let path = folder.join(format!("{}.md", date));
+
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => template.replace("{{date}}", &date),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+
+    let entry = format!("- {} {}", now.format("%H:%M"), text);
+    let updated = insert_under_heading(&content, heading, &entry);
+    std::fs::write(&path, updated).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Name of the mirrored copy of `entry`, e.g. `2024-01-01_09-30-00_42.txt`.
+/// The id keeps dictations made in the same second apart.
+fn mirror_file_name(entry: &TranscriptionLog) -> String {
+    match chrono::DateTime::parse_from_rfc3339(&entry.timestamp) {
+        Ok(time) => format!("{}_{}.txt", time.format("%Y-%m-%d_%H-%M-%S"), entry.id),
+        Err(_) => format!("{}.txt", entry.id),
+    }
+}
+
+/// Write `entry` as its own text file in `folder`, e.g. a OneDrive or Dropbox
+/// folder that syncs it to other machines
+pub fn mirror_to_folder(folder: &str, entry: &TranscriptionLog) -> Result<PathBuf, String> {
+    let folder = Path::new(folder);
+    std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+    let path = folder.join(mirror_file_name(entry));
+    std::fs::write(&path, format!("{}\n", entry.processed_text))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Insert `entry` as the last line of the section that starts with `heading`,
+/// adding the heading at the end of the document if it doesn't exist yet.
+fn insert_under_heading(content: &str, heading: &str, entry: &str) -> String {
+    let heading = heading.trim();
+    let lines: Vec<&str> = content.lines().collect();
+
+    let start = if heading.is_empty() {
+        None
+    } else {
+        lines.iter().position(|line| line.trim() == heading)
+    };
+
+    let Some(start) = start else {
+        let mut output = content.trim_end().to_string();
+        if !output.is_empty() {
+            output.push_str("\n\n");
+        }
+        if !heading.is_empty() {
+            output.push_str(heading);
+            output.push('\n');
+        }
+        output.push_str(entry);
+        output.push('\n');
+        return output;
+    };
+
+    // The section ends at the next heading of the same or a higher level
+    let level = heading.chars().take_while(|c| *c == '#').count();
+    let end = lines
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|(_, line)| {
+            let hashes = line.chars().take_while(|c| *c == '#').count();
+            hashes > 0 && (level == 0 || hashes <= level) && line[hashes..].starts_with(' ')
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(lines.len());
+
+    // Keep blank lines that separate this section from the next one
+    let mut insert_at = end;
+    while insert_at > start + 1 && lines[insert_at - 1].trim().is_empty() {
+        insert_at -= 1;
+    }
+
+    let mut output: Vec<&str> = lines[..insert_at].to_vec();
+    output.push(entry);
+    output.extend_from_slice(&lines[insert_at..]);
+    output.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_go_at_the_end_of_their_heading() {
+        let note = "# 2024-01-01\n\n## Dictation\n- 09:00 first\n\n## Tasks\n- [ ] thing\n";
+        assert_eq!(
+            insert_under_heading(note, "## Dictation", "- 10:00 second"),
+            "# 2024-01-01\n\n## Dictation\n- 09:00 first\n- 10:00 second\n\n## Tasks\n- [ ] thing\n"
+        );
+    }
+
+    #[test]
+    fn missing_heading_is_added() {
+        assert_eq!(
+            insert_under_heading("# 2024-01-01\n", "## Dictation", "- 10:00 hello"),
+            "# 2024-01-01\n\n## Dictation\n- 10:00 hello\n"
+        );
+        assert_eq!(
+            insert_under_heading("", "## Dictation", "- 10:00 hello"),
+            "## Dictation\n- 10:00 hello\n"
+        );
+    }
+
+    #[test]
+    fn mirror_file_name_uses_local_timestamp_and_id() {
+        let entry: TranscriptionLog = serde_json::from_str(
+            r#"{"id": 42, "timestamp": "2024-01-01T09:30:05+02:00",
+                "raw_text": "hi", "processed_text": "Hi."}"#,
+        )
+        .unwrap();
+        assert_eq!(mirror_file_name(&entry), "2024-01-01_09-30-05_42.txt");
+    }
+
+    #[test]
+    fn subheadings_stay_in_their_section() {
+        let note = "## Dictation\n### Morning\n- a\n## Other\n";
+        assert_eq!(
+            insert_under_heading(note, "## Dictation", "- b"),
+            "## Dictation\n### Morning\n- a\n- b\n## Other\n"
+        );
+    }
+}
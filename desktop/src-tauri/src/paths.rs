@@ -0,0 +1,38 @@
+//! Where the app keeps its files: `%LOCALAPPDATA%\windows-whisper`, or a
+//! `data` folder next to the executable in portable mode
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// File next to the executable that turns on portable mode
+const PORTABLE_FLAG_FILE: &str = "portable.flag";
+/// Command-line switch that turns on portable mode
+const PORTABLE_ARG: &str = "--portable";
+/// Folder next to the executable holding portable data
+const PORTABLE_DATA_DIR: &str = "data";
+
+fn exe_dir() -> Option<PathBuf> {
+    Some(std::env::current_exe().ok()?.parent()?.to_path_buf())
+}
+
+/// Whether data lives next to the executable, for running from a USB stick
+/// or without install rights. Decided once per run.
+pub fn is_portable() -> bool {
+    static PORTABLE: OnceLock<bool> = OnceLock::new();
+    *PORTABLE.get_or_init(|| {
+        std::env::args().any(|arg| arg == PORTABLE_ARG)
+            || exe_dir().is_some_and(|dir| dir.join(PORTABLE_FLAG_FILE).exists())
+    })
+}
+
+/// Where settings, history, and recordings live
+pub fn data_dir() -> PathBuf {
+    if is_portable() {
+        if let Some(dir) = exe_dir() {
+            return dir.join(PORTABLE_DATA_DIR);
+        }
+    }
+    let mut path = dirs::data_local_dir().unwrap_or_default();
+    path.push("windows-whisper");
+    path
+}
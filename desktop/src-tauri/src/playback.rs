@@ -0,0 +1,65 @@
+//! Playing back a history entry's saved recording
+
+use rodio::{Decoder, OutputStream, Sink};
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::mpsc::{channel, Sender};
+use std::time::Duration;
+
+/// How often the player checks for a stop or the end of the audio
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A recording being played. Dropping it stops playback.
+pub struct Playback(Sender<()>);
+
+impl Drop for Playback {
+    fn drop(&mut self) {
+        let _ = self.0.send(());
+    }
+}
+
+/// Play the audio file at `path` on the default output device, calling
+/// `on_end` once it finishes or is stopped
+pub fn play(path: &Path, on_end: impl FnOnce() + Send + 'static) -> Result<Playback, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let source = Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let (stop_tx, stop_rx) = channel();
+    let (ready_tx, ready_rx) = channel();
+    // The output stream can't leave the thread that opened it
+    std::thread::spawn(move || {
+        let (_stream, handle) = match OutputStream::try_default() {
+            Ok(output) => output,
+            Err(e) => {
+                let _ = ready_tx.send(Err(format!("No audio output device: {}", e)));
+                return;
+            }
+        };
+        let sink = match Sink::try_new(&handle) {
+            Ok(sink) => sink,
+            Err(e) => {
+                let _ = ready_tx.send(Err(format!("Failed to start playback: {}", e)));
+                return;
+            }
+        };
+        sink.append(source);
+        let _ = ready_tx.send(Ok(()));
+
+        // A stop, or the Playback being dropped, ends it early
+        while !sink.empty() {
+            match stop_rx.recv_timeout(POLL_INTERVAL) {
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+        }
+        sink.stop();
+        on_end();
+    });
+
+    ready_rx
+        .recv()
+        .map_err(|_| "Playback stopped unexpectedly".to_string())??;
+    Ok(Playback(stop_tx))
+}
@@ -0,0 +1,126 @@
+//! Where the recording popup goes on screen
+
+use crate::settings::PopupPosition;
+
+/// Gap between the popup and the screen edge, caret, or mouse pointer, in
+/// logical pixels
+const MARGIN: i32 = 16;
+
+/// A screen area in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Physical size of a popup `size` logical pixels big on a screen with
+/// `scale` as its scale factor
+pub fn scaled_size(size: (u32, u32), scale: f64) -> (i32, i32) {
+    let scale = |length: u32| (length as f64 * scale).round() as i32;
+    (scale(size.0), scale(size.1))
+}
+
+/// Top-left corner for a popup of physical `size` on `monitor`, whose scale
+/// factor is `scale`. `anchor` is the caret or mouse point the follow modes
+/// place it under; without one they fall back to the bottom-center spot.
+pub fn origin(
+    position: PopupPosition,
+    monitor: Rect,
+    size: (i32, i32),
+    anchor: Option<(i32, i32)>,
+    scale: f64,
+) -> (i32, i32) {
+    let (width, height) = size;
+    let margin = (MARGIN as f64 * scale).round() as i32;
+    let left = monitor.x + margin;
+    let right = monitor.x + monitor.width - width - margin;
+    let top = monitor.y + margin;
+    let bottom = monitor.y + monitor.height - height - margin;
+    // Around 75% height, about a quarter up from the bottom
+    let bottom_center = (
+        monitor.x + (monitor.width - width) / 2,
+        monitor.y + monitor.height * 3 / 4 - height / 2,
+    );
+
+    let (x, y) = match (position, anchor) {
+        (PopupPosition::FollowCaret | PopupPosition::FollowMouse, Some((x, y))) => {
+            (x - width / 2, y + margin)
+        }
+        (PopupPosition::TopLeft, _) => (left, top),
+        (PopupPosition::TopRight, _) => (right, top),
+        (PopupPosition::BottomLeft, _) => (left, bottom),
+        (PopupPosition::BottomRight, _) => (right, bottom),
+        _ => bottom_center,
+    };
+    let max_x = monitor.x + monitor.width - width;
+    let max_y = monitor.y + monitor.height - height;
+    (
+        x.clamp(monitor.x, max_x.max(monitor.x)),
+        y.clamp(monitor.y, max_y.max(monitor.y)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MONITOR: Rect = Rect {
+        x: 1920,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+
+    #[test]
+    fn corners_keep_a_margin_from_the_edges() {
+        assert_eq!(
+            origin(PopupPosition::TopLeft, MONITOR, (150, 48), None, 1.0),
+            (1936, 16)
+        );
+        assert_eq!(
+            origin(PopupPosition::BottomRight, MONITOR, (150, 48), None, 1.0),
+            (3674, 1016)
+        );
+    }
+
+    #[test]
+    fn follow_modes_sit_below_the_anchor_and_stay_on_screen() {
+        assert_eq!(
+            origin(
+                PopupPosition::FollowCaret,
+                MONITOR,
+                (150, 48),
+                Some((2500, 300)),
+                1.0
+            ),
+            (2425, 316)
+        );
+        // Near the bottom-right corner the popup is pushed back on screen
+        assert_eq!(
+            origin(
+                PopupPosition::FollowMouse,
+                MONITOR,
+                (150, 48),
+                Some((3835, 1075)),
+                1.0
+            ),
+            (3690, 1032)
+        );
+        // No caret to follow
+        assert_eq!(
+            origin(PopupPosition::FollowCaret, MONITOR, (150, 48), None, 1.0),
+            (2805, 786)
+        );
+    }
+
+    #[test]
+    fn high_dpi_screens_scale_the_size_and_margin() {
+        assert_eq!(scaled_size((150, 48), 1.5), (225, 72));
+        assert_eq!(
+            origin(PopupPosition::TopLeft, MONITOR, (225, 72), None, 1.5),
+            (1944, 24)
+        );
+    }
+}
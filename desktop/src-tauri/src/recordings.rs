@@ -0,0 +1,129 @@
+//! Dictation audio saved next to history entries, kept within an age and
+//! size budget
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// A saved recording, named `<entry id>.wav`
+#[derive(Debug, Clone)]
+struct RecordingFile {
+    id: i64,
+    modified: SystemTime,
+    size: u64,
+}
+
+fn dir() -> PathBuf {
+    let mut path = crate::paths::data_dir();
+    path.push("recordings");
+    path
+}
+
+/// Save the WAV audio for history entry `id`, returning where it went
+pub fn save(id: i64, wav: &[u8]) -> Result<PathBuf, String> {
+    let dir = dir();
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = dir.join(format!("{}.wav", id));
+    std::fs::write(&path, wav).map_err(|e| format!("Failed to save recording: {}", e))?;
+    Ok(path)
+}
+
+fn list() -> Vec<RecordingFile> {
+    let Ok(entries) = std::fs::read_dir(dir()) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let id = entry.path().file_stem()?.to_str()?.parse().ok()?;
+            let metadata = entry.metadata().ok()?;
+            Some(RecordingFile {
+                id,
+                modified: metadata.modified().ok()?,
+                size: metadata.len(),
+            })
+        })
+        .collect()
+}
+
+/// Recordings to delete: anything older than `max_age`, then the oldest
+/// until the rest fit in `max_bytes`. Zero disables either limit.
+fn select_for_pruning(
+    mut files: Vec<RecordingFile>,
+    now: SystemTime,
+    max_age: Duration,
+    max_bytes: u64,
+) -> Vec<i64> {
+    files.sort_by_key(|file| file.modified);
+    let mut total: u64 = files.iter().map(|file| file.size).sum();
+    let mut pruned = Vec::new();
+
+    for file in files {
+        let age = now.duration_since(file.modified).unwrap_or_default();
+        let too_old = !max_age.is_zero() && age > max_age;
+        let over_budget = max_bytes > 0 && total > max_bytes;
+        if too_old || over_budget {
+            total -= file.size;
+            pruned.push(file.id);
+        }
+    }
+    pruned
+}
+
+/// Enforce the retention policy, returning the entry ids whose audio was
+/// deleted
+pub fn prune(max_days: u32, max_mb: u64) -> Vec<i64> {
+    let max_age = Duration::from_secs(max_days as u64 * 24 * 60 * 60);
+    let pruned = select_for_pruning(list(), SystemTime::now(), max_age, max_mb * 1024 * 1024);
+    for id in &pruned {
+        let _ = std::fs::remove_file(dir().join(format!("{}.wav", id)));
+    }
+    pruned
+}
+
+/// Delete every saved recording
+pub fn remove_all() {
+    let _ = std::fs::remove_dir_all(dir());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn file(id: i64, days_old: u32, size: u64, now: SystemTime) -> RecordingFile {
+        RecordingFile {
+            id,
+            modified: now - DAY * days_old,
+            size,
+        }
+    }
+
+    #[test]
+    fn prunes_recordings_past_the_age_limit() {
+        let now = SystemTime::now();
+        let files = vec![file(1, 40, 10, now), file(2, 5, 10, now)];
+        assert_eq!(select_for_pruning(files, now, DAY * 30, 0), vec![1]);
+    }
+
+    #[test]
+    fn prunes_oldest_first_until_under_the_size_budget() {
+        let now = SystemTime::now();
+        let files = vec![
+            file(3, 1, 40, now),
+            file(1, 3, 40, now),
+            file(2, 2, 40, now),
+        ];
+        assert_eq!(
+            select_for_pruning(files, now, Duration::ZERO, 50),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn zero_limits_keep_everything() {
+        let now = SystemTime::now();
+        let files = vec![file(1, 400, 1 << 30, now)];
+        assert!(select_for_pruning(files, now, Duration::ZERO, 0).is_empty());
+    }
+}
@@ -0,0 +1,102 @@
+//! Masking secrets in anything that leaves the app: exported settings,
+//! diagnostics, and log lines
+
+use crate::settings::Settings;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Stands in for a removed secret
+pub const MASK: &str = "[redacted]";
+
+/// Settings fields holding secrets, at any depth
+const SECRET_FIELDS: &[&str] = &["api_key", "token"];
+
+/// Settings as JSON with every secret field masked
+pub fn settings_json(settings: &Settings) -> Value {
+    let mut value = serde_json::to_value(settings).unwrap_or_default();
+    mask_secret_fields(&mut value);
+    value
+}
+
+fn mask_secret_fields(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if !SECRET_FIELDS.contains(&name.as_str()) {
+                    mask_secret_fields(field);
+                } else if field.as_str().is_some_and(|secret| !secret.is_empty()) {
+                    *field = Value::from(MASK);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(mask_secret_fields),
+        _ => {}
+    }
+}
+
+/// Every API key and token in `settings`, for `text` and the log
+pub fn secrets(settings: &Settings) -> Vec<String> {
+    std::iter::once(&settings.api_key)
+        .chain(std::iter::once(&settings.automation_api.token))
+        .chain(settings.hotkey_bindings.iter().map(|b| &b.api_key))
+        .chain(settings.profiles.iter().map(|p| &p.api_key))
+        .filter(|key| !key.is_empty())
+        .cloned()
+        .collect()
+}
+
+/// `text` with auth headers, key-like URL parameters, and each of `secrets`
+/// masked
+pub fn text(text: &str, secrets: &[String]) -> String {
+    static CREDENTIALS: OnceLock<Regex> = OnceLock::new();
+    let credentials = CREDENTIALS.get_or_init(|| {
+        Regex::new(
+            r#"(?i)(authorization:\s*(?:bearer\s+|basic\s+)?|x-api-key:\s*|[?&](?:api_?key|key|token)=)[^\s&"']+"#,
+        )
+        .unwrap()
+    });
+    let mut text = credentials
+        .replace_all(text, format!("${{1}}{}", MASK))
+        .into_owned();
+    for secret in secrets.iter().filter(|secret| !secret.is_empty()) {
+        text = text.replace(secret.as_str(), MASK);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_and_url_parameters_are_masked() {
+        assert_eq!(
+            text("Authorization: Bearer abc.def rest", &[]),
+            "Authorization: Bearer [redacted] rest"
+        );
+        assert_eq!(text("x-api-key: hunter2", &[]), "x-api-key: [redacted]");
+        assert_eq!(
+            text("POST https://hook.example/in?token=s3cret&lang=en", &[]),
+            "POST https://hook.example/in?token=[redacted]&lang=en"
+        );
+        assert_eq!(
+            text("server said hunter22 is wrong", &["hunter22".to_string()]),
+            "server said [redacted] is wrong"
+        );
+    }
+
+    #[test]
+    fn secret_fields_are_masked_at_any_depth() {
+        let mut value = serde_json::json!({
+            "api_key": "main",
+            "api_url": "https://example.dev",
+            "profiles": [{ "name": "Work", "api_key": "work" }, { "api_key": "" }],
+        });
+        mask_secret_fields(&mut value);
+        assert_eq!(value["api_key"], MASK);
+        assert_eq!(value["api_url"], "https://example.dev");
+        assert_eq!(value["profiles"][0]["api_key"], MASK);
+        assert_eq!(value["profiles"][1]["api_key"], "");
+    }
+}
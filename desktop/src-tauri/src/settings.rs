@@ -1,6 +1,8 @@
 //! Settings persistence
 
+use crate::credentials;
 use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
 
 /// A custom find/replace rule for post-processing
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -9,11 +11,435 @@ pub struct ReplacementRule {
     pub replace: String,
 }
 
+/// How the transcript is delivered to the focused application
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    /// Write to the clipboard and send the paste chord
+    #[default]
+    Paste,
+    /// Simulate typing each character
+    Type,
+    /// Inject Unicode key events directly with `SendInput`
+    SendInput,
+    /// Set the focused control's value through UI Automation, for plain
+    /// single-line edit boxes; pastes into anything else
+    UiAutomation,
+}
+
+/// An output mode override for a specific application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppOutputMode {
+    /// Executable name, e.g. `mstsc.exe`
+    pub process: String,
+    pub mode: OutputMode,
+}
+
+/// Key combination used to trigger a paste
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteChord {
+    #[default]
+    CtrlV,
+    CtrlShiftV,
+    ShiftInsert,
+}
+
+/// A paste chord override for a specific application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppPasteChord {
+    /// Executable name, e.g. `WindowsTerminal.exe`
+    pub process: String,
+    pub chord: PasteChord,
+}
+
+/// What to do when the window focused at recording start has lost focus
+/// by the time the transcript is ready
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusChangePolicy {
+    /// Deliver to whatever window is focused now
+    PasteAnyway,
+    /// Re-focus the original window, then deliver
+    #[default]
+    Refocus,
+    /// Skip delivery and leave the transcript on the clipboard
+    Abort,
+}
+
+/// How the recording hotkey starts and stops a dictation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerMode {
+    /// Press once to start, again to stop
+    #[default]
+    Toggle,
+    /// Record only while the hotkey is held down
+    Hold,
+    /// Press to start and again to stop, stopping on its own after
+    /// `max_recording_seconds`
+    ToggleWithLimit,
+}
+
+/// How an in-progress recording is cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelTrigger {
+    /// Press `cancel_hotkey`, which is only grabbed while recording
+    #[default]
+    Key,
+    /// Press the recording hotkey twice quickly
+    DoublePress,
+    /// Recordings can't be cancelled
+    None,
+}
+
+/// How the undo-last-dictation hotkey removes text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UndoMethod {
+    /// One Backspace per inserted character
+    #[default]
+    Backspace,
+    /// The target app's own undo (Ctrl+Z)
+    CtrlZ,
+}
+
+/// A destination for finished transcripts. Several can be enabled at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputTarget {
+    /// Insert into the focused app
+    #[default]
+    Paste,
+    /// Place the transcript on the clipboard
+    ClipboardOnly,
+    /// Append to `append_file_path`
+    AppendToFile,
+    /// Add under the heading of today's note in `daily_note_folder`
+    DailyNote,
+    /// POST to `webhook_url`
+    Webhook,
+    /// Record the raw and processed text in history
+    History,
+}
+
+/// How a hotkey profile post-processes its transcripts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PostProcessingMode {
+    /// Apply the post-processing options from settings
+    #[default]
+    Standard,
+    /// Deliver the transcript exactly as the server returned it
+    Raw,
+}
+
+/// A hotkey that pastes an older transcription, `offset` entries back from
+/// the latest (1 is the one before it)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryPasteHotkey {
+    pub hotkey: String,
+    pub offset: usize,
+}
+
+/// An additional hotkey bound to a profile that overrides the backend,
+/// post-processing, and output for recordings it starts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub hotkey: String,
+    /// Label shown in settings, e.g. "German clipboard"
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub output: OutputTarget,
+    #[serde(default)]
+    pub postprocessing: PostProcessingMode,
+    /// Transcription server for this profile (empty uses `api_url`)
+    #[serde(default)]
+    pub api_url: String,
+    /// API key for `api_url` (empty uses the main key)
+    #[serde(default)]
+    pub api_key: String,
+    /// Dictation language for this profile (empty uses `language`)
+    #[serde(default)]
+    pub language: String,
+}
+
+/// A named set of overrides that applies to every recording while it's the
+/// active profile, switched from the tray
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SettingsProfile {
+    pub name: String,
+    #[serde(default)]
+    pub postprocessing: PostProcessingMode,
+    /// Transcription server (empty uses `api_url`)
+    #[serde(default)]
+    pub api_url: String,
+    /// API key for `api_url` (empty uses the main key)
+    #[serde(default)]
+    pub api_key: String,
+    /// Dictation language (empty uses `language`)
+    #[serde(default)]
+    pub language: String,
+}
+
+/// Local HTTP control API for scripts and devices such as AutoHotkey or a
+/// Stream Deck. Listens on 127.0.0.1 only.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct AutomationApi {
+    pub enabled: bool,
+    pub port: u16,
+    /// Sent by clients as `Authorization: Bearer <token>`
+    pub token: String,
+}
+
+impl Default for AutomationApi {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47_113,
+            token: String::new(),
+        }
+    }
+}
+
+/// A weekly window during which recording hotkeys are ignored
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct QuietHours {
+    pub enabled: bool,
+    /// Days the quiet period starts on, 0 = Monday through 6 = Sunday
+    pub days: Vec<u8>,
+    /// Local start and end times as `HH:MM`; an end before the start runs
+    /// past midnight into the next day
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            days: (0..7).collect(),
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+        }
+    }
+}
+
+/// Chords that answer a review from any app while it is shown in the popup
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ReviewHotkeys {
+    pub paste: String,
+    pub copy_only: String,
+    pub discard: String,
+}
+
+impl Default for ReviewHotkeys {
+    fn default() -> Self {
+        // Ctrl+Alt is AltGr on many layouts, so stay clear of it
+        Self {
+            paste: "Ctrl+Shift+Enter".to_string(),
+            copy_only: "Ctrl+Shift+Insert".to_string(),
+            discard: "Ctrl+Shift+Backspace".to_string(),
+        }
+    }
+}
+
+impl ReviewHotkeys {
+    /// Each configured chord with the field it came from
+    pub fn configured(&self) -> Vec<(String, String)> {
+        [
+            ("review_hotkeys.paste", &self.paste),
+            ("review_hotkeys.copy_only", &self.copy_only),
+            ("review_hotkeys.discard", &self.discard),
+        ]
+        .into_iter()
+        .filter(|(_, hotkey)| !hotkey.trim().is_empty())
+        .map(|(field, hotkey)| (field.to_string(), hotkey.clone()))
+        .collect()
+    }
+}
+
+impl QuietHours {
+    /// Whether quiet hours cover `day` (0 = Monday) at `minute` past midnight
+    pub fn is_active_at(&self, day: u8, minute: u32) -> bool {
+        let (Some(start), Some(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        if !self.enabled || start == end {
+            return false;
+        }
+
+        let previous_day = (day + 6) % 7;
+        if start < end {
+            self.days.contains(&day) && minute >= start && minute < end
+        } else {
+            (self.days.contains(&day) && minute >= start)
+                || (self.days.contains(&previous_day) && minute < end)
+        }
+    }
+
+    /// Whether quiet hours are in effect right now
+    pub fn is_active_now(&self) -> bool {
+        use chrono::{Datelike, Timelike};
+
+        let now = chrono::Local::now();
+        self.is_active_at(
+            now.weekday().num_days_from_monday() as u8,
+            now.hour() * 60 + now.minute(),
+        )
+    }
+}
+
+/// Minutes past midnight for an `HH:MM` time
+fn parse_time(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// A step of the first-run walkthrough
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    /// Enter and test the transcription server
+    Backend,
+    /// Check that the microphone picks up speech
+    Microphone,
+    /// Choose the recording hotkey
+    Hotkey,
+}
+
+impl OnboardingStep {
+    pub const ALL: [Self; 3] = [Self::Backend, Self::Microphone, Self::Hotkey];
+}
+
+/// Progress through the first-run walkthrough, for the onboarding window
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct OnboardingState {
+    pub first_run: bool,
+    pub completed: Vec<OnboardingStep>,
+    /// The first step not done yet
+    pub next: Option<OnboardingStep>,
+}
+
+/// Subtitle file written for each transcribed audio file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+/// Where the recording popup appears
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PopupPosition {
+    /// Under the text caret, where the system reports one
+    FollowCaret,
+    /// Under the mouse pointer
+    FollowMouse,
+    /// Centered, about a quarter up from the bottom of the screen
+    #[default]
+    BottomCenter,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// How the recording popup looks and behaves
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PopupAppearance {
+    pub position: PopupPosition,
+    /// Size in logical pixels, scaled for the screen the popup appears on
+    pub width: u32,
+    pub height: u32,
+    /// 0.0 (invisible) to 1.0 (opaque)
+    pub opacity: f64,
+    /// Let clicks pass through to the window underneath
+    pub click_through: bool,
+    /// Keep the popup and caption bar out of screen shares and recordings
+    pub hide_from_capture: bool,
+}
+
+impl Default for PopupAppearance {
+    fn default() -> Self {
+        Self {
+            position: PopupPosition::default(),
+            width: 150,
+            height: 48,
+            opacity: 1.0,
+            click_through: false,
+            hide_from_capture: false,
+        }
+    }
+}
+
+/// How much transcription history is kept. Pinned entries are exempt from
+/// the count and age limits.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HistoryRetention {
+    /// Off keeps no history at all and deletes what was saved
+    pub enabled: bool,
+    /// Unpinned entries kept, newest first (0 is unlimited)
+    pub max_entries: u32,
+    /// Days an unpinned entry is kept (0 keeps it forever)
+    pub max_age_days: u32,
+    /// Days a deleted entry stays in the trash (0 keeps it until restored)
+    pub trash_days: u32,
+}
+
+impl Default for HistoryRetention {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 1000,
+            max_age_days: 0,
+            trash_days: 30,
+        }
+    }
+}
+
+/// A button on a generic HID device, such as a USB foot pedal, that starts
+/// and stops recording like the main hotkey
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HidTrigger {
+    /// Top-level collection of the device, e.g. 0x0C/0x01 for consumer controls
+    pub usage_page: u16,
+    pub usage: u16,
+    /// Button usage (on the Button page) that acts as the trigger
+    pub button: u16,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub hotkey: String,
     pub api_url: String,
     pub api_key: String,
+    /// Language code sent with each transcription, e.g. "de" (empty lets the
+    /// model detect it)
+    #[serde(default)]
+    pub language: String,
+    /// Names and domain terms the server should favor when it hears them,
+    /// sent with each transcription
+    #[serde(default)]
+    pub boosted_terms: Vec<String>,
 
     // Post-processing settings
     #[serde(default)]
@@ -28,6 +454,173 @@ pub struct Settings {
     pub remove_punctuation: bool,
     #[serde(default = "default_true")]
     pub dedupe_repeated_phrases: bool,
+
+    // Output settings
+    #[serde(default)]
+    pub restore_clipboard: bool,
+    #[serde(default)]
+    pub output_mode: OutputMode,
+    #[serde(default)]
+    pub app_output_modes: Vec<AppOutputMode>,
+    #[serde(default = "default_app_paste_chords")]
+    pub app_paste_chords: Vec<AppPasteChord>,
+    /// Minimum wait between writing the clipboard and pasting
+    #[serde(default = "default_paste_min_delay_ms")]
+    pub paste_min_delay_ms: u64,
+    /// Maximum wait for the clipboard to become readable before pasting anyway
+    #[serde(default = "default_paste_max_delay_ms")]
+    pub paste_max_delay_ms: u64,
+    #[serde(default)]
+    pub focus_change_policy: FocusChangePolicy,
+    /// Skip auto-paste when a password field has focus
+    #[serde(default = "default_true")]
+    pub block_password_fields: bool,
+    /// Type the text instead when the clipboard write or paste chord fails
+    #[serde(default = "default_true")]
+    pub paste_fallback_to_typing: bool,
+    /// Also type the text when the caret doesn't move after pasting. Apps
+    /// that are slow or don't report their caret would get it twice.
+    #[serde(default)]
+    pub verify_paste: bool,
+    /// File used by the `append_to_file` output
+    #[serde(default)]
+    pub append_file_path: String,
+    /// Vault folder holding `YYYY-MM-DD.md` notes for the `daily_note` output
+    #[serde(default)]
+    pub daily_note_folder: String,
+    #[serde(default = "default_daily_note_heading")]
+    pub daily_note_heading: String,
+    /// Contents of a newly created daily note; `{{date}}` is substituted
+    #[serde(default = "default_daily_note_template")]
+    pub daily_note_template: String,
+    /// Show the transcript with Paste / Copy only / Discard before inserting
+    #[serde(default)]
+    pub review_before_paste: bool,
+    /// Review in an editable window instead of the popup
+    #[serde(default)]
+    pub review_editable: bool,
+    /// Empty fields leave that action to the popup's buttons
+    #[serde(default)]
+    pub review_hotkeys: ReviewHotkeys,
+    /// Hotkey that erases the last dictation (empty disables)
+    #[serde(default)]
+    pub undo_hotkey: String,
+    #[serde(default)]
+    pub undo_method: UndoMethod,
+    /// Hotkey that re-pastes the most recent transcription (empty disables)
+    #[serde(default)]
+    pub repaste_hotkey: String,
+    /// Hotkeys that paste earlier transcriptions
+    #[serde(default)]
+    pub history_paste_hotkeys: Vec<HistoryPasteHotkey>,
+    /// Hotkey that pauses and resumes all recording hotkeys (empty disables)
+    #[serde(default)]
+    pub pause_hotkey: String,
+    /// URL that receives a JSON POST after each dictation (empty disables)
+    #[serde(default)]
+    pub webhook_url: String,
+    /// Extra hotkeys, each with its own output target
+    #[serde(default)]
+    pub hotkey_bindings: Vec<HotkeyBinding>,
+    /// Named override sets to switch between from the tray
+    #[serde(default)]
+    pub profiles: Vec<SettingsProfile>,
+    /// Name of the profile in use (empty for none)
+    #[serde(default)]
+    pub active_profile: String,
+    /// Where finished transcripts are sent
+    #[serde(default = "default_outputs")]
+    pub outputs: Vec<OutputTarget>,
+    #[serde(default)]
+    pub trigger_mode: TriggerMode,
+    /// Tell taps, double taps (re-paste), and long presses (history) of the
+    /// main hotkey apart instead of toggling on every press
+    #[serde(default)]
+    pub hotkey_gestures: bool,
+    /// Catch recording hotkeys with a keyboard hook and keep them from
+    /// reaching the focused app, instead of registering them as hotkeys
+    #[serde(default)]
+    pub swallow_trigger_keys: bool,
+    #[serde(default)]
+    pub hid_trigger: Option<HidTrigger>,
+    /// Ignore recording triggers while a full-screen app (e.g. a game) is
+    /// in the foreground
+    #[serde(default)]
+    pub ignore_in_fullscreen: bool,
+    #[serde(default)]
+    pub quiet_hours: QuietHours,
+    /// Paused from the tray or the pause hotkey; stays paused after a restart
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default)]
+    pub automation_api: AutomationApi,
+    /// Look for new releases in the background
+    #[serde(default = "default_true")]
+    pub check_for_updates: bool,
+    #[serde(default)]
+    pub popup: PopupAppearance,
+    /// Show each chunk's transcript in a caption bar while recording
+    #[serde(default)]
+    pub live_captions: bool,
+    /// Keep the microphone open between recordings so capture starts the
+    /// moment the hotkey is pressed. The system shows the mic as in use the
+    /// whole time, so it's off unless asked for.
+    #[serde(default)]
+    pub warm_microphone: bool,
+    /// Name of the microphone to record from, or empty for the system
+    /// default. The default is used while the named one isn't connected.
+    #[serde(default)]
+    pub preferred_input_device: String,
+    /// Write timed subtitles for transcribed files instead of plain text.
+    /// Needs a server that returns timestamps.
+    #[serde(default)]
+    pub subtitle_format: Option<SubtitleFormat>,
+    /// Also translate each dictation into this language (a code like "en"),
+    /// for bilingual correspondence. Empty turns it off.
+    #[serde(default)]
+    pub translation_language: String,
+    /// Paste the translation and leave the original on the clipboard, rather
+    /// than the other way round
+    #[serde(default)]
+    pub paste_translation: bool,
+    /// Set on a fresh install until the first-run walkthrough is done.
+    /// Settings files from before the walkthrough existed leave it unset.
+    #[serde(default)]
+    pub first_run: bool,
+    #[serde(default)]
+    pub onboarding_steps: Vec<OnboardingStep>,
+    #[serde(default)]
+    pub cancel_trigger: CancelTrigger,
+    #[serde(default = "default_cancel_hotkey")]
+    pub cancel_hotkey: String,
+    /// Stop presses sooner than this after starting keep recording
+    #[serde(default = "default_min_recording_ms")]
+    pub min_recording_ms: u64,
+    /// Recording length limit for `toggle_with_limit`
+    #[serde(default = "default_max_recording_seconds")]
+    pub max_recording_seconds: u64,
+    /// Stop a recording once speech is followed by this long a silence; 0
+    /// never does. Not used in hold mode or meetings.
+    #[serde(default)]
+    pub silence_stop_seconds: u64,
+    /// Typing speed for the `type` output mode; 0 types as fast as possible
+    #[serde(default)]
+    pub typing_chars_per_second: u32,
+    #[serde(default)]
+    pub history_retention: HistoryRetention,
+    /// Folder that gets a text file per new history entry, for syncing
+    /// dictations to other machines (empty disables)
+    #[serde(default)]
+    pub history_sync_folder: String,
+    /// Save each dictation's audio next to its history entry
+    #[serde(default)]
+    pub keep_audio: bool,
+    /// Delete saved audio older than this many days (0 keeps it forever)
+    #[serde(default = "default_audio_retention_days")]
+    pub audio_retention_days: u32,
+    /// Delete the oldest saved audio beyond this many MB (0 is unlimited)
+    #[serde(default = "default_audio_max_mb")]
+    pub audio_max_mb: u64,
 }
 
 fn default_true() -> bool {
@@ -49,45 +642,901 @@ fn default_filler_words() -> Vec<String> {
     ]
 }
 
+fn default_outputs() -> Vec<OutputTarget> {
+    vec![OutputTarget::Paste, OutputTarget::History]
+}
+
+fn default_cancel_hotkey() -> String {
+    "Escape".to_string()
+}
+
+fn default_min_recording_ms() -> u64 {
+    500
+}
+
+fn default_max_recording_seconds() -> u64 {
+    120
+}
+
+fn default_audio_retention_days() -> u32 {
+    30
+}
+
+fn default_audio_max_mb() -> u64 {
+    500
+}
+
+fn default_paste_min_delay_ms() -> u64 {
+    30
+}
+
+fn default_paste_max_delay_ms() -> u64 {
+    1000
+}
+
+fn default_daily_note_heading() -> String {
+    "## Dictation".to_string()
+}
+
+fn default_daily_note_template() -> String {
+    "# {{date}}\n".to_string()
+}
+
+fn default_app_paste_chords() -> Vec<AppPasteChord> {
+    vec![
+        AppPasteChord {
+            process: "WindowsTerminal.exe".to_string(),
+            chord: PasteChord::CtrlShiftV,
+        },
+        AppPasteChord {
+            process: "ConEmu64.exe".to_string(),
+            chord: PasteChord::ShiftInsert,
+        },
+        AppPasteChord {
+            process: "ConEmu.exe".to_string(),
+            chord: PasteChord::ShiftInsert,
+        },
+        AppPasteChord {
+            process: "mintty.exe".to_string(),
+            chord: PasteChord::ShiftInsert,
+        },
+    ]
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self {
             hotkey: "ScrollLock".to_string(),
             api_url: String::new(),
             api_key: String::new(),
+            language: String::new(),
+            boosted_terms: Vec::new(),
             auto_capitalize: true,
             remove_filler_words: true,
             filler_words: default_filler_words(),
             custom_replacements: Vec::new(),
             remove_punctuation: false,
             dedupe_repeated_phrases: true,
+            restore_clipboard: false,
+            output_mode: OutputMode::Paste,
+            app_output_modes: Vec::new(),
+            app_paste_chords: default_app_paste_chords(),
+            paste_min_delay_ms: default_paste_min_delay_ms(),
+            paste_max_delay_ms: default_paste_max_delay_ms(),
+            focus_change_policy: FocusChangePolicy::Refocus,
+            block_password_fields: true,
+            paste_fallback_to_typing: true,
+            verify_paste: false,
+            append_file_path: String::new(),
+            daily_note_folder: String::new(),
+            daily_note_heading: default_daily_note_heading(),
+            daily_note_template: default_daily_note_template(),
+            review_before_paste: false,
+            review_editable: false,
+            review_hotkeys: ReviewHotkeys::default(),
+            undo_hotkey: String::new(),
+            undo_method: UndoMethod::Backspace,
+            repaste_hotkey: String::new(),
+            history_paste_hotkeys: Vec::new(),
+            pause_hotkey: String::new(),
+            webhook_url: String::new(),
+            hotkey_bindings: Vec::new(),
+            profiles: Vec::new(),
+            active_profile: String::new(),
+            outputs: default_outputs(),
+            trigger_mode: TriggerMode::Toggle,
+            hotkey_gestures: false,
+            swallow_trigger_keys: false,
+            hid_trigger: None,
+            ignore_in_fullscreen: false,
+            quiet_hours: QuietHours::default(),
+            paused: false,
+            automation_api: AutomationApi::default(),
+            check_for_updates: true,
+            popup: PopupAppearance::default(),
+            live_captions: false,
+            warm_microphone: false,
+            preferred_input_device: String::new(),
+            subtitle_format: None,
+            translation_language: String::new(),
+            paste_translation: false,
+            first_run: true,
+            onboarding_steps: Vec::new(),
+            cancel_trigger: CancelTrigger::Key,
+            cancel_hotkey: default_cancel_hotkey(),
+            min_recording_ms: default_min_recording_ms(),
+            max_recording_seconds: default_max_recording_seconds(),
+            silence_stop_seconds: 0,
+            typing_chars_per_second: 0,
+            history_retention: HistoryRetention::default(),
+            history_sync_folder: String::new(),
+            keep_audio: false,
+            audio_retention_days: default_audio_retention_days(),
+            audio_max_mb: default_audio_max_mb(),
+        }
+    }
+}
+
+/// Why a settings update was rejected, returned to the settings UI
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SettingsError {
+    /// The hotkey string isn't a recognized key combination
+    UnparseableHotkey { field: String, hotkey: String },
+    /// Another application has already registered the hotkey
+    HotkeyInUse { field: String, hotkey: String },
+    /// Two settings are bound to the same hotkey
+    DuplicateHotkey {
+        field: String,
+        other_field: String,
+        hotkey: String,
+    },
+    /// Fields with values that can't work, each with what's wrong
+    Invalid { errors: Vec<FieldError> },
+    /// Writing settings to disk failed
+    Io { message: String },
+}
+
+impl SettingsError {
+    pub fn message(&self) -> String {
+        match self {
+            Self::UnparseableHotkey { field, hotkey } => {
+                format!("{}: \"{}\" isn't a key combination", field, hotkey)
+            }
+            Self::HotkeyInUse { field, hotkey } => {
+                format!("{}: {} is used by another app", field, hotkey)
+            }
+            Self::DuplicateHotkey {
+                field,
+                other_field,
+                hotkey,
+            } => format!("{}: {} is already used by {}", field, hotkey, other_field),
+            Self::Invalid { errors } => errors
+                .iter()
+                .map(|error| format!("{}: {}", error.field, error.message))
+                .collect::<Vec<_>>()
+                .join("; "),
+            Self::Io { message } => message.clone(),
         }
     }
 }
 
+/// One rejected field, named as in the settings JSON (e.g. `api_url` or
+/// `hotkey_bindings[1].api_url`)
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Smallest popup width or height, in pixels
+const MIN_POPUP_SIZE: u32 = 20;
+
+/// Shortest token the automation API accepts
+const MIN_AUTOMATION_TOKEN_CHARS: usize = 16;
+
+/// Longest recording `max_recording_seconds` may allow
+const MAX_RECORDING_SECONDS_LIMIT: u64 = 60 * 60;
+/// Longest pause `silence_stop_seconds` may wait for
+const SILENCE_STOP_SECONDS_LIMIT: u64 = 60;
+
+/// Tidy a pasted server URL: add a missing `https://`, and drop trailing
+/// slashes and the `/transcribe` endpoint that requests append themselves
+pub fn normalize_api_url(url: &str) -> String {
+    let url = url.trim();
+    if url.is_empty() {
+        return String::new();
+    }
+    let mut url = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    };
+    while url.ends_with('/') {
+        url.pop();
+    }
+    if url.to_ascii_lowercase().ends_with("/transcribe") {
+        url.truncate(url.len() - "/transcribe".len());
+        while url.ends_with('/') {
+            url.pop();
+        }
+    }
+    url
+}
+
+/// Whether `url` is an absolute http(s) URL
+pub fn is_http_url(url: &str) -> bool {
+    tauri::Url::parse(url.trim())
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+}
+
+/// How `Settings::load` got past an unreadable settings file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingsRecovery {
+    /// The last good copy from `settings.json.bak` was used
+    RestoredFromBackup,
+    /// The backup was unusable too, so defaults were used
+    ResetToDefaults,
+}
+
+impl SettingsRecovery {
+    pub fn message(self) -> &'static str {
+        match self {
+            Self::RestoredFromBackup => "Settings were corrupted and restored from backup",
+            Self::ResetToDefaults => "Settings were corrupted and reset to defaults",
+        }
+    }
+}
+
+/// settings.json as the app last read or wrote it, so that the file watcher
+/// can tell hand edits from the app's own saves
+static LAST_CONTENT: Mutex<String> = Mutex::new(String::new());
+
+fn settings_path() -> std::path::PathBuf {
+    crate::paths::data_dir().join("settings.json")
+}
+
+fn parse_settings(content: &str) -> Option<Settings> {
+    serde_json::from_str(content).ok()
+}
+
+/// Read settings from `path`, falling back to its `.bak` copy if the file is
+/// unreadable. A missing file is a first run rather than a corruption. The
+/// broken file is moved aside to `.corrupt` so the next save doesn't back
+/// it up over the good copy.
+fn load_from(path: &std::path::Path) -> (Settings, Option<SettingsRecovery>) {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (Settings::default(), None),
+        Err(_) => String::new(),
+    };
+    if let Some(settings) = parse_settings(&content) {
+        return (settings, None);
+    }
+
+    log!("{} is corrupted", path.display());
+    let _ = std::fs::rename(path, path.with_extension("json.corrupt"));
+    match std::fs::read_to_string(path.with_extension("json.bak"))
+        .ok()
+        .and_then(|backup| parse_settings(&backup))
+    {
+        Some(settings) => (settings, Some(SettingsRecovery::RestoredFromBackup)),
+        None => (Settings::default(), Some(SettingsRecovery::ResetToDefaults)),
+    }
+}
+
+/// Replace `path` with `content` without ever leaving it half written: the
+/// new content goes to a temporary file that is renamed over the old one,
+/// which is first copied to `.bak`.
+fn write_atomically(path: &std::path::Path, content: &str) -> std::io::Result<()> {
+    use std::io::Write;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_extension("json.tmp");
+    {
+        let mut file = std::fs::File::create(&temp)?;
+        file.write_all(content.as_bytes())?;
+        file.sync_all()?;
+    }
+    if path.exists() {
+        std::fs::copy(path, path.with_extension("json.bak"))?;
+    }
+    std::fs::rename(&temp, path)
+}
+
 impl Settings {
-    pub fn load() -> Self {
-        let mut path = dirs::data_local_dir().unwrap_or_default();
-        path.push("windows-whisper");
-        std::fs::create_dir_all(&path).ok();
-        path.push("settings.json");
-
-        if let Ok(content) = std::fs::read_to_string(&path) {
-            if let Ok(settings) = serde_json::from_str(&content) {
-                return settings;
+    /// Load settings from disk, reporting whether a corrupted file had to be
+    /// recovered
+    pub fn load() -> (Self, Option<SettingsRecovery>) {
+        let path = settings_path();
+        let (mut settings, recovery) = load_from(&path);
+
+        let moved = settings.load_secrets();
+        // Before anything that could log, so no line shows a key
+        crate::logs::set_secrets(crate::redact::secrets(&settings));
+        if moved {
+            settings.save_without_plaintext_keys();
+        }
+        if recovery.is_some() {
+            if let Err(e) = settings.save() {
+                log!("Failed to save recovered settings: {}", e);
             }
         }
-        Self::default()
+        *LAST_CONTENT.lock().unwrap() = std::fs::read_to_string(&path).unwrap_or_default();
+        (settings, recovery)
+    }
+
+    /// Settings from a settings.json edited outside the app, or `None` if
+    /// it's unchanged since the app last read or wrote it. A file that
+    /// doesn't parse (an editor may still be writing it) is also skipped.
+    pub fn reload_if_changed() -> Option<Self> {
+        let content = std::fs::read_to_string(settings_path()).ok()?;
+        let mut last = LAST_CONTENT.lock().unwrap();
+        if *last == content {
+            return None;
+        }
+        let mut settings = parse_settings(&content)?;
+        *last = content;
+        drop(last);
+
+        if settings.load_secrets() {
+            settings.save_without_plaintext_keys();
+        }
+        Some(settings)
+    }
+
+    /// Fill empty secrets from Credential Manager, and move ones written
+    /// into settings.json there, returning whether any were moved. A
+    /// portable install keeps its keys in its own settings file.
+    fn load_secrets(&mut self) -> bool {
+        if crate::paths::is_portable() {
+            return false;
+        }
+        let mut moved = false;
+        for (target, secret) in self.secrets_mut() {
+            if secret.is_empty() {
+                *secret = credentials::read(&target).unwrap_or_default();
+            } else if credentials::write(&target, secret).is_ok() {
+                moved = true;
+            }
+        }
+        moved
+    }
+
+    /// Rewrite settings.json once its keys have moved to Credential Manager
+    fn save_without_plaintext_keys(&self) {
+        if let Err(e) = self.save() {
+            log!("Failed to move API keys out of settings.json: {}", e);
+        }
+    }
+
+    /// Every secret in the settings, with the Credential Manager entry it's
+    /// kept under
+    fn secrets_mut(&mut self) -> Vec<(String, &mut String)> {
+        let mut secrets = vec![
+            (credentials::API_KEY_TARGET.to_string(), &mut self.api_key),
+            (
+                credentials::AUTOMATION_TOKEN_TARGET.to_string(),
+                &mut self.automation_api.token,
+            ),
+        ];
+        for binding in &mut self.hotkey_bindings {
+            let target = credentials::binding_key_target(&binding.hotkey);
+            secrets.push((target, &mut binding.api_key));
+        }
+        for profile in &mut self.profiles {
+            let target = credentials::profile_key_target(&profile.name);
+            secrets.push((target, &mut profile.api_key));
+        }
+        secrets
     }
 
     pub fn save(&self) -> Result<(), String> {
-        let mut path = dirs::data_local_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
-        path.push("windows-whisper");
-        std::fs::create_dir_all(&path).map_err(|e| e.to_string())?;
-        path.push("settings.json");
+        // API keys go to Credential Manager where available, and only fall
+        // back to the file elsewhere or in portable mode
+        let mut stored = self.clone();
+        if !crate::paths::is_portable() {
+            let mut targets = Vec::new();
+            for (target, secret) in stored.secrets_mut() {
+                if secret.is_empty() {
+                    credentials::delete(&target);
+                } else if credentials::write(&target, secret).is_ok() {
+                    secret.clear();
+                }
+                targets.push(target);
+            }
+            // Forget the keys of bindings and profiles that were removed,
+            // rebound, or renamed
+            if let Some(mut previous) = parse_settings(&LAST_CONTENT.lock().unwrap()) {
+                for (target, _) in previous.secrets_mut() {
+                    if !targets.contains(&target) {
+                        credentials::delete(&target);
+                    }
+                }
+            }
+        }
 
-        let content = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
-        std::fs::write(path, content).map_err(|e| e.to_string())?;
+        let content = serde_json::to_string_pretty(&stored).map_err(|e| e.to_string())?;
+        write_atomically(&settings_path(), &content).map_err(|e| e.to_string())?;
+        *LAST_CONTENT.lock().unwrap() = content;
         Ok(())
     }
+
+    pub fn onboarding_state(&self) -> OnboardingState {
+        OnboardingState {
+            first_run: self.first_run,
+            completed: self.onboarding_steps.clone(),
+            next: OnboardingStep::ALL
+                .into_iter()
+                .find(|step| !self.onboarding_steps.contains(step)),
+        }
+    }
+
+    /// Record `step` as done, ending the first run once every step is
+    pub fn complete_onboarding_step(&mut self, step: OnboardingStep) {
+        if !self.onboarding_steps.contains(&step) {
+            self.onboarding_steps.push(step);
+        }
+        if self.onboarding_state().next.is_none() {
+            self.first_run = false;
+        }
+    }
+
+    /// Normalize every server URL with `normalize_api_url`
+    pub fn normalize_urls(&mut self) {
+        self.api_url = normalize_api_url(&self.api_url);
+        for binding in &mut self.hotkey_bindings {
+            binding.api_url = normalize_api_url(&binding.api_url);
+        }
+        for profile in &mut self.profiles {
+            profile.api_url = normalize_api_url(&profile.api_url);
+        }
+    }
+
+    /// Problems with individual fields, beyond the hotkey checks that need the
+    /// OS. Empty when the settings are usable.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let mut error = |field: String, message: &str| {
+            errors.push(FieldError {
+                field,
+                message: message.to_string(),
+            })
+        };
+
+        let mut urls = vec![
+            ("api_url".to_string(), &self.api_url),
+            ("webhook_url".to_string(), &self.webhook_url),
+        ];
+        for (index, binding) in self.hotkey_bindings.iter().enumerate() {
+            urls.push((
+                format!("hotkey_bindings[{}].api_url", index),
+                &binding.api_url,
+            ));
+        }
+        for (index, profile) in self.profiles.iter().enumerate() {
+            urls.push((format!("profiles[{}].api_url", index), &profile.api_url));
+        }
+        for (field, url) in urls {
+            if !url.trim().is_empty() && !is_http_url(url) {
+                error(field, "Must be an http:// or https:// URL");
+            }
+        }
+
+        for (index, rule) in self.custom_replacements.iter().enumerate() {
+            if rule.find.trim().is_empty() {
+                error(
+                    format!("custom_replacements[{}].find", index),
+                    "Text to replace can't be empty",
+                );
+            }
+        }
+        for (index, profile) in self.profiles.iter().enumerate() {
+            if profile.name.trim().is_empty() {
+                error(format!("profiles[{}].name", index), "Name can't be empty");
+            } else if self.profiles[..index]
+                .iter()
+                .any(|other| other.name == profile.name)
+            {
+                error(
+                    format!("profiles[{}].name", index),
+                    "Another profile has this name",
+                );
+            }
+        }
+
+        if self.max_recording_seconds == 0
+            || self.max_recording_seconds > MAX_RECORDING_SECONDS_LIMIT
+        {
+            error(
+                "max_recording_seconds".to_string(),
+                "Must be between 1 second and an hour",
+            );
+        }
+        if self.silence_stop_seconds > SILENCE_STOP_SECONDS_LIMIT {
+            error(
+                "silence_stop_seconds".to_string(),
+                "Must be a minute or less",
+            );
+        }
+        if !(0.1..=1.0).contains(&self.popup.opacity) {
+            error("popup.opacity".to_string(), "Must be between 10% and 100%");
+        }
+        if self.popup.width < MIN_POPUP_SIZE || self.popup.height < MIN_POPUP_SIZE {
+            error(
+                "popup.width".to_string(),
+                "Popup must be at least 20 pixels in each direction",
+            );
+        }
+        if self.paste_min_delay_ms > self.paste_max_delay_ms {
+            error(
+                "paste_min_delay_ms".to_string(),
+                "Can't be longer than the maximum paste delay",
+            );
+        }
+        if self.automation_api.enabled {
+            if self.automation_api.token.len() < MIN_AUTOMATION_TOKEN_CHARS {
+                error(
+                    "automation_api.token".to_string(),
+                    "Must be at least 16 characters",
+                );
+            }
+            if self.automation_api.port == 0 {
+                error("automation_api.port".to_string(), "Pick a port number");
+            }
+        }
+        if self.quiet_hours.enabled {
+            if parse_time(&self.quiet_hours.start).is_none() {
+                error("quiet_hours.start".to_string(), "Must be a time like 22:00");
+            }
+            if parse_time(&self.quiet_hours.end).is_none() {
+                error("quiet_hours.end".to_string(), "Must be a time like 07:00");
+            }
+        }
+        // The mouse hook only starts recordings, so a button bound to any
+        // other action would just stop working
+        for (field, hotkey) in self.configured_hotkeys() {
+            let recording_trigger = field == "hotkey" || field.starts_with("hotkey_bindings");
+            if !recording_trigger && crate::mouse_hook::parse_mouse_trigger(&hotkey).is_some() {
+                error(field, "Mouse buttons can only start recording");
+            }
+        }
+        errors
+    }
+
+    /// Every configured hotkey as `(field, hotkey)` pairs, skipping unset ones
+    pub fn configured_hotkeys(&self) -> Vec<(String, String)> {
+        let mut hotkeys = vec![
+            ("hotkey".to_string(), self.hotkey.clone()),
+            ("undo_hotkey".to_string(), self.undo_hotkey.clone()),
+            ("repaste_hotkey".to_string(), self.repaste_hotkey.clone()),
+            ("pause_hotkey".to_string(), self.pause_hotkey.clone()),
+        ];
+        for (index, paste) in self.history_paste_hotkeys.iter().enumerate() {
+            hotkeys.push((
+                format!("history_paste_hotkeys[{}]", index),
+                paste.hotkey.clone(),
+            ));
+        }
+        for (index, binding) in self.hotkey_bindings.iter().enumerate() {
+            hotkeys.push((
+                format!("hotkey_bindings[{}]", index),
+                binding.hotkey.clone(),
+            ));
+        }
+        hotkeys.retain(|(_, hotkey)| !hotkey.trim().is_empty());
+        hotkeys
+    }
+
+    /// Settings for a recording started by `binding`, with its profile's
+    /// overrides applied.
+    pub fn for_binding(&self, binding: &HotkeyBinding) -> Settings {
+        self.with_overrides(
+            &binding.api_url,
+            &binding.api_key,
+            &binding.language,
+            binding.postprocessing,
+        )
+    }
+
+    /// The profile picked from the tray, if any
+    pub fn active_profile(&self) -> Option<&SettingsProfile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.name == self.active_profile)
+    }
+
+    /// Settings with the active profile's overrides applied
+    pub fn with_active_profile(&self) -> Settings {
+        match self.active_profile() {
+            Some(profile) => self.with_overrides(
+                &profile.api_url,
+                &profile.api_key,
+                &profile.language,
+                profile.postprocessing,
+            ),
+            None => self.clone(),
+        }
+    }
+
+    /// A copy with each non-empty override replacing its setting
+    fn with_overrides(
+        &self,
+        api_url: &str,
+        api_key: &str,
+        language: &str,
+        postprocessing: PostProcessingMode,
+    ) -> Settings {
+        let mut settings = self.clone();
+        if !api_url.is_empty() {
+            settings.api_url = api_url.to_string();
+        }
+        if !api_key.is_empty() {
+            settings.api_key = api_key.to_string();
+        }
+        if !language.is_empty() {
+            settings.language = language.to_string();
+        }
+        if postprocessing == PostProcessingMode::Raw {
+            settings.auto_capitalize = false;
+            settings.remove_filler_words = false;
+            settings.remove_punctuation = false;
+            settings.dedupe_repeated_phrases = false;
+            settings.custom_replacements.clear();
+        }
+        settings
+    }
+
+    /// Resolve the output mode for the given target process, falling back
+    /// to the global mode when no per-app override matches.
+    pub fn output_mode_for(&self, process_name: Option<&str>) -> OutputMode {
+        process_name
+            .and_then(|name| {
+                self.app_output_modes
+                    .iter()
+                    .find(|rule| crate::target::process_matches(&rule.process, name))
+            })
+            .map(|rule| rule.mode)
+            .unwrap_or(self.output_mode)
+    }
+
+    /// Resolve the paste chord for the given target process.
+    pub fn paste_chord_for(&self, process_name: Option<&str>) -> PasteChord {
+        process_name
+            .and_then(|name| {
+                self.app_paste_chords
+                    .iter()
+                    .find(|rule| crate::target::process_matches(&rule.process, name))
+            })
+            .map(|rule| rule.chord)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quiet(start: &str, end: &str, days: &[u8]) -> QuietHours {
+        QuietHours {
+            enabled: true,
+            days: days.to_vec(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn daytime_quiet_hours_cover_listed_days_only() {
+        let hours = quiet("09:00", "17:30", &[0, 1, 2, 3, 4]);
+        assert!(hours.is_active_at(0, 9 * 60));
+        assert!(hours.is_active_at(4, 17 * 60 + 29));
+        assert!(!hours.is_active_at(4, 17 * 60 + 30));
+        assert!(!hours.is_active_at(5, 12 * 60));
+    }
+
+    #[test]
+    fn overnight_quiet_hours_run_into_the_next_day() {
+        // Friday night only
+        let hours = quiet("22:00", "07:00", &[4]);
+        assert!(hours.is_active_at(4, 23 * 60));
+        assert!(hours.is_active_at(5, 6 * 60));
+        assert!(!hours.is_active_at(5, 23 * 60));
+        assert!(!hours.is_active_at(4, 6 * 60));
+    }
+
+    #[test]
+    fn cleared_review_hotkeys_are_left_out() {
+        let hotkeys = ReviewHotkeys {
+            copy_only: " ".to_string(),
+            ..ReviewHotkeys::default()
+        };
+        let fields: Vec<String> = hotkeys
+            .configured()
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(
+            fields,
+            vec!["review_hotkeys.paste", "review_hotkeys.discard"]
+        );
+    }
+
+    #[test]
+    fn corrupted_settings_are_restored_from_backup() {
+        let dir = std::env::temp_dir().join(format!("whisper-settings-{}", std::process::id()));
+        let path = dir.join("settings.json");
+        let mut settings = Settings::default();
+        settings.hotkey = "Ctrl+Alt+D".to_string();
+        let content = serde_json::to_string(&settings).unwrap();
+        write_atomically(&path, &content).unwrap();
+        write_atomically(&path, &content).unwrap();
+        assert!(path.with_extension("json.bak").exists());
+
+        std::fs::write(&path, "{\"hotkey\": \"Ctrl+").unwrap();
+        let (loaded, recovery) = load_from(&path);
+        assert_eq!(recovery, Some(SettingsRecovery::RestoredFromBackup));
+        assert_eq!(loaded.hotkey, "Ctrl+Alt+D");
+        assert!(path.with_extension("json.corrupt").exists());
+
+        std::fs::remove_file(path.with_extension("json.bak")).unwrap();
+        std::fs::write(&path, "").unwrap();
+        let (_, recovery) = load_from(&path);
+        assert_eq!(recovery, Some(SettingsRecovery::ResetToDefaults));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn active_profile_overrides_settings() {
+        let mut settings = Settings::default();
+        settings.api_url = "https://main.example".to_string();
+        settings.language = "en".to_string();
+        settings.profiles.push(SettingsProfile {
+            name: "German".to_string(),
+            postprocessing: PostProcessingMode::Raw,
+            api_url: String::new(),
+            api_key: String::new(),
+            language: "de".to_string(),
+        });
+        assert_eq!(settings.with_active_profile().language, "en");
+
+        settings.active_profile = "German".to_string();
+        let active = settings.with_active_profile();
+        assert_eq!(active.language, "de");
+        assert_eq!(active.api_url, "https://main.example");
+        assert!(!active.auto_capitalize);
+
+        settings.active_profile = "Deleted".to_string();
+        assert!(settings.active_profile().is_none());
+    }
+
+    #[test]
+    fn each_secret_has_its_own_credential() {
+        let mut settings = Settings::default();
+        settings.api_key = "main-key".to_string();
+        settings.automation_api.token = "automation-token".to_string();
+        settings.hotkey_bindings.push(HotkeyBinding {
+            hotkey: "Ctrl+F1".to_string(),
+            name: "German clipboard".to_string(),
+            output: OutputTarget::default(),
+            postprocessing: PostProcessingMode::default(),
+            api_url: "https://other.example".to_string(),
+            api_key: "binding-key".to_string(),
+            language: "de".to_string(),
+        });
+        settings.profiles.push(SettingsProfile {
+            name: "Work".to_string(),
+            postprocessing: PostProcessingMode::default(),
+            api_url: String::new(),
+            api_key: "profile-key".to_string(),
+            language: String::new(),
+        });
+        let secrets: Vec<(String, String)> = settings
+            .secrets_mut()
+            .into_iter()
+            .map(|(target, secret)| (target, secret.clone()))
+            .collect();
+        assert_eq!(
+            secrets,
+            vec![
+                (
+                    credentials::API_KEY_TARGET.to_string(),
+                    "main-key".to_string()
+                ),
+                (
+                    credentials::AUTOMATION_TOKEN_TARGET.to_string(),
+                    "automation-token".to_string()
+                ),
+                (
+                    "windows-whisper:binding:Ctrl+F1:api_key".to_string(),
+                    "binding-key".to_string()
+                ),
+                (
+                    "windows-whisper:profile:Work:api_key".to_string(),
+                    "profile-key".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn validation_names_each_bad_field() {
+        let mut settings = Settings::default();
+        assert!(settings.validate().is_empty());
+
+        settings.api_url = "worker.example.dev".to_string();
+        settings.webhook_url = "https://hooks.example.com/x".to_string();
+        settings.paste_min_delay_ms = 2000;
+        settings.custom_replacements.push(ReplacementRule {
+            find: " ".to_string(),
+            replace: "x".to_string(),
+        });
+        settings.hotkey = "Mouse5".to_string();
+        settings.undo_hotkey = "Mouse4".to_string();
+        let fields: Vec<_> = settings
+            .validate()
+            .into_iter()
+            .map(|error| error.field)
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "api_url",
+                "custom_replacements[0].find",
+                "paste_min_delay_ms",
+                "undo_hotkey"
+            ]
+        );
+    }
+
+    #[test]
+    fn api_urls_are_normalized() {
+        assert_eq!(
+            normalize_api_url(" whisper.example.workers.dev/ "),
+            "https://whisper.example.workers.dev"
+        );
+        assert_eq!(
+            normalize_api_url("https://whisper.example.workers.dev/transcribe/"),
+            "https://whisper.example.workers.dev"
+        );
+        assert_eq!(
+            normalize_api_url("http://localhost:8787/api/Transcribe"),
+            "http://localhost:8787/api"
+        );
+        assert_eq!(normalize_api_url(""), "");
+    }
+
+    #[test]
+    fn onboarding_ends_when_every_step_is_done() {
+        let mut settings = Settings::default();
+        assert!(settings.first_run);
+        assert_eq!(
+            settings.onboarding_state().next,
+            Some(OnboardingStep::Backend)
+        );
+
+        settings.complete_onboarding_step(OnboardingStep::Microphone);
+        settings.complete_onboarding_step(OnboardingStep::Backend);
+        assert_eq!(
+            settings.onboarding_state().next,
+            Some(OnboardingStep::Hotkey)
+        );
+        assert!(settings.first_run);
+
+        settings.complete_onboarding_step(OnboardingStep::Hotkey);
+        assert!(!settings.first_run);
+        assert_eq!(settings.onboarding_state().next, None);
+    }
+
+    #[test]
+    fn disabled_or_malformed_quiet_hours_never_apply() {
+        let mut hours = quiet("00:00", "23:59", &[0]);
+        hours.enabled = false;
+        assert!(!hours.is_active_at(0, 60));
+        assert!(!quiet("25:00", "07:00", &[0]).is_active_at(0, 60));
+    }
 }
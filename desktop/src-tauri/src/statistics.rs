@@ -0,0 +1,149 @@
+//! Dictation statistics computed from history metadata
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+/// Typing speed assumed when estimating time saved by dictating
+const TYPING_WPM: f64 = 40.0;
+
+/// What `statistics` needs from one history entry
+pub struct DictationSample {
+    /// ISO 8601 timestamp
+    pub timestamp: String,
+    pub word_count: u32,
+    pub duration_ms: Option<u32>,
+}
+
+/// Words dictated in one day (`YYYY-MM-DD`) or ISO week (`YYYY-Www`)
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PeriodWords {
+    pub period: String,
+    pub words: u64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Statistics {
+    pub total_entries: u64,
+    pub total_words: u64,
+    /// Recorded time across entries that know their duration
+    pub total_dictation_seconds: f64,
+    /// Words per minute over entries that know their duration
+    pub average_wpm: Option<f64>,
+    /// Time typing the same words at `TYPING_WPM` would have taken, minus
+    /// the time spent dictating them
+    pub typing_seconds_saved: f64,
+    /// Oldest first
+    pub words_per_day: Vec<PeriodWords>,
+    pub words_per_week: Vec<PeriodWords>,
+}
+
+/// Add `words` to the period at the end of `periods`, or start a new one.
+/// Samples arrive oldest first, so equal periods are always adjacent.
+fn add_words(periods: &mut Vec<PeriodWords>, period: String, words: u64) {
+    match periods.last_mut() {
+        Some(last) if last.period == period => last.words += words,
+        _ => periods.push(PeriodWords { period, words }),
+    }
+}
+
+/// Summarise `samples`, given oldest first
+pub fn statistics(samples: &[DictationSample]) -> Statistics {
+    let mut stats = Statistics {
+        total_entries: samples.len() as u64,
+        total_words: 0,
+        total_dictation_seconds: 0.0,
+        average_wpm: None,
+        typing_seconds_saved: 0.0,
+        words_per_day: Vec::new(),
+        words_per_week: Vec::new(),
+    };
+    let mut timed_words = 0u64;
+
+    for sample in samples {
+        let words = sample.word_count as u64;
+        stats.total_words += words;
+        if let Some(duration_ms) = sample.duration_ms {
+            stats.total_dictation_seconds += duration_ms as f64 / 1000.0;
+            timed_words += words;
+        }
+
+        let day = sample.timestamp.get(..10).unwrap_or_default();
+        let Ok(date) = NaiveDate::parse_from_str(day, "%Y-%m-%d") else {
+            continue;
+        };
+        add_words(&mut stats.words_per_day, day.to_string(), words);
+        let week = date.iso_week();
+        add_words(
+            &mut stats.words_per_week,
+            format!("{}-W{:02}", week.year(), week.week()),
+            words,
+        );
+    }
+
+    if stats.total_dictation_seconds > 0.0 {
+        stats.average_wpm = Some(timed_words as f64 / (stats.total_dictation_seconds / 60.0));
+    }
+    let typing_seconds = timed_words as f64 / TYPING_WPM * 60.0;
+    stats.typing_seconds_saved = (typing_seconds - stats.total_dictation_seconds).max(0.0);
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: &str, word_count: u32, duration_ms: Option<u32>) -> DictationSample {
+        DictationSample {
+            timestamp: timestamp.to_string(),
+            word_count,
+            duration_ms,
+        }
+    }
+
+    #[test]
+    fn totals_and_speeds() {
+        let stats = statistics(&[
+            sample("2026-03-02T09:00:00+00:00", 60, Some(30_000)),
+            sample("2026-03-02T10:00:00+00:00", 60, Some(30_000)),
+            sample("2026-03-03T10:00:00+00:00", 10, None),
+        ]);
+        assert_eq!(stats.total_entries, 3);
+        assert_eq!(stats.total_words, 130);
+        assert_eq!(stats.total_dictation_seconds, 60.0);
+        assert_eq!(stats.average_wpm, Some(120.0));
+        // 120 words take three minutes to type at 40 WPM
+        assert_eq!(stats.typing_seconds_saved, 120.0);
+    }
+
+    #[test]
+    fn words_are_grouped_by_day_and_iso_week() {
+        let stats = statistics(&[
+            sample("2026-03-01T09:00:00+00:00", 5, None),
+            sample("2026-03-02T09:00:00+00:00", 7, None),
+            sample("2026-03-02T18:00:00+00:00", 3, None),
+        ]);
+        let days: Vec<_> = stats.words_per_day.iter().map(|d| d.words).collect();
+        assert_eq!(days, [5, 10]);
+        // Sunday the 1st closes week 9; Monday the 2nd opens week 10
+        assert_eq!(
+            stats.words_per_week,
+            [
+                PeriodWords {
+                    period: "2026-W09".into(),
+                    words: 5
+                },
+                PeriodWords {
+                    period: "2026-W10".into(),
+                    words: 10
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn no_history_has_no_speed() {
+        let stats = statistics(&[]);
+        assert_eq!(stats.average_wpm, None);
+        assert_eq!(stats.typing_seconds_saved, 0.0);
+    }
+}
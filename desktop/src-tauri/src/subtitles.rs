@@ -0,0 +1,125 @@
+//! Subtitle files (SRT and WebVTT) from the timed segments a server returns
+//! for each chunk of a transcribed file
+
+use crate::settings::SubtitleFormat;
+use serde::{Deserialize, Serialize};
+
+/// A stretch of speech, in seconds from the start of its audio
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Add the cues of a chunk starting `offset` seconds into the file. Chunks
+/// overlap, so cues starting before the last one added ends are dropped.
+pub fn append_chunk(cues: &mut Vec<Cue>, offset: f64, chunk: Vec<Cue>) {
+    let covered_until = cues.last().map_or(0.0, |cue| cue.end);
+    cues.extend(
+        chunk
+            .into_iter()
+            .map(|cue| Cue {
+                start: cue.start + offset,
+                end: cue.end + offset,
+                text: cue.text.trim().to_string(),
+            })
+            .filter(|cue| !cue.text.is_empty() && cue.start >= covered_until),
+    );
+}
+
+/// `cues` as a subtitle file in `format`
+pub fn render(format: SubtitleFormat, cues: &[Cue]) -> String {
+    match format {
+        SubtitleFormat::Srt => srt(cues),
+        SubtitleFormat::Vtt => vtt(cues),
+    }
+}
+
+/// SubRip: numbered cues with `00:00:01,500` times
+pub fn srt(cues: &[Cue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                timestamp(cue.start, ','),
+                timestamp(cue.end, ','),
+                cue.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// WebVTT: a header, then cues with `00:00:01.500` times
+pub fn vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n");
+    for cue in cues {
+        out.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            timestamp(cue.start, '.'),
+            timestamp(cue.end, '.'),
+            cue.text
+        ));
+    }
+    out
+}
+
+/// `hh:mm:ss` and milliseconds after `separator`
+fn timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cue(start: f64, end: f64, text: &str) -> Cue {
+        Cue {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn overlapping_chunks_are_offset_and_deduplicated() {
+        let mut cues = Vec::new();
+        append_chunk(
+            &mut cues,
+            0.0,
+            vec![cue(0.0, 4.0, "Hello there."), cue(4.5, 9.5, "How are you?")],
+        );
+        // The next chunk starts a second before the first ends
+        append_chunk(
+            &mut cues,
+            9.0,
+            vec![cue(0.0, 0.5, "you?"), cue(1.0, 3.0, " Fine, thanks. ")],
+        );
+        assert_eq!(
+            cues,
+            vec![
+                cue(0.0, 4.0, "Hello there."),
+                cue(4.5, 9.5, "How are you?"),
+                cue(10.0, 12.0, "Fine, thanks."),
+            ]
+        );
+    }
+
+    #[test]
+    fn formats_use_their_own_time_separators() {
+        let cues = vec![cue(1.5, 3661.25, "Hi")];
+        assert_eq!(srt(&cues), "1\n00:00:01,500 --> 01:01:01,250\nHi\n");
+        assert_eq!(vtt(&cues), "WEBVTT\n\n00:00:01.500 --> 01:01:01.250\nHi\n");
+    }
+}
@@ -0,0 +1,516 @@
+//! Foreground window inspection used to tailor how text is delivered
+
+/// The window that will receive the transcript
+#[derive(Debug, Clone, Default)]
+pub struct TargetWindow {
+    /// Native window handle: an HWND on Windows, an X11 window id on Linux,
+    /// and the app's process id on macOS (0 when unavailable)
+    pub hwnd: isize,
+    /// Executable name, lowercased (e.g. `windowsterminal.exe`)
+    pub process_name: Option<String>,
+    /// Whether the owning process runs elevated (as administrator)
+    pub elevated: bool,
+}
+
+impl TargetWindow {
+    /// Inspect the window that currently has keyboard focus
+    #[cfg(target_os = "windows")]
+    pub fn foreground() -> Option<Self> {
+        use windows_sys::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+        let hwnd = unsafe { GetForegroundWindow() };
+        if hwnd == 0 {
+            return None;
+        }
+
+        let (process_name, elevated) = inspect_window_process(hwnd);
+        Some(Self {
+            hwnd,
+            process_name,
+            elevated,
+        })
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn foreground() -> Option<Self> {
+        let (hwnd, process_name) = focused_window()?;
+        Some(Self {
+            hwnd,
+            process_name,
+            elevated: false,
+        })
+    }
+
+    /// Whether this window still has keyboard focus
+    #[cfg(target_os = "windows")]
+    pub fn is_foreground(&self) -> bool {
+        use windows_sys::Win32::UI::WindowsAndMessaging::GetForegroundWindow;
+
+        unsafe { GetForegroundWindow() == self.hwnd }
+    }
+
+    /// Where the focused window can't be looked up, assume it hasn't changed
+    #[cfg(not(target_os = "windows"))]
+    pub fn is_foreground(&self) -> bool {
+        match focused_window() {
+            Some((hwnd, _)) => hwnd == self.hwnd,
+            None => true,
+        }
+    }
+
+    /// Bring this window back to the foreground, returning whether it worked
+    #[cfg(target_os = "windows")]
+    pub fn focus(&self) -> bool {
+        use windows_sys::Win32::System::Threading::{AttachThreadInput, GetCurrentThreadId};
+        use windows_sys::Win32::UI::WindowsAndMessaging::{
+            BringWindowToTop, GetForegroundWindow, GetWindowThreadProcessId, IsIconic, IsWindow,
+            SetForegroundWindow, ShowWindow, SW_RESTORE,
+        };
+
+        unsafe {
+            if IsWindow(self.hwnd) == 0 {
+                return false;
+            }
+            if IsIconic(self.hwnd) != 0 {
+                ShowWindow(self.hwnd, SW_RESTORE);
+            }
+            if SetForegroundWindow(self.hwnd) != 0 && self.is_foreground() {
+                return true;
+            }
+
+            // The foreground lock only lets the active thread hand over focus,
+            // so share its input state (and the target's) while activating
+            let current = GetCurrentThreadId();
+            let foreground_thread =
+                GetWindowThreadProcessId(GetForegroundWindow(), std::ptr::null_mut());
+            let target_thread = GetWindowThreadProcessId(self.hwnd, std::ptr::null_mut());
+
+            let attached_foreground = foreground_thread != 0
+                && foreground_thread != current
+                && AttachThreadInput(current, foreground_thread, 1) != 0;
+            let attached_target = target_thread != 0
+                && target_thread != current
+                && target_thread != foreground_thread
+                && AttachThreadInput(current, target_thread, 1) != 0;
+
+            BringWindowToTop(self.hwnd);
+            SetForegroundWindow(self.hwnd);
+
+            if attached_target {
+                AttachThreadInput(current, target_thread, 0);
+            }
+            if attached_foreground {
+                AttachThreadInput(current, foreground_thread, 0);
+            }
+        }
+
+        // Activation is processed asynchronously by the target's thread
+        for _ in 0..10 {
+            if self.is_foreground() {
+                return true;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        false
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn focus(&self) -> bool {
+        activate_window(self.hwnd) && self.is_foreground()
+    }
+
+    /// Whether this is a legacy conhost console window (cmd, PowerShell)
+    #[cfg(target_os = "windows")]
+    pub fn is_console(&self) -> bool {
+        window_class_name(self.hwnd).as_deref() == Some("ConsoleWindowClass")
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn is_console(&self) -> bool {
+        false
+    }
+
+    /// Whether this window covers its whole monitor, like a full-screen game.
+    /// The desktop and shell windows don't count.
+    #[cfg(target_os = "windows")]
+    pub fn is_fullscreen(&self) -> bool {
+        use windows_sys::Win32::Foundation::RECT;
+        use windows_sys::Win32::Graphics::Gdi::{
+            GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+        };
+        use windows_sys::Win32::UI::WindowsAndMessaging::GetWindowRect;
+
+        if matches!(
+            window_class_name(self.hwnd).as_deref(),
+            Some("Progman" | "WorkerW" | "Shell_TrayWnd")
+        ) {
+            return false;
+        }
+
+        unsafe {
+            let mut window: RECT = std::mem::zeroed();
+            if GetWindowRect(self.hwnd, &mut window) == 0 {
+                return false;
+            }
+            let monitor = MonitorFromWindow(self.hwnd, MONITOR_DEFAULTTONEAREST);
+            let mut info: MONITORINFO = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+            if GetMonitorInfoW(monitor, &mut info) == 0 {
+                return false;
+            }
+            let screen = info.rcMonitor;
+            window.left <= screen.left
+                && window.top <= screen.top
+                && window.right >= screen.right
+                && window.bottom >= screen.bottom
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn is_fullscreen(&self) -> bool {
+        false
+    }
+
+    /// Input from a non-elevated process is silently dropped by elevated windows (UIPI)
+    pub fn elevation_mismatch(&self) -> bool {
+        self.elevated && !current_process_is_elevated()
+    }
+}
+
+/// The active X11 window and its process name, through `xdotool`. Wayland
+/// doesn't tell other clients which window has focus.
+#[cfg(target_os = "linux")]
+fn focused_window() -> Option<(isize, Option<String>)> {
+    let xdotool = |args: &[&str]| {
+        std::process::Command::new("xdotool")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let window = xdotool(&["getactivewindow"])?;
+    let hwnd = window.parse().ok()?;
+    let process_name = xdotool(&["getwindowpid", window.as_str()])
+        .and_then(|pid| std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok())
+        .map(|name| name.trim().to_lowercase());
+    Some((hwnd, process_name))
+}
+
+#[cfg(target_os = "linux")]
+fn activate_window(hwnd: isize) -> bool {
+    std::process::Command::new("xdotool")
+        .args(["windowactivate", "--sync", &hwnd.to_string()])
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// The frontmost app, identified by its process id
+#[cfg(target_os = "macos")]
+fn focused_window() -> Option<(isize, Option<String>)> {
+    crate::ax::focused_app().map(|(pid, name)| (pid as isize, name))
+}
+
+#[cfg(target_os = "macos")]
+fn activate_window(hwnd: isize) -> bool {
+    crate::ax::activate_app(hwnd as i32)
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn focused_window() -> Option<(isize, Option<String>)> {
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+fn activate_window(_hwnd: isize) -> bool {
+    false
+}
+
+/// Executable name and elevation of the process owning `hwnd`
+#[cfg(target_os = "windows")]
+fn inspect_window_process(hwnd: isize) -> (Option<String>, bool) {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    unsafe {
+        let mut pid: u32 = 0;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        if pid == 0 {
+            return (None, false);
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process == 0 {
+            return (None, false);
+        }
+
+        let mut buffer = [0u16; 260];
+        let mut len = buffer.len() as u32;
+        let name = if QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            buffer.as_mut_ptr(),
+            &mut len,
+        ) != 0
+        {
+            let path = String::from_utf16_lossy(&buffer[..len as usize]);
+            path.rsplit('\\').next().map(|name| name.to_lowercase())
+        } else {
+            None
+        };
+
+        let elevated = process_is_elevated(process).unwrap_or(false);
+        CloseHandle(process);
+        (name, elevated)
+    }
+}
+
+/// Whether the process behind `process` has an elevated token
+#[cfg(target_os = "windows")]
+fn process_is_elevated(process: isize) -> Option<bool> {
+    use windows_sys::Win32::Foundation::{CloseHandle, GetLastError, ERROR_ACCESS_DENIED};
+    use windows_sys::Win32::Security::{
+        GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY,
+    };
+    use windows_sys::Win32::System::Threading::OpenProcessToken;
+
+    unsafe {
+        let mut token = 0;
+        if OpenProcessToken(process, TOKEN_QUERY, &mut token) == 0 {
+            // A non-elevated caller can't open an elevated process's token
+            return if GetLastError() == ERROR_ACCESS_DENIED {
+                Some(true)
+            } else {
+                None
+            };
+        }
+
+        let mut elevation = TOKEN_ELEVATION { TokenIsElevated: 0 };
+        let mut returned = 0u32;
+        let ok = GetTokenInformation(
+            token,
+            TokenElevation,
+            &mut elevation as *mut TOKEN_ELEVATION as *mut _,
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut returned,
+        );
+        CloseHandle(token);
+
+        if ok == 0 {
+            None
+        } else {
+            Some(elevation.TokenIsElevated != 0)
+        }
+    }
+}
+
+/// Whether this app is running as administrator
+#[cfg(target_os = "windows")]
+pub fn current_process_is_elevated() -> bool {
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    process_is_elevated(unsafe { GetCurrentProcess() }).unwrap_or(false)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn current_process_is_elevated() -> bool {
+    false
+}
+
+/// Window class name of `hwnd` (e.g. `ConsoleWindowClass`)
+#[cfg(target_os = "windows")]
+pub fn window_class_name(hwnd: isize) -> Option<String> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::GetClassNameW;
+
+    let mut buffer = [0u16; 256];
+    let len = unsafe { GetClassNameW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32) };
+    if len <= 0 {
+        return None;
+    }
+    Some(String::from_utf16_lossy(&buffer[..len as usize]))
+}
+
+/// The control with keyboard focus inside the foreground window
+#[cfg(target_os = "windows")]
+fn focused_control() -> Option<isize> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetGUIThreadInfo, GUITHREADINFO};
+
+    unsafe {
+        let mut info: GUITHREADINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+        if GetGUIThreadInfo(0, &mut info) == 0 || info.hwndFocus == 0 {
+            return None;
+        }
+        Some(info.hwndFocus)
+    }
+}
+
+/// Screen position of the bottom-left corner of the text caret, from the
+/// system caret or else UI Automation on Windows, and from the Accessibility
+/// API on macOS
+pub fn caret_position() -> Option<(i32, i32)> {
+    system_caret_position()
+        .or_else(crate::uia::caret_position)
+        .or_else(crate::ax::caret_position)
+}
+
+/// Caret position for controls that report it through the system caret,
+/// which most browser, Electron, and UWP apps don't
+#[cfg(target_os = "windows")]
+fn system_caret_position() -> Option<(i32, i32)> {
+    use windows_sys::Win32::Foundation::POINT;
+    use windows_sys::Win32::Graphics::Gdi::ClientToScreen;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetGUIThreadInfo, GUITHREADINFO};
+
+    unsafe {
+        let mut info: GUITHREADINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+        if GetGUIThreadInfo(0, &mut info) == 0 || info.hwndCaret == 0 {
+            return None;
+        }
+        let mut point = POINT {
+            x: info.rcCaret.left,
+            y: info.rcCaret.bottom,
+        };
+        if ClientToScreen(info.hwndCaret, &mut point) == 0 {
+            return None;
+        }
+        Some((point.x, point.y))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn system_caret_position() -> Option<(i32, i32)> {
+    None
+}
+
+/// The focused control if it is a native Edit or RichEdit, which accept `WM_PASTE`
+#[cfg(target_os = "windows")]
+pub fn focused_edit_control() -> Option<isize> {
+    let hwnd = focused_control()?;
+    let class = window_class_name(hwnd)?.to_lowercase();
+    (class == "edit" || class.starts_with("richedit")).then_some(hwnd)
+}
+
+/// Whether the focused control is a password or other secure input
+#[cfg(target_os = "windows")]
+pub fn focus_is_password_field() -> bool {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetWindowLongW, GWL_STYLE};
+
+    const ES_PASSWORD: i32 = 0x0020;
+
+    if let Some(hwnd) = focused_control() {
+        let is_edit =
+            window_class_name(hwnd).is_some_and(|class| class.eq_ignore_ascii_case("Edit"));
+        if is_edit && unsafe { GetWindowLongW(hwnd, GWL_STYLE) } & ES_PASSWORD != 0 {
+            return true;
+        }
+    }
+
+    // Browsers and modern toolkits don't use native edits; ask UI Automation
+    crate::uia::focused_is_password()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn focus_is_password_field() -> bool {
+    false
+}
+
+/// Closes the IME on the focused control for as long as it lives, so
+/// simulated paste chords and key events aren't swallowed by an active
+/// composition. The previous IME state is restored on drop.
+pub struct ImeSuspension {
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    ime_hwnd: isize,
+}
+
+#[cfg(target_os = "windows")]
+const IMC_GETOPENSTATUS: usize = 0x0005;
+#[cfg(target_os = "windows")]
+const IMC_SETOPENSTATUS: usize = 0x0006;
+
+/// Send an `IMC_*` control message to an IME window without hanging on a busy target
+#[cfg(target_os = "windows")]
+fn ime_control(ime_hwnd: isize, command: usize, value: isize) -> Option<usize> {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        SendMessageTimeoutW, SMTO_ABORTIFHUNG, WM_IME_CONTROL,
+    };
+
+    let mut result = 0usize;
+    let ok = unsafe {
+        SendMessageTimeoutW(
+            ime_hwnd,
+            WM_IME_CONTROL,
+            command,
+            value,
+            SMTO_ABORTIFHUNG,
+            200,
+            &mut result,
+        )
+    };
+    (ok != 0).then_some(result)
+}
+
+/// Suspend the IME on the focused control if one is open, finishing any
+/// composition in progress. Returns `None` when no IME is active.
+#[cfg(target_os = "windows")]
+pub fn suspend_ime() -> Option<ImeSuspension> {
+    use windows_sys::Win32::UI::Input::Ime::ImmGetDefaultIMEWnd;
+
+    let focus = focused_control()?;
+    let ime_hwnd = unsafe { ImmGetDefaultIMEWnd(focus) };
+    if ime_hwnd == 0 || ime_control(ime_hwnd, IMC_GETOPENSTATUS, 0)? == 0 {
+        return None;
+    }
+
+    ime_control(ime_hwnd, IMC_SETOPENSTATUS, 0)?;
+    Some(ImeSuspension { ime_hwnd })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn suspend_ime() -> Option<ImeSuspension> {
+    None
+}
+
+impl Drop for ImeSuspension {
+    fn drop(&mut self) {
+        #[cfg(target_os = "windows")]
+        ime_control(self.ime_hwnd, IMC_SETOPENSTATUS, 1);
+    }
+}
+
+/// Checks whether a user-entered process pattern (with or without `.exe`)
+/// refers to the given executable name.
+pub fn process_matches(pattern: &str, process_name: &str) -> bool {
+    fn strip_exe(name: &str) -> String {
+        let name = name.trim().to_lowercase();
+        match name.strip_suffix(".exe") {
+            Some(stem) => stem.to_string(),
+            None => name,
+        }
+    }
+
+    let pattern = strip_exe(pattern);
+    !pattern.is_empty() && pattern == strip_exe(process_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_names_match_without_case_or_extension() {
+        assert!(process_matches(
+            "WindowsTerminal.exe",
+            "windowsterminal.exe"
+        ));
+        assert!(process_matches("mintty", "mintty.exe"));
+        assert!(process_matches(" Code.EXE ", "code.exe"));
+        assert!(!process_matches("code", "codium.exe"));
+        assert!(!process_matches("", "code.exe"));
+    }
+}
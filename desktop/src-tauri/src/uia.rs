@@ -0,0 +1,470 @@
+//! UI Automation helpers for working with the focused control directly
+
+/// Run `f` against the focused UI Automation element on a COM-initialized thread.
+#[cfg(target_os = "windows")]
+fn with_focused_element<T>(
+    f: impl FnOnce(&windows::Win32::UI::Accessibility::IUIAutomationElement) -> Result<T, String>,
+) -> Result<T, String> {
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+        COINIT_MULTITHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{CUIAutomation, IUIAutomation};
+
+    unsafe {
+        let initialized = CoInitializeEx(None, COINIT_MULTITHREADED).is_ok();
+
+        let result =
+            CoCreateInstance::<_, IUIAutomation>(&CUIAutomation, None, CLSCTX_INPROC_SERVER)
+                .map_err(|e| format!("Failed to create UI Automation: {}", e))
+                .and_then(|automation| {
+                    automation
+                        .GetFocusedElement()
+                        .map_err(|e| format!("No focused element: {}", e))
+                })
+                .and_then(|element| f(&element));
+
+        if initialized {
+            CoUninitialize();
+        }
+        result
+    }
+}
+
+/// Whether the focused element reports itself as a password field
+#[cfg(target_os = "windows")]
+pub fn focused_is_password() -> bool {
+    with_focused_element(|element| unsafe {
+        element
+            .CurrentIsPassword()
+            .map(|b| b.as_bool())
+            .map_err(|e| e.to_string())
+    })
+    .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn focused_is_password() -> bool {
+    false
+}
+
+/// Current text of the focused control, if it exposes any via UI Automation
+#[cfg(target_os = "windows")]
+pub fn focused_text() -> Option<String> {
+    use windows::Win32::UI::Accessibility::{
+        IUIAutomationTextPattern, IUIAutomationValuePattern, UIA_TextPatternId, UIA_ValuePatternId,
+    };
+
+    with_focused_element(|element| unsafe {
+        if let Ok(value) =
+            element.GetCurrentPatternAs::<IUIAutomationValuePattern>(UIA_ValuePatternId)
+        {
+            if let Ok(current) = value.CurrentValue() {
+                return Ok(current.to_string());
+            }
+        }
+        let text_pattern: IUIAutomationTextPattern = element
+            .GetCurrentPatternAs(UIA_TextPatternId)
+            .map_err(|e| e.to_string())?;
+        let document = text_pattern.DocumentRange().map_err(|e| e.to_string())?;
+        let text = document.GetText(-1).map_err(|e| e.to_string())?;
+        Ok(text.to_string())
+    })
+    .ok()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn focused_text() -> Option<String> {
+    None
+}
+
+/// Screen position of the bottom-left corner of the caret in the focused
+/// control, for apps without a system caret (browsers, Electron, UWP, Office)
+#[cfg(target_os = "windows")]
+pub fn caret_position() -> Option<(i32, i32)> {
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::UI::Accessibility::{
+        IUIAutomationTextPattern, IUIAutomationTextPattern2, TextPatternRangeEndpoint_End,
+        TextPatternRangeEndpoint_Start, TextUnit_Character, UIA_TextPattern2Id, UIA_TextPatternId,
+    };
+
+    with_focused_element(|element| unsafe {
+        let caret =
+            match element.GetCurrentPatternAs::<IUIAutomationTextPattern2>(UIA_TextPattern2Id) {
+                Ok(pattern) => {
+                    let mut active = BOOL::default();
+                    pattern.GetCaretRange(&mut active).ok()
+                }
+                Err(_) => None,
+            };
+        // Without TextPattern2, the start of the selection is close enough
+        let caret = match caret {
+            Some(caret) => caret,
+            None => {
+                let pattern: IUIAutomationTextPattern = element
+                    .GetCurrentPatternAs(UIA_TextPatternId)
+                    .map_err(|e| e.to_string())?;
+                let selections = pattern.GetSelection().map_err(|e| e.to_string())?;
+                if selections.Length().unwrap_or(0) < 1 {
+                    return Ok(None);
+                }
+                selections.GetElement(0).map_err(|e| e.to_string())?
+            }
+        };
+
+        if let Some([left, top, _, height]) = range_rect(&caret) {
+            return Ok(Some((left as i32, (top + height) as i32)));
+        }
+        // A collapsed range has no rectangle, so measure the character after
+        // the caret, or the one before it at the end of the text
+        let next = caret.Clone().map_err(|e| e.to_string())?;
+        let _ = next.MoveEndpointByUnit(TextPatternRangeEndpoint_End, TextUnit_Character, 1);
+        if let Some([left, top, _, height]) = range_rect(&next) {
+            return Ok(Some((left as i32, (top + height) as i32)));
+        }
+        let previous = caret.Clone().map_err(|e| e.to_string())?;
+        let _ = previous.MoveEndpointByUnit(TextPatternRangeEndpoint_Start, TextUnit_Character, -1);
+        Ok(range_rect(&previous)
+            .map(|[left, top, width, height]| ((left + width) as i32, (top + height) as i32)))
+    })
+    .ok()
+    .flatten()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn caret_position() -> Option<(i32, i32)> {
+    None
+}
+
+/// First bounding rectangle of `range` as left, top, width, height
+#[cfg(target_os = "windows")]
+unsafe fn range_rect(
+    range: &windows::Win32::UI::Accessibility::IUIAutomationTextRange,
+) -> Option<[f64; 4]> {
+    use windows::Win32::System::Ole::{
+        SafeArrayAccessData, SafeArrayDestroy, SafeArrayUnaccessData,
+    };
+
+    let array = range.GetBoundingRectangles().ok()?;
+    if array.is_null() {
+        return None;
+    }
+    let mut data: *mut std::ffi::c_void = std::ptr::null_mut();
+    let rect = if SafeArrayAccessData(array, &mut data).is_ok() {
+        let values = data as *const f64;
+        let count = (*array).rgsabound[0].cElements as usize;
+        let rect = (count >= 4).then(|| [*values, *values.add(1), *values.add(2), *values.add(3)]);
+        let _ = SafeArrayUnaccessData(array);
+        rect
+    } else {
+        None
+    };
+    let _ = SafeArrayDestroy(array);
+    rect
+}
+
+/// Have screen readers speak `text`, raised as a UI Automation notification
+/// from the window `hwnd`. Silently does nothing without a screen reader.
+#[cfg(target_os = "windows")]
+pub fn announce(hwnd: isize, text: &str) {
+    use windows::core::BSTR;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Accessibility::{
+        NotificationKind_ActionCompleted, NotificationProcessing_ImportantMostRecent,
+        UiaHostProviderFromHwnd, UiaRaiseNotificationEvent,
+    };
+
+    unsafe {
+        let Ok(provider) = UiaHostProviderFromHwnd(HWND(hwnd)) else {
+            return;
+        };
+        let _ = UiaRaiseNotificationEvent(
+            &provider,
+            NotificationKind_ActionCompleted,
+            NotificationProcessing_ImportantMostRecent,
+            &BSTR::from(text),
+            &BSTR::from("windows-whisper-status"),
+        );
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn announce(_hwnd: isize, _text: &str) {}
+
+/// Insert text at the caret of the focused control by rewriting its value.
+///
+/// Rewriting replaces the whole value, which drops formatting and the
+/// app's undo history, so only plain single-line edit boxes are written to.
+/// Those also need a writable `ValuePattern` and a `TextPattern` (to locate
+/// the caret). Anything else returns an error so the caller can paste at
+/// the selection instead.
+#[cfg(target_os = "windows")]
+pub fn insert_text(text: &str) -> Result<(), String> {
+    use windows::core::BSTR;
+    use windows::Win32::UI::Accessibility::{
+        IUIAutomationTextPattern, IUIAutomationValuePattern, TextPatternRangeEndpoint_End,
+        TextPatternRangeEndpoint_Start, TextUnit_Character, UIA_EditControlTypeId,
+        UIA_TextPatternId, UIA_ValuePatternId,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::{GetWindowLongW, GWL_STYLE};
+    const ES_MULTILINE: i32 = 0x0004;
+
+    with_focused_element(|element| unsafe {
+        if element.CurrentControlType().ok() != Some(UIA_EditControlTypeId) {
+            return Err("Focused control isn't an edit box".to_string());
+        }
+        let class = element
+            .CurrentClassName()
+            .map(|class| class.to_string().to_lowercase())
+            .unwrap_or_default();
+        if class.contains("richedit") {
+            return Err("Focused control holds formatted text".to_string());
+        }
+        let hwnd = element
+            .CurrentNativeWindowHandle()
+            .map(|hwnd| hwnd.0)
+            .unwrap_or(0);
+        if hwnd != 0 && GetWindowLongW(hwnd, GWL_STYLE) & ES_MULTILINE != 0 {
+            return Err("Focused control is multi-line".to_string());
+        }
+
+        let value: IUIAutomationValuePattern = element
+            .GetCurrentPatternAs(UIA_ValuePatternId)
+            .map_err(|_| "Focused control has no ValuePattern".to_string())?;
+        if value
+            .CurrentIsReadOnly()
+            .map(|b| b.as_bool())
+            .unwrap_or(true)
+        {
+            return Err("Focused control is read-only".to_string());
+        }
+        // Controls without a native window can still be multi-line
+        if value
+            .CurrentValue()
+            .is_ok_and(|current| current.to_string().contains(['\n', '\r']))
+        {
+            return Err("Focused control is multi-line".to_string());
+        }
+
+        let text_pattern: IUIAutomationTextPattern = element
+            .GetCurrentPatternAs(UIA_TextPatternId)
+            .map_err(|_| "Focused control has no TextPattern".to_string())?;
+        let document = text_pattern
+            .DocumentRange()
+            .map_err(|e| format!("Failed to read document: {}", e))?;
+        let selections = text_pattern
+            .GetSelection()
+            .map_err(|e| format!("Failed to read selection: {}", e))?;
+        if selections.Length().unwrap_or(0) < 1 {
+            return Err("Focused control has no caret".to_string());
+        }
+        let selection = selections
+            .GetElement(0)
+            .map_err(|e| format!("Failed to read selection: {}", e))?;
+
+        // Text before the selection start and after the selection end
+        let before = document.Clone().map_err(|e| e.to_string())?;
+        before
+            .MoveEndpointByRange(
+                TextPatternRangeEndpoint_End,
+                &selection,
+                TextPatternRangeEndpoint_Start,
+            )
+            .map_err(|e| e.to_string())?;
+        let after = document.Clone().map_err(|e| e.to_string())?;
+        after
+            .MoveEndpointByRange(
+                TextPatternRangeEndpoint_Start,
+                &selection,
+                TextPatternRangeEndpoint_End,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let prefix = before.GetText(-1).map_err(|e| e.to_string())?.to_string();
+        let suffix = after.GetText(-1).map_err(|e| e.to_string())?.to_string();
+
+        let new_value = format!("{}{}{}", prefix, text, suffix);
+        value
+            .SetValue(&BSTR::from(new_value))
+            .map_err(|e| format!("Failed to set value: {}", e))?;
+
+        // Place the caret right after the inserted text
+        if let Ok(caret) = text_pattern.DocumentRange() {
+            let offset = (prefix.chars().count() + text.chars().count()) as i32;
+            let _ = caret.MoveEndpointByRange(
+                TextPatternRangeEndpoint_End,
+                &caret,
+                TextPatternRangeEndpoint_Start,
+            );
+            let _ =
+                caret.MoveEndpointByUnit(TextPatternRangeEndpoint_End, TextUnit_Character, offset);
+            let _ = caret.MoveEndpointByUnit(
+                TextPatternRangeEndpoint_Start,
+                TextUnit_Character,
+                offset,
+            );
+            let _ = caret.Select();
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn insert_text(_text: &str) -> Result<(), String> {
+    Err("UI Automation is only available on Windows".to_string())
+}
+
+/// What the caret watcher's thread is told
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+enum CaretEvent {
+    FocusChanged,
+    SelectionChanged,
+    Stop,
+}
+
+/// Follows the caret through UI Automation focus and text-selection events
+/// until dropped
+pub struct CaretWatch(std::sync::mpsc::Sender<CaretEvent>);
+
+impl Drop for CaretWatch {
+    fn drop(&mut self) {
+        let _ = self.0.send(CaretEvent::Stop);
+    }
+}
+
+#[cfg(target_os = "windows")]
+#[windows::core::implement(
+    windows::Win32::UI::Accessibility::IUIAutomationFocusChangedEventHandler,
+    windows::Win32::UI::Accessibility::IUIAutomationEventHandler
+)]
+struct CaretEventHandler(std::sync::mpsc::Sender<CaretEvent>);
+
+#[cfg(target_os = "windows")]
+impl windows::Win32::UI::Accessibility::IUIAutomationFocusChangedEventHandler_Impl
+    for CaretEventHandler
+{
+    fn HandleFocusChangedEvent(
+        &self,
+        _sender: Option<&windows::Win32::UI::Accessibility::IUIAutomationElement>,
+    ) -> windows::core::Result<()> {
+        let _ = self.0.send(CaretEvent::FocusChanged);
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl windows::Win32::UI::Accessibility::IUIAutomationEventHandler_Impl for CaretEventHandler {
+    fn HandleAutomationEvent(
+        &self,
+        _sender: Option<&windows::Win32::UI::Accessibility::IUIAutomationElement>,
+        _event_id: windows::Win32::UI::Accessibility::UIA_EVENT_ID,
+    ) -> windows::core::Result<()> {
+        let _ = self.0.send(CaretEvent::SelectionChanged);
+        Ok(())
+    }
+}
+
+/// Call `on_move` whenever focus or the caret moves, from a background
+/// thread, until the returned watch is dropped
+#[cfg(target_os = "windows")]
+pub fn watch_caret(on_move: impl Fn() + Send + 'static) -> Option<CaretWatch> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let handler_sender = sender.clone();
+    std::thread::Builder::new()
+        .name("caret-watch".to_string())
+        .spawn(move || {
+            if let Err(e) = run_caret_watch(handler_sender, receiver, on_move) {
+                log!("Caret watch stopped: {}", e);
+            }
+        })
+        .ok()?;
+    Some(CaretWatch(sender))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn watch_caret(_on_move: impl Fn() + Send + 'static) -> Option<CaretWatch> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+fn run_caret_watch(
+    sender: std::sync::mpsc::Sender<CaretEvent>,
+    receiver: std::sync::mpsc::Receiver<CaretEvent>,
+    on_move: impl Fn(),
+) -> Result<(), String> {
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CoUninitialize, CLSCTX_INPROC_SERVER,
+        COINIT_MULTITHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{
+        CUIAutomation, IUIAutomation, IUIAutomationCacheRequest, IUIAutomationElement,
+        IUIAutomationEventHandler, IUIAutomationFocusChangedEventHandler, TreeScope_Element,
+        UIA_Text_TextSelectionChangedEventId,
+    };
+
+    unsafe {
+        let initialized = CoInitializeEx(None, COINIT_MULTITHREADED).is_ok();
+        let result = (|| {
+            let automation: IUIAutomation =
+                CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER)
+                    .map_err(|e| format!("Failed to create UI Automation: {}", e))?;
+            let focus_handler: IUIAutomationFocusChangedEventHandler =
+                CaretEventHandler(sender.clone()).into();
+            let selection_handler: IUIAutomationEventHandler = CaretEventHandler(sender).into();
+            automation
+                .AddFocusChangedEventHandler(None::<&IUIAutomationCacheRequest>, &focus_handler)
+                .map_err(|e| format!("Failed to watch focus: {}", e))?;
+
+            // Selection events are only raised to handlers on the element
+            // itself, so follow focus to the control being typed in
+            let watch_selection = |element: &IUIAutomationElement| {
+                let _ = automation.AddAutomationEventHandler(
+                    UIA_Text_TextSelectionChangedEventId,
+                    element,
+                    TreeScope_Element,
+                    None::<&IUIAutomationCacheRequest>,
+                    &selection_handler,
+                );
+            };
+            let mut watched = automation.GetFocusedElement().ok();
+            if let Some(element) = &watched {
+                watch_selection(element);
+            }
+
+            while let Ok(event) = receiver.recv() {
+                let mut focus_changed = matches!(event, CaretEvent::FocusChanged);
+                let mut stop = matches!(event, CaretEvent::Stop);
+                // Handle a burst of events with a single move
+                while let Ok(event) = receiver.try_recv() {
+                    focus_changed |= matches!(event, CaretEvent::FocusChanged);
+                    stop |= matches!(event, CaretEvent::Stop);
+                }
+                if stop {
+                    break;
+                }
+                if focus_changed {
+                    if let Some(element) = watched.take() {
+                        let _ = automation.RemoveAutomationEventHandler(
+                            UIA_Text_TextSelectionChangedEventId,
+                            &element,
+                            &selection_handler,
+                        );
+                    }
+                    watched = automation.GetFocusedElement().ok();
+                    if let Some(element) = &watched {
+                        watch_selection(element);
+                    }
+                }
+                on_move();
+            }
+
+            let _ = automation.RemoveAllEventHandlers();
+            Ok(())
+        })();
+        if initialized {
+            CoUninitialize();
+        }
+        result
+    }
+}
@@ -0,0 +1,97 @@
+//! Checking GitHub releases for a newer version and installing it from the
+//! tray
+
+use crate::AppState;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// Wait after startup before the first check, so it doesn't slow launch
+const FIRST_CHECK_DELAY_SECONDS: u64 = 60;
+/// Time between checks while the app keeps running
+const CHECK_INTERVAL_SECONDS: u64 = 6 * 60 * 60;
+
+/// An update found by the last check, waiting for the user to install it
+pub struct PendingUpdate(pub std::sync::Mutex<Option<Update>>);
+
+/// Whether this build has the public key releases are signed with. Without
+/// it every update would fail verification, so none are looked for.
+pub fn signing_configured(app: &AppHandle) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|key| key.as_str())
+        .is_some_and(|key| !key.trim().is_empty())
+}
+
+/// Look for a newer release, remembering it and relabelling the tray item
+/// when there is one. Returns the new version.
+pub async fn check(app: &AppHandle) -> Result<Option<String>, String> {
+    if !signing_configured(app) {
+        return Err("This build isn't set up to receive updates".to_string());
+    }
+    let update = app
+        .updater()
+        .map_err(|e| e.to_string())?
+        .check()
+        .await
+        .map_err(|e| e.to_string())?;
+    let version = update.as_ref().map(|update| update.version.clone());
+    *app.state::<PendingUpdate>().0.lock().unwrap() = update;
+    crate::refresh_update_menu(app);
+    Ok(version)
+}
+
+/// Check now and then while update checks are switched on, telling the user
+/// once about each new version
+pub fn watch(app: AppHandle) {
+    if !signing_configured(&app) {
+        log!("No updater public key configured; not checking for updates");
+        return;
+    }
+    tauri::async_runtime::spawn(async move {
+        let mut announced: Option<String> = None;
+        tokio::time::sleep(std::time::Duration::from_secs(FIRST_CHECK_DELAY_SECONDS)).await;
+        loop {
+            let enabled = app
+                .state::<AppState>()
+                .settings
+                .lock()
+                .unwrap()
+                .check_for_updates;
+            if enabled {
+                match check(&app).await {
+                    Ok(Some(version)) if announced.as_ref() != Some(&version) => {
+                        crate::show_notice(
+                            &app,
+                            &format!(
+                                "Version {} is available. Install it from the tray.",
+                                version
+                            ),
+                        );
+                        announced = Some(version);
+                    }
+                    Ok(_) => {}
+                    Err(e) => log!("Update check failed: {}", e),
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(CHECK_INTERVAL_SECONDS)).await;
+        }
+    });
+}
+
+/// Download and install the pending update, then restart into it
+pub async fn install(app: AppHandle) -> Result<(), String> {
+    let Some(update) = app.state::<PendingUpdate>().0.lock().unwrap().take() else {
+        return Err("No update to install".to_string());
+    };
+    crate::show_notice(&app, &format!("Installing version {}...", update.version));
+    if let Err(e) = update.download_and_install(|_, _| {}, || {}).await {
+        // Keep it on offer so the user can try again
+        *app.state::<PendingUpdate>().0.lock().unwrap() = Some(update);
+        crate::refresh_update_menu(&app);
+        return Err(e.to_string());
+    }
+    app.restart();
+}
@@ -0,0 +1,115 @@
+//! Removing the app's data from disk, for users cleaning up a shared machine
+
+use std::io::Write;
+use std::path::Path;
+
+/// Overwrite a file with zeros before deleting it, so its contents don't
+/// linger in free disk space. Best effort: SSD wear levelling and filesystem
+/// snapshots can still hold old copies. A link is removed without touching
+/// what it points to.
+pub fn shred_file(path: &Path) -> std::io::Result<()> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    if metadata.file_type().is_symlink() {
+        return remove_link(path);
+    }
+    let len = metadata.len();
+    {
+        let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+        let zeros = vec![0u8; 64 * 1024];
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = remaining.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..n])?;
+            remaining -= n as u64;
+        }
+        file.sync_all()?;
+    }
+    std::fs::remove_file(path)
+}
+
+/// Remove a symlink or junction itself. Links to directories need
+/// `remove_dir` on Windows.
+fn remove_link(path: &Path) -> std::io::Result<()> {
+    std::fs::remove_file(path).or_else(|_| std::fs::remove_dir(path))
+}
+
+/// Shred every file under `dir` that `keep` doesn't accept, removing
+/// directories left empty. Links are removed, never followed, so nothing
+/// outside `dir` is touched. Returns a message for each file that couldn't
+/// be removed.
+pub fn shred_dir(dir: &Path, keep: &dyn Fn(&Path) -> bool) -> Vec<String> {
+    let mut errors = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return errors;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let result = if file_type.is_symlink() {
+            if keep(&path) {
+                continue;
+            }
+            remove_link(&path)
+        } else if file_type.is_dir() {
+            errors.extend(shred_dir(&path, keep));
+            let _ = std::fs::remove_dir(&path);
+            continue;
+        } else if keep(&path) {
+            continue;
+        } else {
+            shred_file(&path)
+        };
+        if let Err(e) = result {
+            errors.push(format!("{}: {}", path.display(), e));
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shreds_everything_but_kept_files() {
+        let dir = std::env::temp_dir().join(format!("whisper-wipe-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("recordings")).unwrap();
+        std::fs::write(dir.join("history.json.bak"), "secret").unwrap();
+        std::fs::write(dir.join("recordings").join("1.wav"), "audio").unwrap();
+        std::fs::write(dir.join("settings.json"), "{}").unwrap();
+
+        let errors = shred_dir(&dir, &|path| path.ends_with("settings.json"));
+        assert!(errors.is_empty());
+        assert!(dir.join("settings.json").exists());
+        assert!(!dir.join("history.json.bak").exists());
+        assert!(!dir.join("recordings").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn removes_links_without_touching_their_targets() {
+        let root = std::env::temp_dir().join(format!("whisper-wipe-links-{}", std::process::id()));
+        let dir = root.join("data");
+        let outside = root.join("outside");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("notes.txt"), "keep me").unwrap();
+        std::os::unix::fs::symlink(outside.join("notes.txt"), dir.join("file-link")).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("dir-link")).unwrap();
+
+        let errors = shred_dir(&dir, &|_| false);
+        assert!(errors.is_empty());
+        assert!(std::fs::symlink_metadata(dir.join("file-link")).is_err());
+        assert!(std::fs::symlink_metadata(dir.join("dir-link")).is_err());
+        assert_eq!(
+            std::fs::read_to_string(outside.join("notes.txt")).unwrap(),
+            "keep me"
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
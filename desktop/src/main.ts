@@ -2,7 +2,14 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import "./styles.css";
 
-type AppState = "idle" | "recording" | "processing" | "success" | "error";
+type AppState =
+  | "idle"
+  | "recording"
+  | "processing"
+  | "review"
+  | "success"
+  | "error"
+  | "api-problem";
 
 const win = window as Window & {
   __TAURI__?: unknown;
@@ -14,6 +21,11 @@ const tauriAvailable =
 const previewMode =
   !tauriAvailable || new URLSearchParams(window.location.search).has("preview");
 
+// Chords that answer a review, keyed by the action they trigger
+type ReviewHotkeys = { paste: string; copy_only: string; discard: string };
+type ReviewPrompt = { text: string; hotkeys: ReviewHotkeys };
+let reviewHotkeys: ReviewHotkeys = { paste: "", copy_only: "", discard: "" };
+
 // Create the popup UI
 function createPopupUI(): HTMLElement {
   const popup = document.createElement("div");
@@ -142,6 +154,47 @@ function createErrorUI(message: string): HTMLElement {
   return popup;
 }
 
+// Create API problem UI: an error that opens the Transcription settings
+// when clicked, since only a new key or plan fixes it
+function createApiProblemUI(message: string): HTMLElement {
+  const popup = createErrorUI(message);
+  popup.classList.add("actionable");
+  popup.addEventListener("click", () => {
+    if (!tauriAvailable || previewMode) return;
+    void invoke("open_settings", { tab: "api" });
+    void invoke("hide_popup");
+  });
+  return popup;
+}
+
+// Create review UI: the transcript with paste / copy / discard actions
+function createReviewUI(text: string): HTMLElement {
+  const popup = document.createElement("div");
+  popup.className = "popup review";
+  popup.innerHTML = `
+    <div class="review-text"></div>
+    <div class="review-actions">
+        <button type="button" data-action="paste">Paste</button>
+        <button type="button" data-action="copy_only">Copy</button>
+        <button type="button" data-action="discard">Discard</button>
+    </div>
+  `;
+  popup.querySelector(".review-text")!.textContent = text;
+  popup.querySelectorAll<HTMLButtonElement>("button").forEach((button) => {
+    const hotkey = reviewHotkeys[button.dataset.action as keyof ReviewHotkeys];
+    if (hotkey) {
+      const kbd = document.createElement("kbd");
+      kbd.textContent = hotkey;
+      button.append(" ", kbd);
+    }
+    button.addEventListener("click", () => {
+      if (!tauriAvailable || previewMode) return;
+      void invoke("resolve_review", { action: button.dataset.action });
+    });
+  });
+  return popup;
+}
+
 // Update the app UI based on state
 function updateUI(state: AppState, data?: string) {
   const app = document.getElementById("app")!;
@@ -157,6 +210,10 @@ function updateUI(state: AppState, data?: string) {
       stopVisualizer();
       app.appendChild(createProcessingUI());
       break;
+    case "review":
+      stopVisualizer();
+      app.appendChild(createReviewUI(data || ""));
+      break;
     case "success":
       stopVisualizer();
       app.appendChild(createSuccessUI());
@@ -177,6 +234,16 @@ function updateUI(state: AppState, data?: string) {
         }, 2000);
       }
       break;
+    case "api-problem":
+      stopVisualizer();
+      app.appendChild(createApiProblemUI(data || "API error"));
+      // Left up longer than other errors, to give time to click it
+      if (tauriAvailable && !previewMode) {
+        setTimeout(() => {
+          void invoke("hide_popup");
+        }, 6000);
+      }
+      break;
     case "idle":
     default:
       stopVisualizer();
@@ -253,6 +320,15 @@ async function init() {
     audioData.shift();
   });
 
+  // Popup opacity comes from settings and follows changes to them
+  const applyOpacity = (settings: { popup?: { opacity?: number } }) => {
+    document.body.style.opacity = String(settings.popup?.opacity ?? 1);
+  };
+  invoke<{ popup?: { opacity?: number } }>("get_settings").then(applyOpacity);
+  listen<{ popup?: { opacity?: number } }>("settings-changed", (event) =>
+    applyOpacity(event.payload),
+  );
+
   listen("show-idle", () => {
     updateUI("idle");
   });
@@ -271,6 +347,11 @@ async function init() {
     updateUI("processing");
   });
 
+  listen<ReviewPrompt>("show-review", (event) => {
+    reviewHotkeys = event.payload.hotkeys;
+    updateUI("review", event.payload.text);
+  });
+
   listen<string>("show-success", (event) => {
     updateUI("success", event.payload);
   });
@@ -279,6 +360,10 @@ async function init() {
     updateUI("error", event.payload);
   });
 
+  listen<string>("show-api-problem", (event) => {
+    updateUI("api-problem", event.payload);
+  });
+
   // Prevent context menu
   document.addEventListener("contextmenu", (e) => e.preventDefault());
 
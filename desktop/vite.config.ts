@@ -1,3 +1,4 @@
+import { resolve } from 'path';
 import { defineConfig } from 'vite';
 
 export default defineConfig({
@@ -11,5 +12,17 @@ export default defineConfig({
         target: 'esnext',
         minify: !process.env.TAURI_DEBUG ? 'esbuild' : false,
         sourcemap: !!process.env.TAURI_DEBUG,
+        rollupOptions: {
+            input: {
+                main: resolve(__dirname, 'index.html'),
+                settings: resolve(__dirname, 'settings.html'),
+                review: resolve(__dirname, 'review.html'),
+                history: resolve(__dirname, 'history.html'),
+                onboarding: resolve(__dirname, 'onboarding.html'),
+                scratch: resolve(__dirname, 'scratch.html'),
+                captions: resolve(__dirname, 'captions.html'),
+                transcribe: resolve(__dirname, 'transcribe.html'),
+            },
+        },
     },
 });
@@ -9,16 +9,96 @@ interface Env {
     API_KEY: string;
 }
 
+// Older clients send JSON; current ones send the WAV itself as the body, with
+// these options in the query string (?language=de&timestamps=1&keyword=Tauri)
 interface TranscribeRequest {
     audio: string; // Base64 encoded audio data
+    language?: string; // Optional language code, e.g. "de"
+    timestamps?: boolean; // Also return timed segments, for subtitles
+}
+
+interface AudioRequest {
+    audio: Uint8Array;
+    language?: string;
+    timestamps: boolean;
+    keywords: string[]; // Terms to favor, e.g. names and jargon
+}
+
+interface TranslateRequest {
+    text: string;
+    target: string; // Language code to translate into, e.g. "fr"
+    source?: string; // Language of `text`, when the client knows it
+}
+
+interface Segment {
+    start: number; // Seconds from the start of the audio
+    end: number;
+    text: string;
 }
 
 interface TranscribeResponse {
     success: boolean;
     text?: string;
+    segments?: Segment[];
     error?: string;
 }
 
+// Most words in a subtitle cue built from word timings
+const WORDS_PER_SEGMENT = 10;
+
+// Timed segments from either model: the turbo model returns segments, the
+// base model only word timings, which are grouped into segments
+function toSegments(result: any): Segment[] {
+    if (Array.isArray(result.segments)) {
+        return result.segments.map((segment: any) => ({
+            start: segment.start,
+            end: segment.end,
+            text: String(segment.text).trim(),
+        }));
+    }
+    const words: { word: string; start: number; end: number }[] = result.words || [];
+    const segments: Segment[] = [];
+    for (let i = 0; i < words.length; i += WORDS_PER_SEGMENT) {
+        const group = words.slice(i, i + WORDS_PER_SEGMENT);
+        segments.push({
+            start: group[0].start,
+            end: group[group.length - 1].end,
+            text: group.map((w) => w.word.trim()).join(' '),
+        });
+    }
+    return segments;
+}
+
+// Read either request format
+async function readRequest(request: Request, url: URL): Promise<AudioRequest> {
+    const contentType = request.headers.get('Content-Type') || '';
+    if (contentType.startsWith('application/json')) {
+        const body = await request.json() as TranscribeRequest;
+        return {
+            audio: Uint8Array.from(atob(body.audio || ''), c => c.charCodeAt(0)),
+            language: body.language,
+            timestamps: !!body.timestamps,
+            keywords: [],
+        };
+    }
+    return {
+        audio: new Uint8Array(await request.arrayBuffer()),
+        language: url.searchParams.get('language') || undefined,
+        timestamps: url.searchParams.get('timestamps') === '1',
+        keywords: url.searchParams.getAll('keyword').filter((k) => k.trim()),
+    };
+}
+
+// Base64 for the turbo model, which only takes audio as a string. Built in
+// slices, as spreading a whole recording into one call overflows the stack.
+function toBase64(bytes: Uint8Array): string {
+    let binary = '';
+    for (let i = 0; i < bytes.length; i += 0x8000) {
+        binary += String.fromCharCode(...bytes.subarray(i, i + 0x8000));
+    }
+    return btoa(binary);
+}
+
 export default {
     async fetch(request: Request, env: Env): Promise<Response> {
         // CORS headers for the Tauri app
@@ -33,9 +113,10 @@ export default {
             return new Response(null, { headers: corsHeaders });
         }
 
-        // Only accept POST requests to /transcribe
+        // Only accept POST requests to /transcribe and /translate
         const url = new URL(request.url);
-        if (request.method !== 'POST' || url.pathname !== '/transcribe') {
+        const routes = ['/transcribe', '/translate'];
+        if (request.method !== 'POST' || !routes.includes(url.pathname)) {
             return new Response(
                 JSON.stringify({ success: false, error: 'Not found' }),
                 {
@@ -58,10 +139,34 @@ export default {
         }
 
         try {
-            // Parse the request body
-            const body = await request.json() as TranscribeRequest;
+            if (url.pathname === '/translate') {
+                const body = await request.json() as TranslateRequest;
+                if (!body.text || !body.target) {
+                    return new Response(
+                        JSON.stringify({ success: false, error: 'No text or target language provided' }),
+                        {
+                            status: 400,
+                            headers: { ...corsHeaders, 'Content-Type': 'application/json' }
+                        }
+                    );
+                }
+                const result = await env.AI.run('@cf/meta/m2m100-1.2b', {
+                    text: body.text,
+                    target_lang: body.target,
+                    ...(body.source ? { source_lang: body.source } : {}),
+                });
+                const response: TranscribeResponse = {
+                    success: true,
+                    text: result.translated_text || '',
+                };
+                return new Response(JSON.stringify(response), {
+                    headers: { ...corsHeaders, 'Content-Type': 'application/json' },
+                });
+            }
 
-            if (!body.audio) {
+            const body = await readRequest(request, url);
+
+            if (body.audio.length === 0) {
                 return new Response(
                     JSON.stringify({ success: false, error: 'No audio data provided' }),
                     {
@@ -71,18 +176,29 @@ export default {
                 );
             }
 
-            // Decode base64 audio to Uint8Array
-            const audioBytes = Uint8Array.from(atob(body.audio), c => c.charCodeAt(0));
-
-            // Call Whisper AI model
-            const result = await env.AI.run('@cf/openai/whisper', {
-                audio: [...audioBytes],
-            });
+            // Call Whisper AI model. Only the turbo model accepts a language
+            // hint or a prompt, so it's used when the client asks for either.
+            // Whisper has no keyword list; terms in the prompt are favored.
+            const prompt = body.keywords.length
+                ? `Vocabulary: ${body.keywords.join(', ')}.`
+                : undefined;
+            const result = body.language || prompt
+                ? await env.AI.run('@cf/openai/whisper-large-v3-turbo', {
+                    audio: toBase64(body.audio),
+                    ...(body.language ? { language: body.language } : {}),
+                    ...(prompt ? { initial_prompt: prompt } : {}),
+                })
+                : await env.AI.run('@cf/openai/whisper', {
+                    audio: [...body.audio],
+                });
 
             const response: TranscribeResponse = {
                 success: true,
                 text: result.text || '',
             };
+            if (body.timestamps) {
+                response.segments = toSegments(result);
+            }
 
             return new Response(JSON.stringify(response), {
                 headers: { ...corsHeaders, 'Content-Type': 'application/json' },